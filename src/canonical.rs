@@ -0,0 +1,267 @@
+//! Canonical ordering of the atoms of a molecular graph.
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use purr::feature::AtomKind;
+use purr::feature::BondKind;
+use purr::feature::Configuration;
+use purr::feature::Rnum;
+use purr::graph::Bond;
+use purr::walk::Follower;
+
+use super::copy;
+use super::mass;
+use super::reference::odd_permutation;
+use super::reference::rank;
+use super::reference::Graph;
+use super::Atom;
+use super::Error;
+
+/// Get the order of a bond, with elided bonds between aromatic atoms
+/// counted as aromatic bonds.
+fn bond_order(infos: &[Atom], index: usize, bond: &Bond) -> u8 {
+    match bond.kind {
+        BondKind::Double => 2,
+        BondKind::Triple => 3,
+        BondKind::Quadruple => 4,
+        BondKind::Aromatic => 5,
+        BondKind::Elided if infos[index].aromatic && infos[bond.tid].aromatic => 5,
+        BondKind::Elided | BondKind::Single | BondKind::Up | BondKind::Down => 1,
+    }
+}
+
+/// Count the distinct labels.
+fn classes(labels: &[usize]) -> usize {
+    labels.iter().max().map_or(0, |&m| m + 1)
+}
+
+/// Compute the canonical rank of every atom of a graph.
+///
+/// Atoms are first labeled with their degree, element, aromaticity, charge
+/// and number of hydrogens, and the labels are refined with the labels of
+/// their neighbors and the order of the bonds to them until the number of
+/// distinct labels is stable. Atoms that cannot be distinguished this way
+/// are then told apart by favoring the first atom with the smallest tied
+/// label, and refining the labels again, until every label is distinct.
+/// The configuration of stereocenters is not used to rank atoms.
+fn ranks(graph: &Graph) -> Vec<usize> {
+    let atoms = &graph.atoms;
+    let infos = atoms
+        .iter()
+        .map(|atom| Atom::from_kind(&atom.kind))
+        .collect::<Vec<_>>();
+    let refine = |mut labels: Vec<usize>| loop {
+        let signatures = (0..atoms.len())
+            .map(|index| {
+                let mut neighbors = atoms[index]
+                    .bonds
+                    .iter()
+                    .map(|bond| (labels[bond.tid], bond_order(&infos, index, bond)))
+                    .collect::<Vec<_>>();
+                neighbors.sort_unstable();
+                (labels[index], neighbors)
+            })
+            .collect::<Vec<_>>();
+        let refined = rank(&signatures);
+        if classes(&refined) == classes(&labels) {
+            return refined;
+        }
+        labels = refined;
+    };
+
+    let initial = (0..atoms.len())
+        .map(|index| {
+            let info = &infos[index];
+            let hydrogens = mass::hydrogens(atoms, index);
+            let degree = atoms[index].bonds.len();
+            (degree, info.element, info.aromatic, info.charge, hydrogens)
+        })
+        .collect::<Vec<_>>();
+    let mut labels = refine(rank(&initial));
+    while classes(&labels) < atoms.len() {
+        let mut sorted = labels.clone();
+        sorted.sort_unstable();
+        let tied = sorted.windows(2).find(|w| w[0] == w[1]).unwrap()[0];
+        let first = labels.iter().position(|&label| label == tied).unwrap();
+        let split = labels
+            .iter()
+            .enumerate()
+            .map(|(index, &label)| (label, label == tied && index != first))
+            .collect::<Vec<_>>();
+        labels = refine(rank(&split));
+    }
+    labels
+}
+
+/// Write a graph with its atoms ordered by their canonical ranks.
+///
+/// Each connected component is written from its atom with the smallest
+/// rank, visiting the neighbors of every atom by increasing rank, so that
+/// the same molecule is always written the same way, whatever the order of
+/// its atoms in the graph. The configuration of stereocenters and the
+/// direction of bonds are updated for the new order of the atoms. Returns
+/// the index in the graph of every atom, in the order they were written.
+pub fn visit<F: Follower>(graph: &Graph, follower: &mut F) -> Result<Vec<usize>, Error> {
+    let atoms = &graph.atoms;
+    let ranks = ranks(graph);
+
+    // build a depth-first spanning forest, visiting neighbors by rank
+    let mut parent = vec![None; atoms.len()];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); atoms.len()];
+    let mut order = Vec::with_capacity(atoms.len());
+    let mut visited = vec![false; atoms.len()];
+    let mut roots = (0..atoms.len()).collect::<Vec<_>>();
+    roots.sort_by_key(|&index| ranks[index]);
+    for root in roots {
+        let mut todo: Vec<(usize, Option<usize>)> = vec![(root, None)];
+        while let Some((id, from)) = todo.pop() {
+            if visited[id] {
+                continue;
+            }
+            visited[id] = true;
+            order.push(id);
+            if let Some(from) = from {
+                parent[id] = Some(from);
+                children[from].push(id);
+            }
+            let mut neighbors = atoms[id].bonds.iter().map(|b| b.tid).collect::<Vec<_>>();
+            neighbors.sort_by_key(|&n| std::cmp::Reverse(ranks[n]));
+            todo.extend(neighbors.into_iter().map(|n| (n, Some(id))));
+        }
+    }
+
+    // every bond outside of the spanning forest closes a ring, written
+    // in the order the other atom appears in the SMILES string
+    let mut position = vec![0; atoms.len()];
+    for (i, &id) in order.iter().enumerate() {
+        position[id] = i;
+    }
+    let rings = (0..atoms.len())
+        .map(|id| {
+            let mut rings = atoms[id]
+                .bonds
+                .iter()
+                .filter(|b| parent[id] != Some(b.tid) && parent[b.tid] != Some(id))
+                .collect::<Vec<_>>();
+            rings.sort_by_key(|b| position[b.tid]);
+            rings
+        })
+        .collect::<Vec<_>>();
+
+    // count the atoms written on the main chain of each subtree, which
+    // is the depth to pop when the subtree is written as a branch
+    let mut chain = vec![1; atoms.len()];
+    for &id in order.iter().rev() {
+        if let Some(&last) = children[id].last() {
+            chain[id] += chain[last];
+        }
+    }
+
+    // the bonds were written from the atom with the smallest index
+    let oriented = |from: usize, to: usize, kind: &BondKind| {
+        if from < to {
+            kind.clone()
+        } else {
+            kind.reverse()
+        }
+    };
+
+    // walk the forest, writing every child but the last one in a branch
+    enum Step {
+        Visit(usize),
+        Pop(usize),
+    }
+    let mut open = HashMap::new();
+    let mut free = (1..100).collect::<BTreeSet<u16>>();
+    let mut todo = order
+        .iter()
+        .rev()
+        .filter(|&&id| parent[id].is_none())
+        .map(|&id| Step::Visit(id))
+        .collect::<Vec<_>>();
+    while let Some(step) = todo.pop() {
+        let id = match step {
+            Step::Pop(depth) => {
+                follower.pop(depth);
+                continue;
+            }
+            Step::Visit(id) => id,
+        };
+
+        // list the neighbors in the order they are written, with the
+        // implicit hydrogen right after the previous atom
+        let mut neighbors = Vec::new();
+        neighbors.extend(parent[id].map(Some));
+        if graph.neighbors[id].contains(&None) {
+            neighbors.push(None);
+        }
+        neighbors.extend(rings[id].iter().map(|b| Some(b.tid)));
+        neighbors.extend(children[id].iter().map(|&c| Some(c)));
+        let positions = neighbors
+            .iter()
+            .map(|n| graph.neighbors[id].iter().position(|m| m == n).unwrap())
+            .collect::<Vec<_>>();
+        let kind = match copy::atom_kind(&atoms[id].kind) {
+            AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration,
+                hcount,
+                charge,
+                map,
+            } => AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration: match configuration {
+                    Some(Configuration::TH1) if odd_permutation(&positions) => {
+                        Some(Configuration::TH2)
+                    }
+                    Some(Configuration::TH2) if odd_permutation(&positions) => {
+                        Some(Configuration::TH1)
+                    }
+                    other => other,
+                },
+                hcount,
+                charge,
+                map,
+            },
+            other => other,
+        };
+
+        match parent[id] {
+            None => follower.root(kind),
+            Some(from) => {
+                let bond = atoms[id].bonds.iter().find(|b| b.tid == from).unwrap();
+                follower.extend(oriented(from, id, &bond.kind), kind);
+            }
+        }
+
+        // close the rings opened by atoms already written, then open the
+        // others, so that a ring number is never reused on the same atom
+        let mut closed = Vec::new();
+        for bond in rings[id].iter() {
+            if position[bond.tid] < position[id] {
+                let num = open.remove(&(bond.tid, id)).unwrap();
+                let rnum = Rnum::try_from(num).map_err(|_| Error::TooManyCrossLinks)?;
+                follower.join(BondKind::Elided, rnum);
+                closed.push(num);
+            } else {
+                let num = free.pop_first().ok_or(Error::TooManyCrossLinks)?;
+                let rnum = Rnum::try_from(num).map_err(|_| Error::TooManyCrossLinks)?;
+                follower.join(oriented(id, bond.tid, &bond.kind), rnum);
+                open.insert((id, bond.tid), num);
+            }
+        }
+        free.extend(closed);
+
+        for (i, &child) in children[id].iter().enumerate().rev() {
+            if i + 1 < children[id].len() {
+                todo.push(Step::Pop(chain[child]));
+            }
+            todo.push(Step::Visit(child));
+        }
+    }
+
+    Ok(order)
+}
@@ -0,0 +1,236 @@
+//! Multi-chain complexes bridged by interchain cross-links.
+
+use std::collections::HashMap;
+
+use purr::feature::Rnum;
+use purr::graph::Atom;
+use purr::walk::Follower;
+
+use super::linker;
+use super::mass;
+use super::writer;
+use super::AminoAcid;
+use super::CrossLink;
+use super::Error;
+use super::Protein;
+
+/// A complex of several protein chains bridged by cross-links.
+///
+/// The chains are written as disconnected components of the molecular
+/// graph, only bonded to each other through the cross-links between them,
+/// such as the interchain disulfide bonds of insulin.
+///
+/// # Example
+/// ```rust
+/// use proteinogenic::AminoAcid::*;
+/// use proteinogenic::Complex;
+/// use proteinogenic::CrossLink;
+/// use proteinogenic::Protein;
+///
+/// let chains = [Protein::new(vec![Gly, Cys]), Protein::new(vec![Cys, Gly])];
+/// let mut complex = Complex::new(chains);
+/// complex.cross_link(CrossLink::Cystine(2, 1), (0, 1)).unwrap();
+/// assert_eq!(
+///     complex.smiles().unwrap(),
+///     "NCC(=O)N[C@@H](CS3)C(=O)-O.N[C@@H](CS3)C(=O)NCC(=O)-O"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Complex<S> {
+    chains: Vec<Protein<S>>,
+    cross_links: Vec<((usize, usize), CrossLink)>,
+}
+
+impl<S> Complex<S> {
+    /// Create a new complex from its chains.
+    pub fn new<I>(chains: I) -> Self
+    where
+        I: IntoIterator<Item = Protein<S>>,
+    {
+        Self {
+            chains: chains.into_iter().collect(),
+            cross_links: Vec::new(),
+        }
+    }
+
+    /// Get the chains of the complex, in order.
+    pub fn chains(&self) -> &[Protein<S>] {
+        &self.chains
+    }
+
+    /// Check whether a residue of a chain is involved in a cross-link.
+    fn is_cross_linked(&self, chain: usize, index: u16) -> bool {
+        let interchain = self.cross_links.iter().any(|&((ci, cj), cross_link)| {
+            let (i, j) = cross_link.residues();
+            (ci == chain && i == index) || (cj == chain && j == index)
+        });
+        interchain || self.chains[chain].cross_links.contains_key(&index)
+    }
+
+    /// Check whether the complex already contains the given cross-link.
+    fn contains(&self, cross_link: CrossLink, chains: (usize, usize)) -> bool {
+        let (i, j) = cross_link.residues();
+        if chains.0 == chains.1 {
+            let canonical = cross_link.canonical();
+            let (k, _) = canonical.residues();
+            let existing = self.chains[chains.0].cross_links.get(&k);
+            return existing.map(|&(_, c)| c) == Some(canonical);
+        }
+        self.cross_links.iter().any(|&(c, other)| {
+            (c == chains && other == cross_link)
+                || (cross_link.is_symmetric()
+                    && c == (chains.1, chains.0)
+                    && other == cross_link.with_residues(j, i))
+        })
+    }
+}
+
+impl<S> Complex<S>
+where
+    S: IntoIterator<Item = AminoAcid> + Clone,
+{
+    /// Add a cross-link between residues of two chains of the complex.
+    ///
+    /// The first residue of the cross-link is found in the chain at index
+    /// `chains.0`, and the second one in the chain at index `chains.1`,
+    /// with chains numbered from 0 in the order given to `Complex::new`.
+    /// A cross-link within a single chain is added to the chain itself.
+    /// Each residue can be involved in at most one cross-link, otherwise
+    /// `Error::DuplicateChainCrossLink` is returned with the chain and the
+    /// index of the residue. Adding a cross-link that is already present
+    /// is a no-op. On error, the cross-links previously added are left
+    /// unchanged.
+    pub fn cross_link(
+        &mut self,
+        cross_link: CrossLink,
+        chains: (usize, usize),
+    ) -> Result<&mut Self, Error> {
+        cross_link.check_linker()?;
+        let (i, j) = cross_link.residues();
+        let endpoints = [(chains.0, i), (chains.1, j)];
+        for (chain, index) in endpoints {
+            let length = self
+                .chains
+                .get(chain)
+                .map_or(0, |protein| protein.sequence.clone().into_iter().count());
+            if index == 0 || usize::from(index) > length {
+                return Err(Error::InvalidChainResidue(chain, index));
+            }
+        }
+        if self.contains(cross_link, chains) {
+            return Ok(self);
+        }
+        for (chain, index) in endpoints {
+            if self.is_cross_linked(chain, index) {
+                return Err(Error::DuplicateChainCrossLink(chain, index));
+            }
+        }
+
+        if chains.0 == chains.1 {
+            self.chains[chains.0].cross_link(cross_link)?;
+        } else {
+            self.cross_links.push((chains, cross_link));
+        }
+        Ok(self)
+    }
+
+    /// Visit each atom and bond of the complex using the given follower.
+    ///
+    /// Every chain is visited in order, each starting a new component.
+    pub fn visit<F: Follower>(self, follower: &mut F) -> Result<(), Error> {
+        // number the cross-links between chains after the ring closures
+        // of every chain, since they stay open from one chain to another
+        let mut first = 3;
+        let mut rings = 0;
+        for chain in self.chains.iter() {
+            let cross_links = chain.ring_closures()?;
+            let cyclizations = chain.cyclization_closures(&cross_links)?;
+            let linkers = chain.linker_closures(&cross_links, &cyclizations)?;
+            first = first.max(linkers.end);
+            rings = rings.max(linkers.len() as u16);
+        }
+
+        // number the rings of every linker after the interchain cross-links,
+        // since a linker between chains is written while they are all open
+        let linker_rnum = first + self.cross_links.len() as u16;
+        for (_, cross_link) in self.cross_links.iter() {
+            if let CrossLink::Custom { linker, .. } = cross_link {
+                rings = rings.max(linker::ring_count(linker).unwrap_or(0));
+            }
+        }
+        if linker_rnum + rings > 100 {
+            return Err(Error::TooManyCrossLinks);
+        }
+
+        // the residues of an interchain cross-link are in different chains,
+        // so they can only be checked together here
+        let residue = |chain: usize, index: u16| {
+            let sequence = self.chains[chain].sequence.clone();
+            sequence.into_iter().nth(usize::from(index) - 1)
+        };
+        for &((chain_i, chain_j), cross_link) in self.cross_links.iter() {
+            let (i, j) = cross_link.residues();
+            if let (Some(aa_i), Some(aa_j)) = (residue(chain_i, i), residue(chain_j, j)) {
+                cross_link.check_pair(aa_i, aa_j)?;
+            }
+        }
+
+        // the partner of each residue is found in another chain, so it is
+        // given the index 0, which is never used by a residue
+        let mut interchain = vec![HashMap::new(); self.chains.len()];
+        for (k, &((chain_i, chain_j), cross_link)) in self.cross_links.iter().enumerate() {
+            let rnum = Rnum::try_from(first + k as u16).map_err(|_| Error::TooManyCrossLinks)?;
+            let (i, j) = cross_link.residues();
+            interchain[chain_i].insert(i, (rnum.clone(), cross_link.with_residues(i, 0)));
+            interchain[chain_j].insert(j, (rnum, cross_link.with_residues(0, j)));
+        }
+
+        for (chain, interchain) in self.chains.into_iter().zip(interchain.iter()) {
+            chain.visit_with(follower, interchain, linker_rnum)?;
+        }
+        Ok(())
+    }
+
+    /// Generate a SMILES string for the complex.
+    pub fn smiles(self) -> Result<String, Error> {
+        let mut writer = writer::Writer::new();
+        self.visit(&mut writer)?;
+        Ok(writer.write())
+    }
+
+    /// Compute the monoisotopic mass of the complex.
+    ///
+    /// The hydrogens lost in the formation of the cross-links, both within
+    /// and between chains, are accounted for.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Complex;
+    /// use proteinogenic::CrossLink;
+    /// use proteinogenic::Protein;
+    ///
+    /// let chains = [Protein::new(vec![Cys]), Protein::new(vec![Cys])];
+    /// let mut complex = Complex::new(chains);
+    /// complex.cross_link(CrossLink::Cystine(1, 1), (0, 1)).unwrap();
+    /// let mass = complex.monoisotopic_mass().unwrap();
+    /// assert!((mass - 240.023849).abs() < 1e-6);
+    /// ```
+    pub fn monoisotopic_mass(&self) -> Result<f64, Error> {
+        self.atoms().map(|atoms| mass::molecule_mass(&atoms))
+    }
+
+    /// Compute the molecular formula of the complex, in Hill notation.
+    pub fn molecular_formula(&self) -> Result<String, Error> {
+        self.atoms().map(|atoms| mass::molecular_formula(&atoms))
+    }
+
+    /// Build the molecular graph of the complex.
+    fn atoms(&self) -> Result<Vec<Atom>, Error> {
+        let mut builder = purr::graph::Builder::new();
+        self.clone().visit(&mut builder)?;
+        Ok(builder
+            .build()
+            .expect("complex walk should produce a valid graph"))
+    }
+}
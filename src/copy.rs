@@ -0,0 +1,239 @@
+//! Copies of the atom features of `purr`, which do not implement `Clone`.
+
+use purr::feature::Aliphatic;
+use purr::feature::Aromatic;
+use purr::feature::AtomKind;
+use purr::feature::BracketAromatic;
+use purr::feature::BracketSymbol;
+use purr::feature::Charge;
+use purr::feature::Element;
+use purr::feature::Number;
+use purr::feature::VirtualHydrogen;
+
+/// Copy an atom kind, field by field.
+pub fn atom_kind(kind: &AtomKind) -> AtomKind {
+    match kind {
+        AtomKind::Star => AtomKind::Star,
+        AtomKind::Aliphatic(aliphatic) => AtomKind::Aliphatic(self::aliphatic(aliphatic)),
+        AtomKind::Aromatic(aromatic) => AtomKind::Aromatic(self::aromatic(aromatic)),
+        AtomKind::Bracket {
+            isotope,
+            symbol,
+            configuration,
+            hcount,
+            charge,
+            map,
+        } => AtomKind::Bracket {
+            isotope: isotope.as_ref().map(number),
+            symbol: bracket_symbol(symbol),
+            configuration: configuration.clone(),
+            hcount: hcount.as_ref().map(virtual_hydrogen),
+            charge: charge.as_ref().map(self::charge),
+            map: map.as_ref().map(number),
+        },
+    }
+}
+
+/// Copy the symbol of a bracket atom.
+pub fn bracket_symbol(symbol: &BracketSymbol) -> BracketSymbol {
+    match symbol {
+        BracketSymbol::Star => BracketSymbol::Star,
+        BracketSymbol::Element(element) => BracketSymbol::Element(self::element(element)),
+        BracketSymbol::Aromatic(aromatic) => BracketSymbol::Aromatic(bracket_aromatic(aromatic)),
+    }
+}
+
+/// Copy an aliphatic symbol of the organic subset.
+pub fn aliphatic(aliphatic: &Aliphatic) -> Aliphatic {
+    match aliphatic {
+        Aliphatic::B => Aliphatic::B,
+        Aliphatic::C => Aliphatic::C,
+        Aliphatic::N => Aliphatic::N,
+        Aliphatic::O => Aliphatic::O,
+        Aliphatic::S => Aliphatic::S,
+        Aliphatic::P => Aliphatic::P,
+        Aliphatic::F => Aliphatic::F,
+        Aliphatic::Cl => Aliphatic::Cl,
+        Aliphatic::Br => Aliphatic::Br,
+        Aliphatic::I => Aliphatic::I,
+        Aliphatic::At => Aliphatic::At,
+        Aliphatic::Ts => Aliphatic::Ts,
+    }
+}
+
+/// Copy an aromatic symbol of the organic subset.
+pub fn aromatic(aromatic: &Aromatic) -> Aromatic {
+    match aromatic {
+        Aromatic::B => Aromatic::B,
+        Aromatic::C => Aromatic::C,
+        Aromatic::N => Aromatic::N,
+        Aromatic::O => Aromatic::O,
+        Aromatic::P => Aromatic::P,
+        Aromatic::S => Aromatic::S,
+    }
+}
+
+/// Copy an aromatic symbol of a bracket atom.
+pub fn bracket_aromatic(aromatic: &BracketAromatic) -> BracketAromatic {
+    match aromatic {
+        BracketAromatic::B => BracketAromatic::B,
+        BracketAromatic::C => BracketAromatic::C,
+        BracketAromatic::N => BracketAromatic::N,
+        BracketAromatic::O => BracketAromatic::O,
+        BracketAromatic::S => BracketAromatic::S,
+        BracketAromatic::P => BracketAromatic::P,
+        BracketAromatic::Se => BracketAromatic::Se,
+        BracketAromatic::As => BracketAromatic::As,
+    }
+}
+
+/// Copy an element.
+pub fn element(element: &Element) -> Element {
+    match element {
+        Element::H => Element::H,
+        Element::He => Element::He,
+        Element::Li => Element::Li,
+        Element::Be => Element::Be,
+        Element::B => Element::B,
+        Element::C => Element::C,
+        Element::N => Element::N,
+        Element::O => Element::O,
+        Element::F => Element::F,
+        Element::Ne => Element::Ne,
+        Element::Na => Element::Na,
+        Element::Mg => Element::Mg,
+        Element::Al => Element::Al,
+        Element::Si => Element::Si,
+        Element::P => Element::P,
+        Element::S => Element::S,
+        Element::Cl => Element::Cl,
+        Element::Ar => Element::Ar,
+        Element::K => Element::K,
+        Element::Ca => Element::Ca,
+        Element::Sc => Element::Sc,
+        Element::Ti => Element::Ti,
+        Element::V => Element::V,
+        Element::Cr => Element::Cr,
+        Element::Mn => Element::Mn,
+        Element::Fe => Element::Fe,
+        Element::Co => Element::Co,
+        Element::Ni => Element::Ni,
+        Element::Cu => Element::Cu,
+        Element::Zn => Element::Zn,
+        Element::Ga => Element::Ga,
+        Element::Ge => Element::Ge,
+        Element::As => Element::As,
+        Element::Se => Element::Se,
+        Element::Br => Element::Br,
+        Element::Kr => Element::Kr,
+        Element::Rb => Element::Rb,
+        Element::Sr => Element::Sr,
+        Element::Y => Element::Y,
+        Element::Zr => Element::Zr,
+        Element::Nb => Element::Nb,
+        Element::Mo => Element::Mo,
+        Element::Tc => Element::Tc,
+        Element::Ru => Element::Ru,
+        Element::Rh => Element::Rh,
+        Element::Pd => Element::Pd,
+        Element::Ag => Element::Ag,
+        Element::Cd => Element::Cd,
+        Element::In => Element::In,
+        Element::Sn => Element::Sn,
+        Element::Sb => Element::Sb,
+        Element::Te => Element::Te,
+        Element::I => Element::I,
+        Element::Xe => Element::Xe,
+        Element::Cs => Element::Cs,
+        Element::Ba => Element::Ba,
+        Element::La => Element::La,
+        Element::Ce => Element::Ce,
+        Element::Pr => Element::Pr,
+        Element::Nd => Element::Nd,
+        Element::Pm => Element::Pm,
+        Element::Sm => Element::Sm,
+        Element::Eu => Element::Eu,
+        Element::Gd => Element::Gd,
+        Element::Tb => Element::Tb,
+        Element::Dy => Element::Dy,
+        Element::Ho => Element::Ho,
+        Element::Er => Element::Er,
+        Element::Tm => Element::Tm,
+        Element::Yb => Element::Yb,
+        Element::Lu => Element::Lu,
+        Element::Hf => Element::Hf,
+        Element::Ta => Element::Ta,
+        Element::W => Element::W,
+        Element::Re => Element::Re,
+        Element::Os => Element::Os,
+        Element::Ir => Element::Ir,
+        Element::Pt => Element::Pt,
+        Element::Au => Element::Au,
+        Element::Hg => Element::Hg,
+        Element::Tl => Element::Tl,
+        Element::Pb => Element::Pb,
+        Element::Bi => Element::Bi,
+        Element::Po => Element::Po,
+        Element::At => Element::At,
+        Element::Rn => Element::Rn,
+        Element::Fr => Element::Fr,
+        Element::Ra => Element::Ra,
+        Element::Ac => Element::Ac,
+        Element::Th => Element::Th,
+        Element::Pa => Element::Pa,
+        Element::U => Element::U,
+        Element::Np => Element::Np,
+        Element::Pu => Element::Pu,
+        Element::Am => Element::Am,
+        Element::Cm => Element::Cm,
+        Element::Bk => Element::Bk,
+        Element::Cf => Element::Cf,
+        Element::Es => Element::Es,
+        Element::Fm => Element::Fm,
+        Element::Md => Element::Md,
+        Element::No => Element::No,
+        Element::Lr => Element::Lr,
+        Element::Rf => Element::Rf,
+        Element::Db => Element::Db,
+        Element::Sg => Element::Sg,
+        Element::Bh => Element::Bh,
+        Element::Hs => Element::Hs,
+        Element::Mt => Element::Mt,
+        Element::Ds => Element::Ds,
+        Element::Rg => Element::Rg,
+        Element::Cn => Element::Cn,
+        Element::Nh => Element::Nh,
+        Element::Fl => Element::Fl,
+        Element::Mc => Element::Mc,
+        Element::Lv => Element::Lv,
+        Element::Ts => Element::Ts,
+        Element::Og => Element::Og,
+    }
+}
+
+/// Copy the virtual hydrogen count of a bracket atom.
+pub fn virtual_hydrogen(hcount: &VirtualHydrogen) -> VirtualHydrogen {
+    match hcount {
+        VirtualHydrogen::H0 => VirtualHydrogen::H0,
+        VirtualHydrogen::H1 => VirtualHydrogen::H1,
+        VirtualHydrogen::H2 => VirtualHydrogen::H2,
+        VirtualHydrogen::H3 => VirtualHydrogen::H3,
+        VirtualHydrogen::H4 => VirtualHydrogen::H4,
+        VirtualHydrogen::H5 => VirtualHydrogen::H5,
+        VirtualHydrogen::H6 => VirtualHydrogen::H6,
+        VirtualHydrogen::H7 => VirtualHydrogen::H7,
+        VirtualHydrogen::H8 => VirtualHydrogen::H8,
+        VirtualHydrogen::H9 => VirtualHydrogen::H9,
+    }
+}
+
+/// Copy the charge of a bracket atom.
+pub fn charge(charge: &Charge) -> Charge {
+    let value: i8 = charge.into();
+    Charge::try_from(value).expect("charges can be rebuilt from their value")
+}
+
+/// Copy an isotope or atom map number.
+pub fn number(number: &Number) -> Number {
+    Number::try_from(u16::from(number)).expect("numbers can be rebuilt from their value")
+}
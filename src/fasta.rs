@@ -0,0 +1,103 @@
+//! Ingestion of protein sequences from FASTA files.
+
+use std::io::BufRead;
+
+use super::AminoAcid;
+use super::Error;
+use super::Protein;
+use super::UnknownResidue;
+
+/// An error for FASTA records that cannot be turned into a valid protein.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FastaError {
+    /// The sequence of the record contains an unknown residue.
+    UnknownResidue(UnknownResidue),
+    /// The protein built from the record failed validation.
+    Invalid(Error),
+}
+
+impl std::fmt::Display for FastaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            FastaError::UnknownResidue(e) => e.fmt(f),
+            FastaError::Invalid(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FastaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FastaError::UnknownResidue(e) => Some(e),
+            FastaError::Invalid(e) => Some(e),
+        }
+    }
+}
+
+impl From<UnknownResidue> for FastaError {
+    fn from(e: UnknownResidue) -> Self {
+        FastaError::UnknownResidue(e)
+    }
+}
+
+impl From<Error> for FastaError {
+    fn from(e: Error) -> Self {
+        FastaError::Invalid(e)
+    }
+}
+
+/// A FASTA record, with its header and its validated protein.
+pub type FastaRecord = (String, Result<Protein<Vec<AminoAcid>>, FastaError>);
+
+/// Build and validate the protein of a single FASTA record.
+fn build(sequence: &[u8]) -> Result<Protein<Vec<AminoAcid>>, FastaError> {
+    // a trailing `*` marks the stop codon in translated sequences
+    let sequence = sequence.strip_suffix(b"*").unwrap_or(sequence);
+    let protein = Protein::try_from(sequence)?;
+    protein.validate()?;
+    Ok(protein)
+}
+
+/// Read every record of a FASTA file and validate the resulting proteins.
+///
+/// Records are returned in order with their header, without the leading
+/// `>`. A record with invalid contents is reported with its error rather
+/// than interrupting the whole file, so that a database can be screened in
+/// a single pass. Sequence lines found before the first header are
+/// reported as a record with an empty header. Only I/O errors abort the
+/// reading.
+///
+/// # Example
+/// ```rust
+/// use proteinogenic::FastaError;
+///
+/// let fasta = b">sp|P01542|CRAM_CRAAB\nTTCCPSIVAR\nSNFNVCRLPG\n>bad\nGBG\n";
+/// let records = proteinogenic::read_and_validate_fasta(&fasta[..]).unwrap();
+/// assert_eq!(records.len(), 2);
+/// assert_eq!(records[0].0, "sp|P01542|CRAM_CRAAB");
+/// assert!(records[0].1.is_ok());
+/// assert!(matches!(records[1].1, Err(FastaError::UnknownResidue(_))));
+/// ```
+pub fn read_and_validate_fasta<R: BufRead>(reader: R) -> std::io::Result<Vec<FastaRecord>> {
+    let mut records = Vec::new();
+    let mut header = None;
+    let mut sequence = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('>') {
+            if header.is_some() || !sequence.is_empty() {
+                let name = header.take().unwrap_or_default();
+                records.push((name, build(&sequence)));
+                sequence.clear();
+            }
+            header = Some(name.trim().to_string());
+        } else {
+            sequence.extend(line.bytes());
+        }
+    }
+    if header.is_some() || !sequence.is_empty() {
+        records.push((header.unwrap_or_default(), build(&sequence)));
+    }
+    Ok(records)
+}
@@ -2,31 +2,125 @@
 
 extern crate purr;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
 
 use purr::feature::Aliphatic;
 use purr::feature::Aromatic;
 use purr::feature::AtomKind;
 use purr::feature::BondKind;
+use purr::feature::BracketAromatic;
 use purr::feature::BracketSymbol;
+use purr::feature::Charge;
 use purr::feature::Configuration;
 use purr::feature::Element;
+use purr::feature::Number;
 use purr::feature::Rnum;
 use purr::feature::VirtualHydrogen;
 use purr::walk::Follower;
 
-/// An error marker for sequences containing invalid amino acids.
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct UnknownResidue;
+mod canonical;
+mod complex;
+mod copy;
+mod fasta;
+mod linker;
+mod mass;
+mod molfile;
+#[cfg(feature = "rand")]
+mod random;
+mod reference;
+mod residue;
+mod writer;
+
+pub use complex::Complex;
+pub use fasta::read_and_validate_fasta;
+pub use fasta::FastaError;
+pub use fasta::FastaRecord;
+#[cfg(feature = "rand")]
+pub use random::NATURAL_ABUNDANCE;
+pub use reference::reference_smiles;
+pub use reference::verify_residue;
+pub use residue::Residue;
+pub use residue::ResidueContext;
+
+/// An error for sequences containing invalid amino acids.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UnknownResidue {
+    /// The position of the unknown residue in the sequence, starting at 1,
+    /// or `None` for a residue code decoded on its own.
+    pub position: Option<usize>,
+    /// The unknown 1-letter code, or `None` for a 3-letter code.
+    pub symbol: Option<char>,
+}
+
+impl UnknownResidue {
+    /// Record the position of the unknown residue in its sequence.
+    fn at(self, position: usize) -> Self {
+        Self {
+            position: Some(position),
+            ..self
+        }
+    }
+}
 
 impl std::fmt::Display for UnknownResidue {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "unknown residue found in sequence")
+        match self.symbol {
+            Some(symbol) => write!(f, "unknown residue {:?}", symbol)?,
+            None => write!(f, "unknown residue")?,
+        }
+        match self.position {
+            Some(position) => write!(f, " found at position {}", position),
+            None => write!(f, " found in sequence"),
+        }
     }
 }
 
 impl std::error::Error for UnknownResidue {}
 
+/// An error marker for cross-link notations that cannot be parsed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseCrossLinkError;
+
+impl std::fmt::Display for ParseCrossLinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "invalid cross-link notation")
+    }
+}
+
+impl std::error::Error for ParseCrossLinkError {}
+
+/// An error for disulfide connectivities that cannot be applied.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DisulfideError {
+    /// A pair of the connectivity cannot be parsed.
+    Parse(String),
+    /// The disulfide bond of a pair cannot be added to the protein.
+    Invalid(CrossLink, Error),
+}
+
+impl std::fmt::Display for DisulfideError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            DisulfideError::Parse(pair) => write!(f, "invalid disulfide notation {:?}", pair),
+            DisulfideError::Invalid(cross_link, e) => {
+                write!(f, "invalid disulfide {}: {}", cross_link, e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DisulfideError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DisulfideError::Parse(_) => None,
+            DisulfideError::Invalid(_, e) => Some(e),
+        }
+    }
+}
+
 /// A generic error type for this crate.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
@@ -67,17 +161,306 @@ pub enum Error {
     /// ```
     DuplicateCrossLink(u16),
 
+    /// A residue does not exist.
+    ///
+    /// This issue can occur when a cross-link is requested with a residue
+    /// index out of range. Residues are indexed from 1, so the index 0 is
+    /// always invalid.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::Cys;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Cys]);
+    /// assert_eq!(
+    ///     prot.cross_link(proteinogenic::CrossLink::Cystine(0, 1)),
+    ///     Err(Error::InvalidResidue(0)),
+    /// );
+    ///
+    /// prot.cross_link(proteinogenic::CrossLink::Cystine(2, 3)).unwrap();
+    /// assert_eq!(prot.validate(), Err(Error::InvalidResidue(3)));
+    /// ```
+    InvalidResidue(u16),
+
+    /// A residue of a complex does not exist.
+    ///
+    /// This issue can occur when a cross-link between chains of a complex
+    /// is requested with a chain or a residue index out of range.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::Cys;
+    ///
+    /// let chains = vec![proteinogenic::Protein::new(vec![Cys]); 2];
+    /// let mut complex = proteinogenic::Complex::new(chains);
+    /// assert_eq!(
+    ///     complex.cross_link(proteinogenic::CrossLink::Cystine(1, 2), (0, 1)),
+    ///     Err(Error::InvalidChainResidue(1, 2)),
+    /// );
+    /// ```
+    InvalidChainResidue(usize, u16),
+
+    /// A residue of a complex is involved in more than one cross-link.
+    ///
+    /// This is reported with the chain and the index of the residue, since
+    /// the same index can refer to a free residue in another chain.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::Cys;
+    /// use proteinogenic::CrossLink::Cystine;
+    ///
+    /// let chains = vec![proteinogenic::Protein::new(vec![Cys, Cys]); 2];
+    /// let mut complex = proteinogenic::Complex::new(chains);
+    /// complex.cross_link(Cystine(1, 1), (0, 1)).unwrap();
+    /// assert_eq!(
+    ///     complex.cross_link(Cystine(2, 1), (0, 1)),
+    ///     Err(Error::DuplicateChainCrossLink(1, 1)),
+    /// );
+    /// ```
+    DuplicateChainCrossLink(usize, u16),
+
+    /// A range of residues is invalid.
+    ///
+    /// This issue can occur when a subsequence is requested with a range
+    /// whose start is after its end, or whose bounds cannot be represented
+    /// as residue indices.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::Gly;
+    ///
+    /// let prot = proteinogenic::Protein::new(vec![Gly; 4]);
+    /// assert_eq!(prot.subsequence(3..1), Err(Error::InvalidRange(3, 1)));
+    /// ```
+    InvalidRange(usize, usize),
+
+    /// The linker of a custom cross-link is invalid.
+    ///
+    /// This issue can occur when the linker cannot be parsed as SMILES,
+    /// when it does not start with the first of exactly two attachment
+    /// points, or when it contains atoms or bonds the crate cannot handle,
+    /// such as isotopes or elements without a tabulated mass.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::CrossLink;
+    /// use proteinogenic::AminoAcid::Cys;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Cys]);
+    /// let cross_link = CrossLink::Custom { i: 1, j: 2, linker: "*CC" };
+    /// assert_eq!(prot.cross_link(cross_link), Err(Error::InvalidLinker("*CC")));
+    /// ```
+    InvalidLinker(&'static str),
+
+    /// A custom residue is modified.
+    ///
+    /// This issue can occur when a residue that is not an `AminoAcid` is
+    /// involved in a cross-link, a side chain cyclization, or carries a
+    /// modification of its side chain, since their atoms are only known to
+    /// the residue itself.
+    InvalidCustomResidue(u16),
+
     /// A requested cyclization is invalid.
     ///
     /// This issue can occur when a requested cycle cannot be created from the
     /// amino acid residues at the given location.
     InvalidCyclization(u16, AminoAcid, Cyclization),
 
+    /// A terminus is involved in more than one cyclization.
+    ///
+    /// # Example
+    /// The N-terminal amine cannot form both a lasso and a head-to-tail cycle:
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::Cyclization;
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly, Asp, Gly]);
+    /// prot.add_cyclization(Cyclization::Lasso(2)).unwrap();
+    /// assert_eq!(
+    ///     prot.add_cyclization(Cyclization::HeadToTail),
+    ///     Err(Error::DuplicateCyclization(Cyclization::HeadToTail)),
+    /// );
+    /// ```
+    DuplicateCyclization(Cyclization),
+
+    /// A modification is invalid.
+    ///
+    /// This issue can occur when a requested modification cannot be applied
+    /// to the amino acid residue at the given location.
+    ///
+    /// # Example
+    /// L-alanine has no ionizable side chain, and cannot be protonated:
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::Modification;
+    /// use proteinogenic::AminoAcid::Ala;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala]);
+    /// prot.modification(1, Modification::Protonated);
+    ///
+    /// let mut f = purr::write::Writer::new();
+    /// assert!(matches!(prot.visit(&mut f), Err(Error::InvalidModification(1, Ala, _))));
+    /// ```
+    InvalidModification(u16, AminoAcid, Modification),
+
+    /// A thioamide bond is invalid.
+    ///
+    /// This issue can occur when a thioamide bond is requested after the
+    /// last residue of a linear peptide, or outside of the sequence
+    /// (including index 0, since residue indices start at 1).
+    InvalidThioamide(u16),
+
+    /// A γ-glutamyl bond is invalid.
+    ///
+    /// This issue can occur when a γ-glutamyl bond is requested on a residue
+    /// other than L-glutamic acid, or on a residue whose side chain is
+    /// already involved in a cyclization.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Asp, Gly]);
+    /// prot.gamma_glutamyl(1);
+    ///
+    /// let mut f = purr::write::Writer::new();
+    /// assert_eq!(prot.visit(&mut f), Err(Error::InvalidGammaGlutamyl(1, Asp)));
+    /// ```
+    InvalidGammaGlutamyl(u16, AminoAcid),
+
+    /// An isoaspartyl bond is invalid.
+    ///
+    /// This issue can occur when an isoaspartyl bond is requested on a
+    /// residue other than L-aspartic acid, or on a residue whose side chain
+    /// is already involved in a cyclization.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Asn, Gly]);
+    /// prot.iso_aspartate(1);
+    ///
+    /// let mut f = purr::write::Writer::new();
+    /// assert_eq!(prot.visit(&mut f), Err(Error::InvalidIsoAspartyl(1, Asn)));
+    /// ```
+    InvalidIsoAspartyl(u16, AminoAcid),
+
+    /// A cis peptide bond annotation is invalid.
+    ///
+    /// This issue can occur when a cis peptide bond is requested before a
+    /// residue other than L-proline, or before the first residue of a
+    /// protein that is not head-to-tail cyclized.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly, Ala]);
+    /// prot.proline_cis(2);
+    ///
+    /// let mut f = purr::write::Writer::new();
+    /// assert_eq!(prot.visit(&mut f), Err(Error::InvalidCisProline(2, Ala)));
+    /// ```
+    InvalidCisProline(u16, AminoAcid),
+
+    /// A β-amino acid is invalid.
+    ///
+    /// This issue can occur when a β³-amino acid is requested for a residue
+    /// other than the standard amino acids, or for a residue that already
+    /// forms a γ-glutamyl bond.
+    InvalidBetaAminoAcid(u16, AminoAcid),
+
+    /// A double bond geometry is invalid.
+    ///
+    /// This issue can occur when a double bond geometry is requested for
+    /// a residue without a stereogenic double bond.
+    InvalidGeometry(u16, AminoAcid, Geometry),
+
+    /// A methyllanthionine configuration is invalid.
+    ///
+    /// This issue can occur when a configuration is requested for a residue
+    /// other than an L-threonine involved in a methyllanthionine.
+    InvalidMeLanConfiguration(u16, AminoAcid, MeLanConfiguration),
+
+    /// An N-terminal modification is invalid.
+    ///
+    /// This issue can occur when the N-terminus is modified while also being
+    /// involved in a cyclization, when an acyl chain is too short to carry
+    /// the requested substituents, or when a pyroglutamate is requested for
+    /// a protein which does not start with L-glutamine or L-glutamic acid.
+    ///
+    /// # Example
+    /// A head-to-tail cyclized peptide has no free N-terminal amine:
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::NTerm;
+    /// use proteinogenic::AminoAcid::Gly;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly, Gly]);
+    /// prot.cyclization(proteinogenic::Cyclization::HeadToTail);
+    /// prot.n_terminus(NTerm::Acetyl);
+    ///
+    /// let mut f = purr::write::Writer::new();
+    /// assert!(matches!(prot.visit(&mut f), Err(Error::InvalidNTerm(NTerm::Acetyl))));
+    /// ```
+    InvalidNTerm(NTerm),
+
+    /// A C-terminal modification is invalid.
+    ///
+    /// This issue can occur when the C-terminus is modified while also being
+    /// involved in a head-to-tail cyclization.
+    InvalidCTerm(CTerm),
+
+    /// A head-to-tail cyclization was requested for a single residue.
+    ///
+    /// The amine and the carboxyl group of a single residue cannot form a
+    /// peptide bond with each other, so a cyclic peptide needs at least
+    /// two residues.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Error;
+    /// use proteinogenic::AminoAcid::Ala;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala]);
+    /// prot.cyclization(proteinogenic::Cyclization::HeadToTail);
+    ///
+    /// let mut f = purr::write::Writer::new();
+    /// assert_eq!(prot.visit(&mut f), Err(Error::CyclizationTooSmall));
+    /// ```
+    CyclizationTooSmall,
+
     /// Too many cross-links were created.
     ///
-    /// This can occur when a protein contains too many cross-links, which will
-    /// exhaust the number of possibilites for ring identifiers in SMILES.
+    /// This can occur when a protein contains too many cross-links and
+    /// cyclizations, which will exhaust the number of possibilites for ring
+    /// identifiers in SMILES.
     TooManyCrossLinks,
+
+    /// Too many atoms or bonds were created.
+    ///
+    /// This can occur when exporting a large protein to a V2000 Molfile,
+    /// which cannot contain more than 999 atoms or 999 bonds.
+    TooManyAtoms,
+
+    /// The molecular graph of a protein could not be built.
+    ///
+    /// This is reported with the index of the offending atom when a walk
+    /// leaves a ring closure unbalanced, or closes a ring with a bond
+    /// incompatible with the one that opened it. It should not occur for
+    /// proteins accepted by `visit`.
+    InvalidGraph(usize),
 }
 
 impl std::fmt::Display for Error {
@@ -89,27 +472,153 @@ impl std::fmt::Display for Error {
                     "number of cross-links in protein exceeds the allowed value"
                 )
             }
+            Error::TooManyAtoms => {
+                write!(f, "number of atoms in protein exceeds the allowed value")
+            }
+            Error::CyclizationTooSmall => {
+                write!(f, "head-to-tail cyclization requires at least two residues")
+            }
             Error::DuplicateCrossLink(i) => {
                 write!(f, "residue {} is already involded in a cross-link", i)
             }
-            Error::InvalidCrossLink(i, aa, _) => {
-                write!(f, "invalid cross-link for residue {} ({})", i, aa.as_code())
+            Error::InvalidResidue(i) => {
+                write!(f, "residue {} not found in protein", i)
+            }
+            Error::InvalidChainResidue(chain, i) => {
+                write!(f, "residue {} not found in chain {}", i, chain)
+            }
+            Error::DuplicateChainCrossLink(chain, i) => {
+                write!(
+                    f,
+                    "residue {} of chain {} is already involved in a cross-link",
+                    i, chain
+                )
+            }
+            Error::InvalidRange(start, end) => {
+                write!(f, "invalid range of residues {}..{}", start, end)
+            }
+            Error::InvalidCustomResidue(i) => {
+                write!(f, "custom residue {} cannot be modified", i)
+            }
+            Error::InvalidLinker(linker) => {
+                write!(f, "invalid cross-link linker {:?}", linker)
+            }
+            Error::InvalidCrossLink(i, aa, cross_link) => {
+                write!(
+                    f,
+                    "invalid cross-link {} for residue {}: expected {}, found {}",
+                    cross_link,
+                    i,
+                    cross_link.expected(*i),
+                    aa.as_code()
+                )
+            }
+            Error::InvalidModification(i, aa, _) => {
+                write!(
+                    f,
+                    "invalid modification for residue {} ({})",
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidThioamide(i) => {
+                write!(f, "invalid thioamide bond after residue {}", i)
+            }
+            Error::InvalidGeometry(i, aa, geometry) => {
+                write!(
+                    f,
+                    "invalid geometry {:?} for residue {} ({})",
+                    geometry,
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidMeLanConfiguration(i, aa, configuration) => {
+                write!(
+                    f,
+                    "invalid methyllanthionine configuration {:?} for residue {} ({})",
+                    configuration,
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidBetaAminoAcid(i, aa) => {
+                write!(
+                    f,
+                    "invalid β-amino acid for residue {} ({})",
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidGammaGlutamyl(i, aa) => {
+                write!(
+                    f,
+                    "invalid γ-glutamyl bond for residue {} ({})",
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidCisProline(i, aa) => {
+                write!(
+                    f,
+                    "invalid cis peptide bond for residue {} ({})",
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidIsoAspartyl(i, aa) => {
+                write!(
+                    f,
+                    "invalid isoaspartyl bond for residue {} ({})",
+                    i,
+                    aa.as_code()
+                )
+            }
+            Error::InvalidNTerm(_) => {
+                write!(f, "invalid N-terminal modification")
+            }
+            Error::InvalidCTerm(_) => {
+                write!(f, "invalid C-terminal modification")
             }
-            Error::InvalidCyclization(i, aa, _) => {
+            Error::DuplicateCyclization(cyclization) => {
                 write!(
                     f,
-                    "invalid cyclization for residue {} ({})",
+                    "cyclization {} involves an already cyclized terminus",
+                    cyclization
+                )
+            }
+            Error::InvalidCyclization(i, aa, cyclization) => {
+                write!(
+                    f,
+                    "invalid cyclization {} for residue {} ({})",
+                    cyclization,
                     i,
                     aa.as_code()
                 )
             }
+            Error::InvalidGraph(atom) => {
+                write!(f, "invalid molecular graph at atom {}", atom)
+            }
         }
     }
 }
 
 impl std::error::Error for Error {}
 
-/// A single L-α amino-acid.
+impl From<purr::graph::Error> for Error {
+    fn from(error: purr::graph::Error) -> Self {
+        match error {
+            purr::graph::Error::Rnum(atom) => Error::InvalidGraph(atom),
+            purr::graph::Error::Join(atom, _) => Error::InvalidGraph(atom),
+        }
+    }
+}
+
+/// A single amino-acid residue.
+///
+/// Most residues are L-α amino-acids, but the backbone spacers `BAla` and
+/// `Gaba` are β and γ amino-acids, with one and two extra methylene groups
+/// between their amino and carboxyl groups.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AminoAcid {
     /// [L-arginine](https://en.wikipedia.org/wiki/Arginine).
@@ -231,22 +740,174 @@ pub enum AminoAcid {
     ///
     /// ![Skeletal formula of (Z)-dehydrobutyrine](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=18820)
     Dhb,
-}
 
-impl AminoAcid {
-    /// Create an `AminoAcid` variant from a 1-letter code.
-    pub fn from_char(code: char) -> Result<AminoAcid, UnknownResidue> {
-        match code {
-            'R' => Ok(AminoAcid::Arg),
-            'H' => Ok(AminoAcid::His),
-            'K' => Ok(AminoAcid::Lys),
-            'D' => Ok(AminoAcid::Asp),
-            'E' => Ok(AminoAcid::Glu),
-            'S' => Ok(AminoAcid::Ser),
-            'T' => Ok(AminoAcid::Thr),
-            'N' => Ok(AminoAcid::Asn),
-            'Q' => Ok(AminoAcid::Gln),
-            'G' => Ok(AminoAcid::Gly),
+    /// [5-hydroxy-L-tryptophan](https://en.wikipedia.org/wiki/5-Hydroxytryptophan).
+    ///
+    /// ![Skeletal formula of 5-hydroxy-L-tryptophan](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=17780)
+    Htp,
+
+    /// N-formyl-L-kynurenine.
+    ///
+    /// An oxidation product of L-tryptophan, where the pyrrole ring of the
+    /// indole has been opened.
+    Nfk,
+
+    /// Oxindolylalanine.
+    ///
+    /// An oxidation product of L-tryptophan, where the pyrrole ring of the
+    /// indole has been saturated into a 2-oxindole while the benzene ring
+    /// stays aromatic.
+    Oia,
+
+    /// [β-alanine](https://en.wikipedia.org/wiki/%CE%92-Alanine).
+    ///
+    /// A β-amino acid, which extends the backbone with an additional
+    /// methylene group between the amine and the carboxyl group.
+    ///
+    /// ![Skeletal formula of β-alanine](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=16958)
+    BAla,
+
+    /// [γ-aminobutyric acid](https://en.wikipedia.org/wiki/GABA).
+    ///
+    /// A γ-amino acid, which extends the backbone with two additional
+    /// methylene groups between the amine and the carboxyl group.
+    ///
+    /// ![Skeletal formula of γ-aminobutyric acid](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=16865)
+    Gaba,
+
+    /// [L-homoarginine](https://en.wikipedia.org/wiki/Homoarginine).
+    ///
+    /// A homologue of L-arginine with an additional methylene group in
+    /// its side chain.
+    ///
+    /// ![Skeletal formula of L-homoarginine](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=27747)
+    Har,
+
+    /// 4-fluoro-L-phenylalanine.
+    ///
+    /// A halogenated L-phenylalanine, with a fluorine atom in *para*
+    /// position of the phenyl ring.
+    Phe4F,
+
+    /// 4-chloro-L-phenylalanine.
+    ///
+    /// A halogenated L-phenylalanine, with a chlorine atom in *para*
+    /// position of the phenyl ring.
+    Phe4Cl,
+
+    /// 4-bromo-L-phenylalanine.
+    ///
+    /// A halogenated L-phenylalanine, with a bromine atom in *para*
+    /// position of the phenyl ring.
+    Phe4Br,
+
+    /// (S)-2-(4-pentenyl)alanine.
+    ///
+    /// An α-methyl, α-alkenyl amino acid used in hydrocarbon-stapled
+    /// peptides, where it is bridged by ring-closing metathesis to another
+    /// (S)-2-(4-pentenyl)alanine four residues apart.
+    S5,
+
+    /// (R)-2-(7-octenyl)alanine.
+    ///
+    /// An α-methyl, α-alkenyl amino acid used in hydrocarbon-stapled
+    /// peptides, where it is bridged by ring-closing metathesis to a
+    /// (S)-2-(4-pentenyl)alanine seven residues apart.
+    R8,
+
+    /// [L-ornithine](https://en.wikipedia.org/wiki/Ornithine).
+    ///
+    /// A homologue of L-lysine with one less methylene group in its side
+    /// chain, ending with a δ-amine.
+    ///
+    /// ![Skeletal formula of L-ornithine](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=15729)
+    Orn,
+
+    /// L-2,4-diaminobutyric acid.
+    ///
+    /// A homologue of L-lysine with two less methylene groups in its side
+    /// chain, ending with a γ-amine.
+    Dab,
+
+    /// L-2,3-diaminopropionic acid.
+    ///
+    /// A homologue of L-lysine with three less methylene groups in its
+    /// side chain, ending with a β-amine.
+    Dap,
+
+    /// An unknown residue.
+    ///
+    /// Unknown residues keep the backbone of the protein at the right
+    /// length, and are rendered according to the `Masking` strategy of the
+    /// protein they belong to.
+    Xaa,
+}
+
+impl AminoAcid {
+    /// Every supported amino acid, in declaration order.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid;
+    ///
+    /// assert!(AminoAcid::ALL.contains(&AminoAcid::Sec));
+    /// assert_eq!(AminoAcid::ALL.last(), Some(&AminoAcid::Xaa));
+    /// ```
+    pub const ALL: &[AminoAcid] = &[
+        AminoAcid::Arg,
+        AminoAcid::His,
+        AminoAcid::Lys,
+        AminoAcid::Asp,
+        AminoAcid::Glu,
+        AminoAcid::Ser,
+        AminoAcid::Thr,
+        AminoAcid::Asn,
+        AminoAcid::Gln,
+        AminoAcid::Gly,
+        AminoAcid::Pro,
+        AminoAcid::Cys,
+        AminoAcid::Sec,
+        AminoAcid::Ala,
+        AminoAcid::Val,
+        AminoAcid::Ile,
+        AminoAcid::Leu,
+        AminoAcid::Met,
+        AminoAcid::Phe,
+        AminoAcid::Tyr,
+        AminoAcid::Trp,
+        AminoAcid::Pyl,
+        AminoAcid::Dha,
+        AminoAcid::Dhb,
+        AminoAcid::Htp,
+        AminoAcid::Nfk,
+        AminoAcid::Oia,
+        AminoAcid::BAla,
+        AminoAcid::Gaba,
+        AminoAcid::Har,
+        AminoAcid::Phe4F,
+        AminoAcid::Phe4Cl,
+        AminoAcid::Phe4Br,
+        AminoAcid::S5,
+        AminoAcid::R8,
+        AminoAcid::Orn,
+        AminoAcid::Dab,
+        AminoAcid::Dap,
+        AminoAcid::Xaa,
+    ];
+
+    /// Create an `AminoAcid` variant from a 1-letter code.
+    pub fn from_char(code: char) -> Result<AminoAcid, UnknownResidue> {
+        match code {
+            'R' => Ok(AminoAcid::Arg),
+            'H' => Ok(AminoAcid::His),
+            'K' => Ok(AminoAcid::Lys),
+            'D' => Ok(AminoAcid::Asp),
+            'E' => Ok(AminoAcid::Glu),
+            'S' => Ok(AminoAcid::Ser),
+            'T' => Ok(AminoAcid::Thr),
+            'N' => Ok(AminoAcid::Asn),
+            'Q' => Ok(AminoAcid::Gln),
+            'G' => Ok(AminoAcid::Gly),
             'P' => Ok(AminoAcid::Pro),
             'C' => Ok(AminoAcid::Cys),
             'U' => Ok(AminoAcid::Sec),
@@ -259,38 +920,61 @@ impl AminoAcid {
             'Y' => Ok(AminoAcid::Tyr),
             'W' => Ok(AminoAcid::Trp),
             'O' => Ok(AminoAcid::Pyl),
-            _ => Err(UnknownResidue),
+            'X' => Ok(AminoAcid::Xaa),
+            _ => Err(UnknownResidue {
+                position: None,
+                symbol: Some(code),
+            }),
         }
     }
 
     /// Create an `AminoAcid` variant from a 3-letter code.
+    ///
+    /// Codes are matched case-insensitively, so that `"ALA"` and `"ala"`
+    /// are recognized as well as the canonical `"Ala"` returned by
+    /// `AminoAcid::as_code`.
     pub fn from_code(code: &str) -> Result<AminoAcid, UnknownResidue> {
-        match code {
-            "Arg" => Ok(AminoAcid::Arg),
-            "His" => Ok(AminoAcid::His),
-            "Lys" => Ok(AminoAcid::Lys),
-            "Asp" => Ok(AminoAcid::Asp),
-            "Glu" => Ok(AminoAcid::Glu),
-            "Ser" => Ok(AminoAcid::Ser),
-            "Thr" => Ok(AminoAcid::Thr),
-            "Asn" => Ok(AminoAcid::Asn),
-            "Gln" => Ok(AminoAcid::Gln),
-            "Gly" => Ok(AminoAcid::Gly),
-            "Pro" => Ok(AminoAcid::Pro),
-            "Cys" => Ok(AminoAcid::Cys),
-            "Sec" => Ok(AminoAcid::Sec),
-            "Ala" => Ok(AminoAcid::Ala),
-            "Val" => Ok(AminoAcid::Val),
-            "Ile" => Ok(AminoAcid::Ile),
-            "Leu" => Ok(AminoAcid::Leu),
-            "Met" => Ok(AminoAcid::Met),
-            "Phe" => Ok(AminoAcid::Phe),
-            "Tyr" => Ok(AminoAcid::Tyr),
-            "Trp" => Ok(AminoAcid::Trp),
-            "Pyl" => Ok(AminoAcid::Pyl),
-            "Dha" => Ok(AminoAcid::Dha),
-            "Dhb" => Ok(AminoAcid::Dhb),
-            _ => Err(UnknownResidue),
+        match code.to_ascii_lowercase().as_str() {
+            "arg" => Ok(AminoAcid::Arg),
+            "his" => Ok(AminoAcid::His),
+            "lys" => Ok(AminoAcid::Lys),
+            "asp" => Ok(AminoAcid::Asp),
+            "glu" => Ok(AminoAcid::Glu),
+            "ser" => Ok(AminoAcid::Ser),
+            "thr" => Ok(AminoAcid::Thr),
+            "asn" => Ok(AminoAcid::Asn),
+            "gln" => Ok(AminoAcid::Gln),
+            "gly" => Ok(AminoAcid::Gly),
+            "pro" => Ok(AminoAcid::Pro),
+            "cys" => Ok(AminoAcid::Cys),
+            "sec" => Ok(AminoAcid::Sec),
+            "ala" => Ok(AminoAcid::Ala),
+            "val" => Ok(AminoAcid::Val),
+            "ile" => Ok(AminoAcid::Ile),
+            "leu" => Ok(AminoAcid::Leu),
+            "met" => Ok(AminoAcid::Met),
+            "phe" => Ok(AminoAcid::Phe),
+            "tyr" => Ok(AminoAcid::Tyr),
+            "trp" => Ok(AminoAcid::Trp),
+            "pyl" => Ok(AminoAcid::Pyl),
+            "dha" => Ok(AminoAcid::Dha),
+            "dhb" => Ok(AminoAcid::Dhb),
+            "htp" => Ok(AminoAcid::Htp),
+            "nfk" => Ok(AminoAcid::Nfk),
+            "oia" => Ok(AminoAcid::Oia),
+            "bala" => Ok(AminoAcid::BAla),
+            "gaba" => Ok(AminoAcid::Gaba),
+            "har" => Ok(AminoAcid::Har),
+            "phe4f" => Ok(AminoAcid::Phe4F),
+            "phe4cl" => Ok(AminoAcid::Phe4Cl),
+            "phe4br" => Ok(AminoAcid::Phe4Br),
+            "s5" => Ok(AminoAcid::S5),
+            "r8" => Ok(AminoAcid::R8),
+            "orn" => Ok(AminoAcid::Orn),
+            "dab" => Ok(AminoAcid::Dab),
+            "dap" => Ok(AminoAcid::Dap),
+            "xaa" => Ok(AminoAcid::Xaa),
+            _ => Err(UnknownResidue::default()),
         }
     }
 
@@ -321,11 +1005,272 @@ impl AminoAcid {
             AminoAcid::Pyl => "Pyl",
             AminoAcid::Dha => "Dha",
             AminoAcid::Dhb => "Dhb",
+            AminoAcid::Htp => "Htp",
+            AminoAcid::Nfk => "Nfk",
+            AminoAcid::Oia => "Oia",
+            AminoAcid::BAla => "bAla",
+            AminoAcid::Gaba => "Gaba",
+            AminoAcid::Har => "Har",
+            AminoAcid::Phe4F => "Phe4F",
+            AminoAcid::Phe4Cl => "Phe4Cl",
+            AminoAcid::Phe4Br => "Phe4Br",
+            AminoAcid::S5 => "S5",
+            AminoAcid::R8 => "R8",
+            AminoAcid::Orn => "Orn",
+            AminoAcid::Dab => "Dab",
+            AminoAcid::Dap => "Dap",
+            AminoAcid::Xaa => "Xaa",
+        }
+    }
+
+    /// The side chain atom where modifications of the residue are attached.
+    ///
+    /// This is the terminal heteroatom of the side chain, such as the ε
+    /// nitrogen of L-lysine, the hydroxyl oxygen of L-serine, or the thiol
+    /// sulfur of L-cysteine. Residues without a modifiable side chain, such
+    /// as glycine, L-alanine or L-phenylalanine, return `None`.
+    pub fn attachment_atom(&self) -> Option<AttachmentAtom> {
+        match self {
+            AminoAcid::Arg => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::His => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Lys => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Asp => Some(AttachmentAtom::Oxygen),
+            AminoAcid::Glu => Some(AttachmentAtom::Oxygen),
+            AminoAcid::Ser => Some(AttachmentAtom::Oxygen),
+            AminoAcid::Thr => Some(AttachmentAtom::Oxygen),
+            AminoAcid::Asn => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Gln => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Gly => None,
+            AminoAcid::Pro => None,
+            AminoAcid::Cys => Some(AttachmentAtom::Sulfur),
+            AminoAcid::Sec => Some(AttachmentAtom::Selenium),
+            AminoAcid::Ala => None,
+            AminoAcid::Val => None,
+            AminoAcid::Ile => None,
+            AminoAcid::Leu => None,
+            AminoAcid::Met => Some(AttachmentAtom::Sulfur),
+            AminoAcid::Phe => None,
+            AminoAcid::Tyr => Some(AttachmentAtom::Oxygen),
+            AminoAcid::Trp => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Pyl => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Dha => None,
+            AminoAcid::Dhb => None,
+            AminoAcid::Htp => Some(AttachmentAtom::Oxygen),
+            AminoAcid::Nfk => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Oia => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::BAla => None,
+            AminoAcid::Gaba => None,
+            AminoAcid::Har => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Phe4F => None,
+            AminoAcid::Phe4Cl => None,
+            AminoAcid::Phe4Br => None,
+            AminoAcid::S5 => None,
+            AminoAcid::R8 => None,
+            AminoAcid::Orn => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Dab => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Dap => Some(AttachmentAtom::Nitrogen),
+            AminoAcid::Xaa => None,
+        }
+    }
+
+    /// The physicochemical class of the side chain of the amino acid.
+    ///
+    /// Standard residues follow the usual classification, with L-histidine
+    /// as basic, and glycine and L-proline as special. Non-standard residues
+    /// are classified from their side chain: L-homoarginine and the
+    /// homologues of L-lysine are basic, the
+    /// halogenated phenylalanines and the oxidation products of tryptophan
+    /// are aromatic, L-pyrrolysine and L-selenocysteine are polar, and the
+    /// stapling residues are hydrophobic. Dehydrated residues, β- and
+    /// γ-amino acids and unknown residues are special.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid;
+    /// use proteinogenic::ResidueClass;
+    ///
+    /// assert_eq!(AminoAcid::Leu.property(), ResidueClass::Hydrophobic);
+    /// assert_eq!(AminoAcid::Phe4F.property(), ResidueClass::Aromatic);
+    /// ```
+    pub fn property(&self) -> ResidueClass {
+        match self {
+            AminoAcid::Arg => ResidueClass::Basic,
+            AminoAcid::His => ResidueClass::Basic,
+            AminoAcid::Lys => ResidueClass::Basic,
+            AminoAcid::Asp => ResidueClass::Acidic,
+            AminoAcid::Glu => ResidueClass::Acidic,
+            AminoAcid::Ser => ResidueClass::Polar,
+            AminoAcid::Thr => ResidueClass::Polar,
+            AminoAcid::Asn => ResidueClass::Polar,
+            AminoAcid::Gln => ResidueClass::Polar,
+            AminoAcid::Gly => ResidueClass::Special,
+            AminoAcid::Pro => ResidueClass::Special,
+            AminoAcid::Cys => ResidueClass::Polar,
+            AminoAcid::Sec => ResidueClass::Polar,
+            AminoAcid::Ala => ResidueClass::Hydrophobic,
+            AminoAcid::Val => ResidueClass::Hydrophobic,
+            AminoAcid::Ile => ResidueClass::Hydrophobic,
+            AminoAcid::Leu => ResidueClass::Hydrophobic,
+            AminoAcid::Met => ResidueClass::Hydrophobic,
+            AminoAcid::Phe => ResidueClass::Aromatic,
+            AminoAcid::Tyr => ResidueClass::Aromatic,
+            AminoAcid::Trp => ResidueClass::Aromatic,
+            AminoAcid::Pyl => ResidueClass::Polar,
+            AminoAcid::Dha => ResidueClass::Special,
+            AminoAcid::Dhb => ResidueClass::Special,
+            AminoAcid::Htp => ResidueClass::Aromatic,
+            AminoAcid::Nfk => ResidueClass::Aromatic,
+            AminoAcid::Oia => ResidueClass::Aromatic,
+            AminoAcid::BAla => ResidueClass::Special,
+            AminoAcid::Gaba => ResidueClass::Special,
+            AminoAcid::Har => ResidueClass::Basic,
+            AminoAcid::Phe4F => ResidueClass::Aromatic,
+            AminoAcid::Phe4Cl => ResidueClass::Aromatic,
+            AminoAcid::Phe4Br => ResidueClass::Aromatic,
+            AminoAcid::S5 => ResidueClass::Hydrophobic,
+            AminoAcid::R8 => ResidueClass::Hydrophobic,
+            AminoAcid::Orn => ResidueClass::Basic,
+            AminoAcid::Dab => ResidueClass::Basic,
+            AminoAcid::Dap => ResidueClass::Basic,
+            AminoAcid::Xaa => ResidueClass::Special,
         }
     }
+
+    /// Check whether the α carbon of the amino acid is not a stereocenter.
+    fn is_achiral(&self) -> bool {
+        matches!(
+            self,
+            AminoAcid::Gly
+                | AminoAcid::Dha
+                | AminoAcid::Dhb
+                | AminoAcid::BAla
+                | AminoAcid::Gaba
+                | AminoAcid::Xaa
+        )
+    }
+
+    /// Generate a SMILES string for the side chain of the amino acid.
+    ///
+    /// The side chain is rendered from a dummy atom standing for the α
+    /// carbon, without any of the backbone atoms. For instance, the side
+    /// chain of L-alanine is rendered as `*C`.
+    ///
+    /// β-alanine and GABA have no side chain: the dummy atom stands for
+    /// the carbon bonded to the backbone nitrogen, and the methylenes
+    /// leading to the carbonyl carbon are written instead, giving `*C` and
+    /// `*CC` respectively.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid;
+    ///
+    /// assert_eq!(AminoAcid::Ala.side_chain_smiles(), "*C");
+    /// assert_eq!(AminoAcid::BAla.side_chain_smiles(), "*C");
+    /// assert_eq!(AminoAcid::Gaba.side_chain_smiles(), "*CC");
+    /// ```
+    pub fn side_chain_smiles(&self) -> String {
+        let mut recorder = Recorder::default();
+        recorder.root(AtomKind::Aliphatic(Aliphatic::N));
+        let context = Context {
+            cross_links: &HashMap::new(),
+            modifications: &HashMap::new(),
+            gamma_glutamyls: &HashSet::new(),
+            iso_aspartyls: &HashSet::new(),
+            cis_prolines: &HashSet::new(),
+            d_residues: &HashSet::new(),
+            beta_residues: &HashSet::new(),
+            masking: Masking::default(),
+            geometries: &HashMap::new(),
+            melan_configurations: &HashMap::new(),
+            cyclizations: &[],
+            linker_rnum: 3,
+            full_stereo: true,
+            residue_maps: false,
+            kekulize: false,
+            pyroglutamate: false,
+        };
+        Protein::<Vec<AminoAcid>>::visit_residue(*self, &mut recorder, 1, &context)
+            .expect("residue without modifications cannot fail to be visited");
+
+        let mut writer = purr::write::Writer::new();
+        recorder.replay_side_chain(&mut writer);
+        writer.write()
+    }
+
+    /// Generate a SMILES string for the free amino acid.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid;
+    ///
+    /// assert_eq!(AminoAcid::Ala.smiles().unwrap(), "N[C@@H](C)C(=O)-O");
+    /// ```
+    pub fn smiles(&self) -> Result<String, Error> {
+        Protein::new([*self]).smiles()
+    }
+
+    /// Generate a SMILES string for the residue capped at both termini.
+    ///
+    /// The residue is written between an N-terminal acetyl group and a
+    /// C-terminal N-methylamide, which is the "dipeptide" form (Ace-X-Nme)
+    /// commonly used to parameterize residues for molecular dynamics.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid;
+    ///
+    /// assert_eq!(
+    ///     AminoAcid::Ala.capped_smiles().unwrap(),
+    ///     "CC(=O)N[C@@H](C)C(=O)-NC"
+    /// );
+    /// ```
+    pub fn capped_smiles(&self) -> Result<String, Error> {
+        let mut prot = Protein::new([*self]);
+        prot.n_terminus(NTerm::Acetyl)
+            .c_terminus(CTerm::MethylAmide);
+        prot.smiles()
+    }
+}
+
+/// The side chain atom of a residue where modifications are attached.
+///
+/// See `AminoAcid::attachment_atom` for the convention used for each residue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttachmentAtom {
+    /// The terminal nitrogen of an amine, amide, guanidine or imidazole group.
+    Nitrogen,
+    /// The oxygen of a hydroxyl or carboxyl group.
+    Oxygen,
+    /// The sulfur of a thiol or thioether group.
+    Sulfur,
+    /// The selenium of a selenol group.
+    Selenium,
+}
+
+/// The physicochemical class of the side chain of a residue.
+///
+/// See `AminoAcid::property` for the classification of each residue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResidueClass {
+    /// An aliphatic side chain, such as the one of L-leucine.
+    Hydrophobic,
+    /// An uncharged side chain with a heteroatom, such as the one of L-serine.
+    Polar,
+    /// A side chain negatively charged at physiological pH.
+    Acidic,
+    /// A side chain that can be positively charged at physiological pH.
+    Basic,
+    /// A side chain with an aromatic ring, such as the one of L-phenylalanine.
+    Aromatic,
+    /// A residue with an unusual backbone, such as glycine or L-proline.
+    Special,
 }
 
 /// A covalent bond between several amino-acid residues.
+///
+/// Residues are given by their index in the sequence, starting at 1 for
+/// the N-terminal residue. Use `Protein::residue_index` to get the index
+/// of a residue from its 0-based position in the sequence.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CrossLink {
     /// [L-cystine](https://en.wikipedia.org/wiki/Cystine).
@@ -336,6 +1281,10 @@ pub enum CrossLink {
     /// [meso-lanthionine](https://en.wikipedia.org/wiki/Lanthionine).
     ///
     /// ![Skeletal formula of meso-lanthionine](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=25205)
+    ///
+    /// The sulfur is donated by the L-cysteine residue, which can be given
+    /// either first or second: the other residue is the L-serine whose
+    /// dehydrated side chain accepts the thiol.
     Lan(u16, u16),
 
     /// β-methyllanthionine.
@@ -345,637 +1294,7717 @@ pub enum CrossLink {
     ///
     /// ![Skeletal formula of Lysinoalanine](https://www.ebi.ac.uk/chebi/displayImage.do?defaultImage=true&imageIndex=0&chebiId=189781)
     Lal(u16, u16),
-}
 
-/// A peptide cyclization mechanism.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Cyclization {
-    /// No cyclization, resulting in a linear peptide.
-    None,
+    /// Selenolanthionine.
+    ///
+    /// The selenoether analog of lanthionine, where the bridge is formed
+    /// by the selenium of a selenocysteine rather than the sulfur of a
+    /// cysteine.
+    SeLan(u16, u16),
 
-    /// Head-to-tail cyclization, resulting in an homodetic cyclic peptide.
-    HeadToTail,
+    /// A diselenide bond between two L-selenocysteine residues.
+    ///
+    /// The selenium analog of cystine, found in engineered peptides where
+    /// it is used to direct oxidative folding.
+    Diselenide(u16, u16),
 
-    /// Head-to-sidechain cyclization, resulting in a lasso peptide.
+    /// A selenylsulfide bond between a L-selenocysteine and a L-cysteine.
     ///
-    /// This cyclization process forms an isopeptidic bond between the
-    /// amine group of the N-terminal amino acid and the carboxyl group of
-    /// an Asp or Glu residue.
-    Lasso(u16),
-}
+    /// This mixed bond is formed in the active site of selenoenzymes such
+    /// as thioredoxin reductase. The residues can be given in any order.
+    SelenylSulfide(u16, u16),
 
-impl Default for Cyclization {
-    fn default() -> Self {
-        Cyclization::None
-    }
-}
+    /// Sactionine, a thioether between a cysteine and an α carbon.
+    ///
+    /// The sulfur of the L-cysteine at the first index is bonded to the α
+    /// carbon of the residue at the second index, as found in sactipeptides
+    /// such as subtilosin A. The configuration of the α carbon involved in
+    /// the thioether is left unspecified.
+    Thioether(u16, u16),
 
-/// A protein abstracted as a modified peptide.
-#[derive(Debug, Clone, PartialEq)]
-pub struct Protein<S> {
-    cyclization: Cyclization,
+    /// A hydrocarbon staple between two α-alkenyl amino acids.
+    ///
+    /// The terminal alkenes of the stapling residues, `S5` or `R8`, are
+    /// joined by ring-closing metathesis into an all-carbon bridge, with
+    /// the loss of ethylene. The geometry of the resulting double bond is
+    /// left unspecified.
+    HydrocarbonStaple(u16, u16),
 
-    cross_links: HashMap<u16, (Rnum, CrossLink)>,
-    cross_link_num: u16,
+    /// A histidine-tyrosine cross-link, as found in protein-derived cofactors.
+    ///
+    /// The τ nitrogen of the L-histidine is bonded to a carbon of the
+    /// L-tyrosine ring in ortho of the hydroxyl group, as found in the
+    /// active site of cytochrome c oxidase. The residues can be given in
+    /// any order.
+    HisTyr(u16, u16),
 
-    sequence: S,
+    /// A synthetic cross-link through a custom linker.
+    ///
+    /// The linker is a SMILES fragment with two attachment points, written
+    /// as `*` atoms, the first of which must start the fragment. The first
+    /// attachment point is bonded to the side chain of residue `i`, and the
+    /// second one to the side chain of residue `j`, which makes it possible
+    /// to describe stapled peptides, such as two cysteines bis-alkylated by
+    /// a *m*-xylene linker (`*Cc1cccc(C*)c1`).
+    ///
+    /// The heteroatom of the side chain of L-cysteine, L-selenocysteine,
+    /// L-lysine, L-serine and L-threonine can be linked. L-pyrrolysine can
+    /// be linked as an electrophile through the imine carbon of its
+    /// pyrroline ring, which is reduced to a pyrrolidine by the addition of
+    /// the linker, leaving the configuration of the new stereocenter
+    /// unspecified. The atoms of the linker are written as given, even in a
+    /// kekulized protein.
+    Custom {
+        /// The index of the residue bonded to the first attachment point.
+        i: u16,
+        /// The index of the residue bonded to the second attachment point.
+        j: u16,
+        /// The SMILES of the linker.
+        linker: &'static str,
+    },
 }
 
-impl<S> Protein<S> {
-    /// Mark whether the peptide is cyclized through a known cyclization mechanism.
-    pub fn cyclization(&mut self, cyclization: Cyclization) -> &mut Self {
-        self.cyclization = cyclization;
-        self
+impl std::fmt::Display for CrossLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let (i, j) = self.residues();
+        match self {
+            CrossLink::Cystine(_, _) => write!(f, "Cystine({}-{})", i, j),
+            CrossLink::Lan(_, _) => write!(f, "Lan({}-{})", i, j),
+            CrossLink::MeLan(_, _) => write!(f, "MeLan({}-{})", i, j),
+            CrossLink::Lal(_, _) => write!(f, "Lal({}-{})", i, j),
+            CrossLink::SeLan(_, _) => write!(f, "SeLan({}-{})", i, j),
+            CrossLink::Diselenide(_, _) => write!(f, "Diselenide({}-{})", i, j),
+            CrossLink::SelenylSulfide(_, _) => write!(f, "SelenylSulfide({}-{})", i, j),
+            CrossLink::Thioether(_, _) => write!(f, "Thioether({}-{})", i, j),
+            CrossLink::HydrocarbonStaple(_, _) => write!(f, "HydrocarbonStaple({}-{})", i, j),
+            CrossLink::HisTyr(_, _) => write!(f, "HisTyr({}-{})", i, j),
+            CrossLink::Custom { linker, .. } => write!(f, "Custom({}-{}, {})", i, j, linker),
+        }
     }
+}
 
-    /// Add a cross-link between residues of the peptide.
-    pub fn cross_link(&mut self, cross_link: CrossLink) -> Result<&mut Self, Error> {
-        let rnum = Rnum::try_from(self.cross_link_num).unwrap(); // FIXME
-        match cross_link {
-            CrossLink::Cystine(i, j)
-            | CrossLink::Lan(i, j)
-            | CrossLink::MeLan(i, j)
-            | CrossLink::Lal(i, j) => {
-                let val = (rnum, cross_link);
-                if let Some(_) = self.cross_links.insert(i, val.clone()) {
-                    return Err(Error::DuplicateCrossLink(i));
-                }
-                if let Some(_) = self.cross_links.insert(j, val) {
-                    return Err(Error::DuplicateCrossLink(j));
-                }
-            }
-        }
+impl std::str::FromStr for CrossLink {
+    type Err = ParseCrossLinkError;
 
-        self.cross_link_num += 1;
-        Ok(self)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CrossLink::parse(s)
     }
+}
 
-    /// Perform a walk on the atoms and bonds of the amino acid.
+impl CrossLink {
+    /// Parse a cross-link from a textual notation.
     ///
-    /// The follower must have been initialized with a head. It will finish its
-    /// walk on the β carbon, without visiting the atoms part of the peptidic
-    /// bond.
+    /// Both the notation listing the bridged residues, as found in UniProt
+    /// annotations (e.g. `Cys3-Cys15`), and the notation produced by the
+    /// `Display` implementation (e.g. `Cystine(3-15)`) are supported. With
+    /// the former, the kind of cross-link is inferred from the residues, so
+    /// thioethers, which can be formed with most residues, can only be
+    /// parsed from the latter.
     ///
-    /// # Note
-    /// This is implemented as an associated method rather than a function to
-    /// make the borrow-checker happy about us borrowing `self.sequence` mutably
-    /// and `self.cross_links` immutably.
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::CrossLink;
     ///
-    fn visit_residue<F: Follower>(
-        aa: AminoAcid,
-        follower: &mut F,
-        index: u16,
-        cross_links: &HashMap<u16, (Rnum, CrossLink)>,
-        cyclization: &Cyclization,
-    ) -> Result<(), Error> {
-        const CARBON_TH2: AtomKind = AtomKind::Bracket {
-            symbol: BracketSymbol::Element(Element::C),
-            configuration: Some(Configuration::TH2),
-            hcount: Some(VirtualHydrogen::H1),
-            isotope: None,
-            charge: None,
-            map: None,
+    /// assert_eq!(CrossLink::parse("Cys3-Cys15"), Ok(CrossLink::Cystine(3, 15)));
+    /// assert_eq!(CrossLink::parse("Ser2-Cys7"), Ok(CrossLink::Lan(2, 7)));
+    /// assert_eq!(CrossLink::parse("MeLan(2-7)"), Ok(CrossLink::MeLan(2, 7)));
+    /// ```
+    pub fn parse(s: &str) -> Result<Self, ParseCrossLinkError> {
+        let index = |n: &str| match n.parse::<u16>() {
+            Ok(0) | Err(_) => Err(ParseCrossLinkError),
+            Ok(i) => Ok(i),
         };
-        const CARBON_TH1: AtomKind = AtomKind::Bracket {
-            symbol: BracketSymbol::Element(Element::C),
-            configuration: Some(Configuration::TH1),
-            hcount: Some(VirtualHydrogen::H1),
-            isotope: None,
-            charge: None,
-            map: None,
+
+        // notation produced by `Display`, such as `Cystine(3-15)`
+        if let Some((name, rest)) = s.split_once('(') {
+            let (i, j) = rest
+                .strip_suffix(')')
+                .and_then(|r| r.split_once('-'))
+                .ok_or(ParseCrossLinkError)?;
+            let (i, j) = (index(i)?, index(j)?);
+            return match name {
+                "Cystine" => Ok(CrossLink::Cystine(i, j)),
+                "Lan" => Ok(CrossLink::Lan(i, j)),
+                "MeLan" => Ok(CrossLink::MeLan(i, j)),
+                "Lal" => Ok(CrossLink::Lal(i, j)),
+                "SeLan" => Ok(CrossLink::SeLan(i, j)),
+                "Diselenide" => Ok(CrossLink::Diselenide(i, j)),
+                "SelenylSulfide" => Ok(CrossLink::SelenylSulfide(i, j)),
+                "Thioether" => Ok(CrossLink::Thioether(i, j)),
+                "HydrocarbonStaple" => Ok(CrossLink::HydrocarbonStaple(i, j)),
+                "HisTyr" => Ok(CrossLink::HisTyr(i, j)),
+                _ => Err(ParseCrossLinkError),
+            };
+        }
+
+        // notation listing the residues, such as `Cys3-Cys15`
+        let residue = |r: &str| {
+            let split = r
+                .find(|c: char| c.is_ascii_digit())
+                .ok_or(ParseCrossLinkError)?;
+            let aa = AminoAcid::from_code(&r[..split]).map_err(|_| ParseCrossLinkError)?;
+            Ok((aa, index(&r[split..])?))
         };
+        let (left, right) = s.split_once('-').ok_or(ParseCrossLinkError)?;
+        let ((aa_i, i), (aa_j, j)) = (residue(left)?, residue(right)?);
+        [
+            CrossLink::Cystine(i, j),
+            CrossLink::Lan(i, j),
+            CrossLink::MeLan(i, j),
+            CrossLink::Lal(i, j),
+            CrossLink::SeLan(i, j),
+            CrossLink::Diselenide(i, j),
+            CrossLink::SelenylSulfide(i, j),
+            CrossLink::HisTyr(i, j),
+        ]
+        .into_iter()
+        .find(|cross_link| cross_link.is_compatible(aa_i, aa_j))
+        .ok_or(ParseCrossLinkError)
+    }
 
-        // only some amino-acids can build a cross-link
-        if let Some((_, cross_link)) = cross_links.get(&index) {
-            match aa {
-                AminoAcid::Thr => (),
-                AminoAcid::Cys => (),
-                AminoAcid::Ser => (),
-                AminoAcid::Lys => (),
-                other => {
-                    return Err(Error::InvalidCrossLink(index, other, *cross_link));
-                }
+    /// Check whether the cross-link can bridge the given residues.
+    ///
+    /// The residues can be given in any order.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// assert!(CrossLink::Lan(1, 2).is_compatible(Ser, Cys));
+    /// assert!(!CrossLink::Lan(1, 2).is_compatible(Ser, Ser));
+    /// assert!(!CrossLink::Cystine(1, 2).is_compatible(Cys, Ala));
+    /// ```
+    pub fn is_compatible(&self, aa_i: AminoAcid, aa_j: AminoAcid) -> bool {
+        match (self.residue_types(), self) {
+            (Some((x, y)), _) => (aa_i, aa_j) == (x, y) || (aa_i, aa_j) == (y, x),
+            (None, CrossLink::HydrocarbonStaple(_, _)) => {
+                Self::is_stapling(aa_i) && Self::is_stapling(aa_j)
             }
+            (None, CrossLink::Custom { .. }) => Self::is_linkable(aa_i) && Self::is_linkable(aa_j),
+            (None, _) => aa_i == AminoAcid::Cys && Self::has_alpha_hydrogen(aa_j),
         }
+    }
 
-        // only some amino-acids can cyclized
-        match cyclization {
-            Cyclization::Lasso(n) if n == &index => match aa {
-                AminoAcid::Asp => (),
-                AminoAcid::Glu => (),
-                other => {
-                    return Err(Error::InvalidCyclization(index, other, *cyclization));
-                }
-            },
-            _ => (),
+    /// Check whether the residue at `index` can be involved in the cross-link.
+    fn accepts(&self, index: u16, aa: AminoAcid) -> bool {
+        match (self.residue_types(), self) {
+            (Some((x, y)), _) => aa == x || aa == y,
+            (None, CrossLink::Thioether(i, _)) if *i == index => aa == AminoAcid::Cys,
+            (None, CrossLink::HydrocarbonStaple(_, _)) => Self::is_stapling(aa),
+            (None, CrossLink::Custom { .. }) => Self::is_linkable(aa),
+            (None, _) => Self::has_alpha_hydrogen(aa),
         }
+    }
 
-        // visit the alpha carbon and the residue
-        match aa {
-            AminoAcid::Dhb => {
-                // alpha carbon
-                follower.extend(BondKind::Up, AtomKind::Aliphatic(Aliphatic::C));
-                // residue
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Down, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(2);
-            }
+    /// Check that the residues bridged by the cross-link form a valid pair.
+    ///
+    /// Residues that are not accepted on their own are left to be reported
+    /// when they are visited. Otherwise, an incompatible pair is reported
+    /// on the second residue of the cross-link.
+    fn check_pair(&self, aa_i: AminoAcid, aa_j: AminoAcid) -> Result<(), Error> {
+        let (i, j) = self.residues();
+        if self.accepts(i, aa_i) && self.accepts(j, aa_j) && !self.is_compatible(aa_i, aa_j) {
+            return Err(Error::InvalidCrossLink(j, aa_j, *self));
+        }
+        Ok(())
+    }
 
-            AminoAcid::Dha => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                // residue
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(1);
+    /// Describe the residues accepted at `index` by the cross-link.
+    fn expected(&self, index: u16) -> String {
+        match (self.residue_types(), self) {
+            (Some((x, y)), _) if x == y => x.as_code().to_string(),
+            (Some((x, y)), _) => format!("{} or {}", x.as_code(), y.as_code()),
+            (None, CrossLink::Thioether(i, _)) if *i == index => {
+                AminoAcid::Cys.as_code().to_string()
             }
+            (None, CrossLink::HydrocarbonStaple(_, _)) => String::from("S5 or R8"),
+            (None, CrossLink::Custom { .. }) => String::from("Cys, Sec, Lys, Ser, Thr or Pyl"),
+            (None, _) => String::from("a residue with an α hydrogen"),
+        }
+    }
 
-            AminoAcid::Pyl => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, CARBON_TH1);
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, CARBON_TH1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::N));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.pop(11);
-            }
+    /// Get the residues bridged by the cross-link.
+    ///
+    /// Returns `None` for thioethers, which can be formed with the α carbon
+    /// of most residues, for hydrocarbon staples, which can be formed with
+    /// any pair of stapling residues, and for custom cross-links.
+    fn residue_types(&self) -> Option<(AminoAcid, AminoAcid)> {
+        match self {
+            CrossLink::Cystine(_, _) => Some((AminoAcid::Cys, AminoAcid::Cys)),
+            CrossLink::Lan(_, _) => Some((AminoAcid::Cys, AminoAcid::Ser)),
+            CrossLink::MeLan(_, _) => Some((AminoAcid::Cys, AminoAcid::Thr)),
+            CrossLink::Lal(_, _) => Some((AminoAcid::Lys, AminoAcid::Ser)),
+            CrossLink::SeLan(_, _) => Some((AminoAcid::Sec, AminoAcid::Ser)),
+            CrossLink::Diselenide(_, _) => Some((AminoAcid::Sec, AminoAcid::Sec)),
+            CrossLink::SelenylSulfide(_, _) => Some((AminoAcid::Sec, AminoAcid::Cys)),
+            CrossLink::HisTyr(_, _) => Some((AminoAcid::His, AminoAcid::Tyr)),
+            CrossLink::Thioether(_, _) => None,
+            CrossLink::HydrocarbonStaple(_, _) => None,
+            CrossLink::Custom { .. } => None,
+        }
+    }
 
-            AminoAcid::Gly => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-            }
+    /// Check whether a residue has an alkene that can form a hydrocarbon staple.
+    fn is_stapling(aa: AminoAcid) -> bool {
+        matches!(aa, AminoAcid::S5 | AminoAcid::R8)
+    }
 
-            AminoAcid::Ala => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(1);
-            }
+    /// Check whether the side chain of a residue can carry a custom linker.
+    fn is_linkable(aa: AminoAcid) -> bool {
+        matches!(
+            aa,
+            AminoAcid::Cys
+                | AminoAcid::Sec
+                | AminoAcid::Lys
+                | AminoAcid::Ser
+                | AminoAcid::Thr
+                | AminoAcid::Pyl
+        )
+    }
 
-            AminoAcid::Pro => {
-                // proline ring
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH1);
-                follower.join(BondKind::Elided, Rnum::R1);
-            }
+    /// Check whether the α carbon of a residue carries a hydrogen.
+    fn has_alpha_hydrogen(aa: AminoAcid) -> bool {
+        !matches!(
+            aa,
+            AminoAcid::Dha
+                | AminoAcid::Dhb
+                | AminoAcid::BAla
+                | AminoAcid::Gaba
+                | AminoAcid::S5
+                | AminoAcid::R8
+                | AminoAcid::Xaa
+        )
+    }
 
-            AminoAcid::Val => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(2);
-            }
+    /// Get the indices of the residues involved in the cross-link.
+    fn residues(&self) -> (u16, u16) {
+        match *self {
+            CrossLink::Cystine(i, j)
+            | CrossLink::Lan(i, j)
+            | CrossLink::MeLan(i, j)
+            | CrossLink::Lal(i, j)
+            | CrossLink::SeLan(i, j)
+            | CrossLink::Diselenide(i, j)
+            | CrossLink::SelenylSulfide(i, j)
+            | CrossLink::Thioether(i, j)
+            | CrossLink::HydrocarbonStaple(i, j)
+            | CrossLink::HisTyr(i, j)
+            | CrossLink::Custom { i, j, .. } => (i, j),
+        }
+    }
 
-            AminoAcid::Leu => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(3);
-            }
+    /// Check whether the cross-link is the same regardless of residue order.
+    ///
+    /// Thioethers bond the sulfur of the first residue to the α carbon of
+    /// the second one, and custom linkers are not always symmetric, so the
+    /// order of their residues matters.
+    fn is_symmetric(&self) -> bool {
+        !matches!(self, CrossLink::Thioether(_, _) | CrossLink::Custom { .. })
+    }
 
-            AminoAcid::Met => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(4);
-            }
+    /// Get the cross-link with its residues in canonical order.
+    ///
+    /// The residues of symmetric cross-links are sorted by index, so that
+    /// the same bond is always stored the same way.
+    fn canonical(&self) -> CrossLink {
+        match self.residues() {
+            (i, j) if i > j && self.is_symmetric() => self.with_residues(j, i),
+            _ => *self,
+        }
+    }
 
-            AminoAcid::Phe => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.pop(7);
+    /// Check that the linker of a custom cross-link is valid.
+    fn check_linker(&self) -> Result<(), Error> {
+        match self {
+            CrossLink::Custom { linker, .. } if linker::ring_count(linker).is_none() => {
+                Err(Error::InvalidLinker(linker))
             }
+            _ => Ok(()),
+        }
+    }
 
-            AminoAcid::Tyr => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.pop(7);
-            }
+    /// Get a copy of the cross-link between different residues.
+    fn with_residues(&self, i: u16, j: u16) -> CrossLink {
+        match self {
+            CrossLink::Cystine(_, _) => CrossLink::Cystine(i, j),
+            CrossLink::Lan(_, _) => CrossLink::Lan(i, j),
+            CrossLink::MeLan(_, _) => CrossLink::MeLan(i, j),
+            CrossLink::Lal(_, _) => CrossLink::Lal(i, j),
+            CrossLink::SeLan(_, _) => CrossLink::SeLan(i, j),
+            CrossLink::Diselenide(_, _) => CrossLink::Diselenide(i, j),
+            CrossLink::SelenylSulfide(_, _) => CrossLink::SelenylSulfide(i, j),
+            CrossLink::Thioether(_, _) => CrossLink::Thioether(i, j),
+            CrossLink::HydrocarbonStaple(_, _) => CrossLink::HydrocarbonStaple(i, j),
+            CrossLink::HisTyr(_, _) => CrossLink::HisTyr(i, j),
+            CrossLink::Custom { linker, .. } => CrossLink::Custom { i, j, linker },
+        }
+    }
+}
 
-            AminoAcid::Cys => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                match cross_links.get(&index) {
-                    // no cross-link, just add the thiol group.
-                    None => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
-                        follower.pop(2);
-                    }
-                    // cystine, add the first sulfur, the other Cys will add the second one.
-                    Some((rnum, CrossLink::Cystine(_, _))) => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
-                        follower.join(BondKind::Elided, rnum.clone());
-                        follower.pop(2);
-                    }
-                    // lanthionine, the sulfur comes from the cysteine
-                    Some((rnum, CrossLink::Lan(_, _))) => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
-                        follower.join(BondKind::Elided, rnum.clone());
-                        follower.pop(2);
-                    }
-                    // methyllanthionine, add the sulfur, the threonine won't add the hydroxy group
-                    Some((rnum, CrossLink::MeLan(_, _))) => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
-                        follower.join(BondKind::Elided, rnum.clone());
-                        follower.pop(2);
-                    }
-                    // other cross-links are not permitted
-                    Some((_, other)) => {
-                        return Err(Error::InvalidCrossLink(index, aa, *other));
-                    }
-                }
-            }
+/// A chemical modification of a single amino-acid residue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Modification {
+    /// Protonation of an ionizable side chain.
+    ///
+    /// This yields an imidazolium group for L-histidine, an ammonium group
+    /// for L-lysine and its homologues, and a guanidinium group for
+    /// L-arginine and L-homoarginine.
+    Protonated,
 
-            AminoAcid::Ser => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                match cross_links.get(&index) {
-                    // no cross-link, just add the alcohol
-                    None => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
-                        follower.pop(2);
-                    }
-                    // lanthionine or lysinoalanine, bridge with the sulfur or nitrogen atom
-                    Some((rnum, CrossLink::Lan(_, _))) | Some((rnum, CrossLink::Lal(_, _))) => {
-                        follower.join(BondKind::Elided, rnum.clone());
-                        follower.pop(1);
-                    }
-                    // other cross-links are not permitted
-                    Some((_, other)) => {
-                        return Err(Error::InvalidCrossLink(index, aa, *other));
-                    }
-                }
-            }
+    /// Oxidation of a side chain sulfur with the given number of oxygens.
+    ///
+    /// The thiol of L-cysteine can be oxidized to a sulfenic (1), sulfinic
+    /// (2) or sulfonic (3) acid. The thioether of L-methionine, or of a
+    /// L-cysteine involved in a thioether cross-link, can be oxidized to a
+    /// sulfoxide (1) or a sulfone (2). The configuration of the sulfur of
+    /// a sulfoxide is left unspecified.
+    Oxidized(u8),
 
-            AminoAcid::Sec => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(
-                    BondKind::Elided,
-                    AtomKind::Bracket {
-                        symbol: BracketSymbol::Element(Element::Se),
-                        isotope: None,
-                        configuration: None,
-                        hcount: None,
-                        charge: None,
-                        map: None,
-                    },
-                );
-                follower.pop(2);
-            }
+    /// Acetylation of a side chain hydroxyl or amine.
+    ///
+    /// This yields an acetate ester for L-serine and L-threonine, and an
+    /// acetamide for L-lysine. The acetylated group cannot be involved in
+    /// a cross-link or a cyclization.
+    Acetyl,
 
-            AminoAcid::Thr => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                match cross_links.get(&index) {
-                    None => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                        follower.pop(1);
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
-                        follower.pop(2);
-                    }
-                    Some((rnum, CrossLink::MeLan(_, _))) => {
-                        follower.join(BondKind::Elided, rnum.clone());
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                        follower.pop(2);
-                    }
-                    Some((_, other)) => {
-                        return Err(Error::InvalidCrossLink(index, aa, *other));
-                    }
-                }
-            }
+    /// Phosphorylation of a side chain hydroxyl, carboxyl or amine.
+    ///
+    /// This yields a phosphate ester for L-serine, L-threonine and
+    /// L-tyrosine, a phosphoramidate on the τ nitrogen of L-histidine, and
+    /// an acyl phosphate for L-aspartic acid, the latter two being found in
+    /// two-component signaling systems. The phosphate is written in its
+    /// neutral form.
+    Phospho,
 
-            AminoAcid::Asn => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.pop(3);
-            }
+    /// Citrullination of the guanidine group of L-arginine.
+    ///
+    /// The imine of the guanidine is replaced by a carbonyl, which yields
+    /// the ureido group of L-citrulline, as produced by peptidylarginine
+    /// deiminases.
+    Citrullination,
 
-            AminoAcid::Gln => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.pop(4);
-            }
+    /// Deamidation of the side chain amide of L-asparagine or L-glutamine.
+    ///
+    /// The residue is written as the L-aspartic acid or L-glutamic acid it
+    /// yields. Deamidated asparagine can also be linked through its side
+    /// chain with `Protein::iso_aspartate`, as the isoaspartate formed
+    /// through the succinimide intermediate.
+    Deamidation,
+}
 
-            AminoAcid::Arg => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::N));
-                follower.pop(1);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.pop(6);
-            }
+/// A chemical modification of the N-terminal amine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NTerm {
+    /// A free primary amine.
+    #[default]
+    Free,
 
-            AminoAcid::Lys => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                match cross_links.get(&index) {
-                    // no cross-link, nothing to do
-                    None => (),
-                    // lysinoalanine, bridge with the other residue
-                    Some((rnum, CrossLink::Lal(_, _))) => {
-                        follower.join(BondKind::Elided, rnum.clone());
-                    }
-                    // other cross-links are not permitted
-                    Some((_, other)) => {
-                        return Err(Error::InvalidCrossLink(index, aa, *other));
-                    }
-                }
-                follower.pop(5);
-            }
+    /// An acetamide, as found in many eukaryotic proteins.
+    Acetyl,
 
-            AminoAcid::His => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::N));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.pop(6);
-            }
+    /// A formamide, as found in the N-formylmethionine of bacterial
+    /// proteins and in peptides such as gramicidin A.
+    Formyl,
 
-            AminoAcid::Asp => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                match cyclization {
-                    Cyclization::Lasso(n) if n == &index => {
-                        follower.join(BondKind::Elided, Rnum::R0);
-                    }
-                    _ => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
-                        follower.pop(1);
+    /// A secondary amine carrying a methyl group, as found in N-terminally
+    /// methylated proteins such as the histone H2B of some organisms.
+    ///
+    /// Like the free amine, the methylated amine cannot be involved in a
+    /// cyclization.
+    Methyl,
+
+    /// An amide with a linear fatty acid, as found in lipopeptides.
+    ///
+    /// `carbons` is the number of carbons of the acyl chain, including the
+    /// carbonyl carbon. When `hydroxy` is set, the chain carries a hydroxyl
+    /// group on its β carbon, as found in surfactin or iturin. The
+    /// configuration of the β carbon is left unspecified.
+    FattyAcyl { carbons: u8, hydroxy: bool },
+
+    /// A lactam formed by the N-terminal amine with the side chain of the
+    /// first residue, as found in many peptide hormones.
+    ///
+    /// Pyroglutamate arises from the cyclization of an N-terminal
+    /// L-glutamine, releasing ammonia, or L-glutamic acid, releasing water.
+    Pyroglutamate,
+}
+
+impl NTerm {
+    /// Get the number of carbons and the β-hydroxylation of the acyl chain.
+    fn acyl_chain(&self) -> Option<(u8, bool)> {
+        match *self {
+            NTerm::Free | NTerm::Methyl | NTerm::Pyroglutamate => None,
+            NTerm::Acetyl => Some((2, false)),
+            NTerm::Formyl => Some((1, false)),
+            NTerm::FattyAcyl { carbons, hydroxy } => Some((carbons, hydroxy)),
+        }
+    }
+}
+
+/// A chemical modification of the C-terminal carboxyl group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CTerm {
+    /// A free carboxylic acid.
+    #[default]
+    Free,
+
+    /// A primary carboxamide, as found in many peptide hormones.
+    Amide,
+
+    /// A hydrazide, used as a thioester precursor for native chemical
+    /// ligation.
+    Hydrazide,
+
+    /// A methyl ester, used to protect the C-terminus in peptide synthesis.
+    MethylEster,
+
+    /// An N-methylamide, used with an N-terminal acetyl group to cap
+    /// single residues in force field parameterization.
+    MethylAmide,
+
+    /// An amide with the phosphoethanolamine of a GPI anchor.
+    ///
+    /// Only the ethanolamine phosphate bonded to the ω residue is written,
+    /// as a phosphoric acid: the glycan core and the lipid moiety of the
+    /// glycosylphosphatidylinositol anchor are out of scope.
+    GpiEthanolamine,
+}
+
+/// The geometry of the double bond of a dehydroamino acid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Geometry {
+    /// The (Z) isomer, with the amine and the side chain on the same side.
+    ///
+    /// This is the most common isomer of dehydrobutyrine in lanthipeptides.
+    #[default]
+    Z,
+
+    /// The (E) isomer, with the amine and the side chain on opposite sides.
+    E,
+}
+
+/// The configuration of the β carbon of a methyllanthionine.
+///
+/// This is the stereocenter carrying the methyl group, derived from the
+/// β carbon of the threonine involved in the cross-link.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeLanConfiguration {
+    /// The (3S) configuration.
+    ///
+    /// This is the configuration found in the methyllanthionines of most
+    /// lanthipeptides, such as nisin.
+    #[default]
+    S,
+
+    /// The (3R) configuration.
+    ///
+    /// This is the configuration found in some non-canonical
+    /// methyllanthionines, such as in cytolysin.
+    R,
+}
+
+/// A peptide cyclization mechanism.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cyclization {
+    /// No cyclization, resulting in a linear peptide.
+    None,
+
+    /// Head-to-tail cyclization, resulting in an homodetic cyclic peptide.
+    HeadToTail,
+
+    /// Head-to-sidechain cyclization, resulting in a lasso peptide.
+    ///
+    /// This cyclization process forms an isopeptidic bond between the
+    /// amine group of the N-terminal amino acid and the carboxyl group of
+    /// an Asp or Glu residue.
+    Lasso(u16),
+
+    /// Sidechain-to-tail cyclization, resulting in a branched cyclic peptide.
+    ///
+    /// This cyclization process forms an isopeptidic bond between the
+    /// carboxyl group of the C-terminal amino acid and the ε-amine group of
+    /// a Lys residue, as found in bacitracin, or the side chain amine of one
+    /// of its Orn, Dab or Dap homologues, as found in polymyxins.
+    Branched(u16),
+}
+
+impl Cyclization {
+    /// Check whether the cyclization involves the N-terminal amine.
+    fn n_terminal(&self) -> bool {
+        matches!(self, Cyclization::HeadToTail | Cyclization::Lasso(_))
+    }
+
+    /// Check whether the cyclization involves the C-terminal carboxyl group.
+    fn c_terminal(&self) -> bool {
+        matches!(self, Cyclization::HeadToTail | Cyclization::Branched(_))
+    }
+
+    /// Get the residue whose side chain is involved in the cyclization.
+    fn side_chain(&self) -> Option<u16> {
+        match self {
+            Cyclization::Lasso(i) | Cyclization::Branched(i) => Some(*i),
+            Cyclization::None | Cyclization::HeadToTail => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Cyclization {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Cyclization::None => write!(f, "None"),
+            Cyclization::HeadToTail => write!(f, "HeadToTail"),
+            Cyclization::Lasso(i) => write!(f, "Lasso(1-{})", i),
+            Cyclization::Branched(i) => write!(f, "Branched({})", i),
+        }
+    }
+}
+
+/// The kind of ring formed by the backbone of a peptide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CyclizationKind {
+    /// A linear peptide, without any ring between residues.
+    Linear,
+
+    /// A cyclic peptide whose rings only contain peptide bonds.
+    Homodetic,
+
+    /// A cyclic peptide with rings containing other bonds, such as
+    /// disulfide bonds, thioethers, or isopeptide bonds with a side chain.
+    Heterodetic,
+}
+
+impl Default for Cyclization {
+    fn default() -> Self {
+        Cyclization::None
+    }
+}
+
+/// A strategy for numbering the ring closures of cross-links.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RingNumbering {
+    /// Number the cross-links sequentially, in the order they were added.
+    ///
+    /// Each cross-link gets its own ring closure number, which limits the
+    /// number of cross-links in a protein to 97.
+    #[default]
+    Sequential,
+
+    /// Reuse ring closure numbers as soon as a ring has been closed.
+    ///
+    /// A ring closure number can be reused once both of its endpoints have
+    /// been visited, so this strategy only requires as many numbers as the
+    /// maximum number of cross-links open at a given position.
+    Reuse,
+}
+
+/// A strategy for rendering unknown residues.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Masking {
+    /// Render the α carbon of unknown residues as a wildcard atom.
+    ///
+    /// This marks the gaps of the sequence explicitly, but the atoms of
+    /// the unknown residues are not accounted for in masses and formulas.
+    #[default]
+    Wildcard,
+
+    /// Render unknown residues as a glycine-like spacer.
+    Spacer,
+}
+
+/// A single step of a walk on the atoms and bonds of a protein.
+///
+/// The walk starts on a root atom, and proceeds in depth-first order,
+/// in the order the atoms appear in the SMILES string of the protein.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AtomEvent {
+    /// Start the walk on a new atom.
+    Root(Atom),
+    /// Add a new atom bonded to the current atom, and move to it.
+    Extend(Bond, Atom),
+    /// Open or close a ring on the current atom, with the given number.
+    Join(Bond, u16),
+    /// Move back to a previously visited atom, by the given depth.
+    Pop(usize),
+}
+
+/// The elements, formal charges and bonds of the heavy atoms of a protein.
+///
+/// Atoms are indexed in the order they are visited, and each bond is given
+/// once as a pair of atom indices with its kind. The element of a wildcard
+/// atom is `None`.
+pub type AtomTable = (Vec<Option<Element>>, Vec<i8>, Vec<(usize, usize, BondKind)>);
+
+/// The number of atoms of each element, indexed by element symbol.
+///
+/// Counts are signed, since a contribution to a formula can remove atoms,
+/// such as the hydrogens lost when forming a disulfide bond.
+pub type Formula = BTreeMap<&'static str, i32>;
+
+/// An atom visited during a walk on a protein.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Atom {
+    /// The symbol of the element, or `None` for a wildcard atom.
+    pub element: Option<&'static str>,
+    /// Whether the atom is part of an aromatic ring.
+    pub aromatic: bool,
+    /// The number of hydrogens, if given explicitly.
+    ///
+    /// Atoms without an explicit hydrogen count have implicit hydrogens
+    /// determined from their default valences, following SMILES rules.
+    pub hydrogens: Option<u8>,
+    /// The formal charge of the atom.
+    pub charge: i8,
+    /// The configuration of the atom, if it is a stereocenter.
+    pub chirality: Option<Chirality>,
+    /// The atom map number of the atom, if any.
+    pub map: Option<u16>,
+}
+
+impl Atom {
+    /// Create a new atom from the atom kind of a walk.
+    fn from_kind(kind: &AtomKind) -> Self {
+        let mut atom = Atom {
+            element: mass::element(kind).map(|e| mass::symbol(&e)),
+            aromatic: false,
+            hydrogens: None,
+            charge: 0,
+            chirality: None,
+            map: None,
+        };
+        match kind {
+            AtomKind::Star | AtomKind::Aliphatic(_) => (),
+            AtomKind::Aromatic(_) => atom.aromatic = true,
+            AtomKind::Bracket {
+                symbol,
+                configuration,
+                hcount,
+                charge,
+                map,
+                ..
+            } => {
+                atom.aromatic = matches!(symbol, BracketSymbol::Aromatic(_));
+                atom.hydrogens = Some(match hcount {
+                    None | Some(VirtualHydrogen::H0) => 0,
+                    Some(VirtualHydrogen::H1) => 1,
+                    Some(VirtualHydrogen::H2) => 2,
+                    Some(VirtualHydrogen::H3) => 3,
+                    Some(VirtualHydrogen::H4) => 4,
+                    Some(VirtualHydrogen::H5) => 5,
+                    Some(VirtualHydrogen::H6) => 6,
+                    Some(VirtualHydrogen::H7) => 7,
+                    Some(VirtualHydrogen::H8) => 8,
+                    Some(VirtualHydrogen::H9) => 9,
+                });
+                atom.charge = charge.as_ref().map(|c| c.into()).unwrap_or(0);
+                atom.chirality = configuration.as_ref().map(|c| match c {
+                    Configuration::TH1 => Chirality::Anticlockwise,
+                    Configuration::TH2 => Chirality::Clockwise,
+                    other => unreachable!("unsupported configuration: {:?}", other),
+                });
+                atom.map = map.as_ref().map(|n| n.into());
+            }
+        }
+        atom
+    }
+}
+
+/// The configuration of a tetrahedral stereocenter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chirality {
+    /// Neighbors are listed anticlockwise, written `@` in SMILES.
+    Anticlockwise,
+    /// Neighbors are listed clockwise, written `@@` in SMILES.
+    Clockwise,
+}
+
+/// The configuration of the α carbon of a residue, in Fischer notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StereoLabel {
+    /// An α carbon with the configuration of L-amino acids.
+    L,
+    /// An α carbon with the configuration of D-amino acids.
+    D,
+    /// An α carbon which is not a stereocenter, such as in glycine.
+    Achiral,
+}
+
+/// A bond visited during a walk on a protein.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bond {
+    /// An implicit bond, single or aromatic depending on its atoms.
+    Implicit,
+    /// An explicit single bond.
+    Single,
+    /// A double bond.
+    Double,
+    /// A triple bond.
+    Triple,
+    /// A quadruple bond.
+    Quadruple,
+    /// An explicit aromatic bond.
+    Aromatic,
+    /// A single bond pointing up from the previous atom.
+    Up,
+    /// A single bond pointing down from the previous atom.
+    Down,
+}
+
+impl Bond {
+    /// Create a new bond from the bond kind of a walk.
+    fn from_kind(kind: &BondKind) -> Self {
+        match kind {
+            BondKind::Elided => Bond::Implicit,
+            BondKind::Single => Bond::Single,
+            BondKind::Double => Bond::Double,
+            BondKind::Triple => Bond::Triple,
+            BondKind::Quadruple => Bond::Quadruple,
+            BondKind::Aromatic => Bond::Aromatic,
+            BondKind::Up => Bond::Up,
+            BondKind::Down => Bond::Down,
+        }
+    }
+}
+
+/// The modifications of a protein used while visiting its residues.
+struct Context<'p> {
+    cross_links: &'p HashMap<u16, (Rnum, CrossLink)>,
+    modifications: &'p HashMap<u16, Vec<Modification>>,
+    gamma_glutamyls: &'p HashSet<u16>,
+    iso_aspartyls: &'p HashSet<u16>,
+    cis_prolines: &'p HashSet<u16>,
+    d_residues: &'p HashSet<u16>,
+    beta_residues: &'p HashSet<u16>,
+    masking: Masking,
+    geometries: &'p HashMap<u16, Geometry>,
+    melan_configurations: &'p HashMap<u16, MeLanConfiguration>,
+    cyclizations: &'p [(Rnum, Cyclization)],
+    linker_rnum: u16,
+    full_stereo: bool,
+    residue_maps: bool,
+    kekulize: bool,
+    pyroglutamate: bool,
+}
+
+/// A protein abstracted as a modified peptide.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Protein<S> {
+    cyclizations: Vec<Cyclization>,
+
+    cross_links: HashMap<u16, (u16, CrossLink)>,
+    cross_link_num: u16,
+    ring_numbering: RingNumbering,
+
+    modifications: HashMap<u16, Vec<Modification>>,
+    thioamides: HashSet<u16>,
+    gamma_glutamyls: HashSet<u16>,
+    iso_aspartyls: HashSet<u16>,
+    cis_prolines: HashSet<u16>,
+    d_residues: HashSet<u16>,
+    beta_residues: HashSet<u16>,
+    masking: Masking,
+    geometries: HashMap<u16, Geometry>,
+    melan_configurations: HashMap<u16, MeLanConfiguration>,
+    n_term: NTerm,
+    c_term: CTerm,
+
+    full_stereo: bool,
+    residue_maps: bool,
+    kekulize: bool,
+    explicit_hydrogens: bool,
+    zwitterion: bool,
+
+    sequence: S,
+}
+
+impl<S> Protein<S> {
+    /// Mark whether the peptide is cyclized through a known cyclization mechanism.
+    ///
+    /// This replaces any cyclization previously added to the peptide.
+    pub fn cyclization(&mut self, cyclization: Cyclization) -> &mut Self {
+        self.cyclizations.clear();
+        if cyclization != Cyclization::None {
+            self.cyclizations.push(cyclization);
+        }
+        self
+    }
+
+    /// Add a cyclization to the peptide, keeping the existing ones.
+    ///
+    /// Independent cyclizations can be combined to obtain bicyclic peptides,
+    /// as long as each terminus is involved in at most one of them.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Cyclization;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly, Asp, Lys, Gly]);
+    /// prot.add_cyclization(Cyclization::Lasso(2)).unwrap();
+    /// prot.add_cyclization(Cyclization::Branched(3)).unwrap();
+    /// assert!(prot.validate().is_ok());
+    /// ```
+    pub fn add_cyclization(&mut self, cyclization: Cyclization) -> Result<&mut Self, Error> {
+        if cyclization == Cyclization::None {
+            return Ok(self);
+        }
+        let conflict = self.cyclizations.iter().any(|other| {
+            (cyclization.n_terminal() && other.n_terminal())
+                || (cyclization.c_terminal() && other.c_terminal())
+        });
+        if conflict {
+            return Err(Error::DuplicateCyclization(cyclization));
+        }
+        self.cyclizations.push(cyclization);
+        Ok(self)
+    }
+
+    /// Classify the rings formed between the residues of the peptide.
+    ///
+    /// Rings contained in a single residue, such as the pyrrolidine of
+    /// proline or a pyroglutamate, are not taken into account.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    /// use proteinogenic::Cyclization;
+    /// use proteinogenic::CyclizationKind;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Gly, Cys]);
+    /// assert_eq!(prot.cyclization_kind(), CyclizationKind::Linear);
+    /// prot.cyclization(Cyclization::HeadToTail);
+    /// assert_eq!(prot.cyclization_kind(), CyclizationKind::Homodetic);
+    /// prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+    /// assert_eq!(prot.cyclization_kind(), CyclizationKind::Heterodetic);
+    /// ```
+    pub fn cyclization_kind(&self) -> CyclizationKind {
+        let side_chain = self.cyclizations.iter().any(|c| c.side_chain().is_some());
+        if side_chain || !self.cross_links.is_empty() {
+            CyclizationKind::Heterodetic
+        } else if self.cyclizations.contains(&Cyclization::HeadToTail) {
+            CyclizationKind::Homodetic
+        } else {
+            CyclizationKind::Linear
+        }
+    }
+
+    /// Assign a ring closure number to each cyclization.
+    ///
+    /// The first cyclization always uses `R0`, while the others use the
+    /// numbers following the ones used by the cross-links, given by
+    /// `cross_links`.
+    fn cyclization_closures(
+        &self,
+        cross_links: &HashMap<u16, (Rnum, CrossLink)>,
+    ) -> Result<Vec<(Rnum, Cyclization)>, Error> {
+        let first = match self.ring_numbering {
+            RingNumbering::Sequential => self.cross_link_num,
+            RingNumbering::Reuse => cross_links
+                .values()
+                .map(|(rnum, _)| rnum_to_u16(rnum))
+                .max()
+                .map_or(3, |num| num + 1),
+        };
+        let mut closures = Vec::with_capacity(self.cyclizations.len());
+        for (i, &cyclization) in self.cyclizations.iter().enumerate() {
+            let rnum = match i {
+                0 => Rnum::R0,
+                _ => Rnum::try_from(first + i as u16 - 1).map_err(|_| Error::TooManyCrossLinks)?,
+            };
+            closures.push((rnum, cyclization));
+        }
+        Ok(closures)
+    }
+
+    /// Get the ring closure numbers available to custom linkers.
+    ///
+    /// Linkers are written while the rings of the other cross-links and of
+    /// the cyclizations are still open, so their own rings are numbered
+    /// after every ring closure number in use.
+    fn linker_closures(
+        &self,
+        cross_links: &HashMap<u16, (Rnum, CrossLink)>,
+        cyclizations: &[(Rnum, Cyclization)],
+    ) -> Result<Range<u16>, Error> {
+        let first = cross_links
+            .values()
+            .map(|(rnum, _)| rnum)
+            .chain(cyclizations.iter().map(|(rnum, _)| rnum))
+            .map(rnum_to_u16)
+            .max()
+            .map_or(3, |num| num.max(2) + 1);
+        let rings = cross_links
+            .values()
+            .filter_map(|(_, cross_link)| match cross_link {
+                CrossLink::Custom { linker, .. } => linker::ring_count(linker),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        if first + rings > 100 {
+            return Err(Error::TooManyCrossLinks);
+        }
+        Ok(first..first + rings)
+    }
+
+    /// Add a cross-link between residues of the peptide.
+    ///
+    /// Residues are indexed from 1. Each residue can be involved in at most
+    /// one cross-link. On error, the cross-links previously added are left
+    /// unchanged. Indices past the end of the sequence are only reported
+    /// when the protein is visited.
+    ///
+    /// Adding a cross-link that is already present is a no-op, even with
+    /// its residues given in reverse order, such as `Cystine(15, 3)` after
+    /// `Cystine(3, 15)`.
+    pub fn cross_link(&mut self, cross_link: CrossLink) -> Result<&mut Self, Error> {
+        cross_link.check_linker()?;
+        let cross_link = cross_link.canonical();
+        let (i, j) = cross_link.residues();
+        if i == 0 || j == 0 {
+            return Err(Error::InvalidResidue(0));
+        }
+        if self.cross_links.get(&i).map(|&(_, c)| c) == Some(cross_link) {
+            return Ok(self);
+        }
+        if i == j || self.cross_links.contains_key(&i) {
+            return Err(Error::DuplicateCrossLink(i));
+        }
+        if self.cross_links.contains_key(&j) {
+            return Err(Error::DuplicateCrossLink(j));
+        }
+
+        let val = (self.cross_link_num, cross_link);
+        self.cross_links.insert(i, val);
+        self.cross_links.insert(j, val);
+        self.cross_link_num += 1;
+        Ok(self)
+    }
+
+    /// Add the disulfide bonds listed in a connectivity string.
+    ///
+    /// The connectivity is a comma-separated list of pairs of cysteine
+    /// indices, such as `3-15,2-40`, each of which is added as a
+    /// `CrossLink::Cystine`. On error, the pair that could not be parsed or
+    /// added is reported, and the cross-links of the protein are left
+    /// unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    /// use proteinogenic::DisulfideError;
+    /// use proteinogenic::Error;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Cys, Gly, Cys, Cys]);
+    /// prot.disulfides("1-4, 2-5").unwrap();
+    /// assert_eq!(prot.smiles().unwrap().matches('3').count(), 2);
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Cys, Cys]);
+    /// assert_eq!(
+    ///     prot.disulfides("1-2,2-3"),
+    ///     Err(DisulfideError::Invalid(CrossLink::Cystine(2, 3), Error::DuplicateCrossLink(2))),
+    /// );
+    /// ```
+    pub fn disulfides(&mut self, connectivity: &str) -> Result<&mut Self, DisulfideError> {
+        let mut cross_links = Vec::new();
+        for pair in connectivity.split(',').map(str::trim) {
+            let index = |n: &str| match n.trim().parse::<u16>() {
+                Ok(0) | Err(_) => None,
+                Ok(i) => Some(i),
+            };
+            let cross_link = pair
+                .split_once('-')
+                .and_then(|(i, j)| Some(CrossLink::Cystine(index(i)?, index(j)?)))
+                .ok_or_else(|| DisulfideError::Parse(pair.to_string()))?;
+            cross_links.push(cross_link);
+        }
+
+        let backup = (self.cross_links.clone(), self.cross_link_num);
+        for cross_link in cross_links {
+            if let Err(e) = self.cross_link(cross_link) {
+                (self.cross_links, self.cross_link_num) = backup;
+                return Err(DisulfideError::Invalid(cross_link, e));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Set the strategy used to number the ring closures of cross-links.
+    pub fn ring_numbering(&mut self, ring_numbering: RingNumbering) -> &mut Self {
+        self.ring_numbering = ring_numbering;
+        self
+    }
+
+    /// Get the ring closure number assigned to the cross-link of a residue.
+    ///
+    /// This is the number used for the cross-link when the protein is
+    /// visited, according to the `RingNumbering` strategy. Returns `None`
+    /// if the residue is not cross-linked, or if no valid ring closure
+    /// number could be assigned to its cross-link.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    /// use proteinogenic::RingNumbering;
+    /// use purr::feature::Rnum;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Cys, Cys, Cys]);
+    /// prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+    /// prot.cross_link(CrossLink::Cystine(3, 4)).unwrap();
+    /// assert_eq!(prot.cross_link_rnum(3), Rnum::try_from(4).ok());
+    ///
+    /// prot.ring_numbering(RingNumbering::Reuse);
+    /// assert_eq!(prot.cross_link_rnum(3), Rnum::try_from(3).ok());
+    /// assert_eq!(prot.cross_link_rnum(5), None);
+    /// ```
+    pub fn cross_link_rnum(&self, index: u16) -> Option<Rnum> {
+        match self.ring_numbering {
+            RingNumbering::Sequential => {
+                let &(num, _) = self.cross_links.get(&index)?;
+                Rnum::try_from(num).ok()
+            }
+            RingNumbering::Reuse => {
+                let mut closures = self.ring_closures().ok()?;
+                closures.remove(&index).map(|(rnum, _)| rnum)
+            }
+        }
+    }
+
+    /// Assign a ring closure number to each cross-linked residue.
+    fn ring_closures(&self) -> Result<HashMap<u16, (Rnum, CrossLink)>, Error> {
+        let mut closures = HashMap::new();
+        match self.ring_numbering {
+            RingNumbering::Sequential => {
+                for (&index, &(num, cross_link)) in self.cross_links.iter() {
+                    let rnum = Rnum::try_from(num).map_err(|_| Error::TooManyCrossLinks)?;
+                    closures.insert(index, (rnum, cross_link));
+                }
+            }
+            RingNumbering::Reuse => {
+                let mut indices = self.cross_links.keys().cloned().collect::<Vec<u16>>();
+                indices.sort_unstable();
+
+                // R0 is used for cyclization, R1 and R2 in residues
+                let mut used = [false; 100];
+                used[..3].copy_from_slice(&[true; 3]);
+
+                let mut open = HashMap::new();
+                for index in indices {
+                    let (_, cross_link) = self.cross_links[&index];
+                    let (i, j) = cross_link.residues();
+                    let partner = if i == index { j } else { i };
+                    match open.remove(&partner) {
+                        // second residue of the cross-link: close the ring
+                        Some(num) => {
+                            used[num] = false;
+                            let rnum = Rnum::try_from(num as u16).unwrap();
+                            closures.insert(index, (rnum, cross_link));
+                        }
+                        // first residue of the cross-link: open a new ring
+                        None => {
+                            let num = used
+                                .iter()
+                                .position(|&u| !u)
+                                .ok_or(Error::TooManyCrossLinks)?;
+                            used[num] = true;
+                            open.insert(index, num);
+                            let rnum = Rnum::try_from(num as u16).unwrap();
+                            closures.insert(index, (rnum, cross_link));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(closures)
+    }
+
+    /// Set whether to render the stereocenters outside of the α carbons.
+    ///
+    /// By default, every defined stereocenter is rendered, including the β
+    /// carbons of L-threonine and L-isoleucine. When disabled, only the
+    /// configuration of the α carbons is rendered.
+    pub fn full_stereo(&mut self, full_stereo: bool) -> &mut Self {
+        self.full_stereo = full_stereo;
+        self
+    }
+
+    /// Set whether to write aromatic rings in their Kekulé form.
+    ///
+    /// When enabled, the atoms of the aromatic rings of L-phenylalanine,
+    /// L-tyrosine, L-tryptophan, L-histidine and their derivatives are
+    /// written as aliphatic atoms with explicit alternating double bonds,
+    /// for compatibility with tools that do not support aromatic SMILES.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Phe]);
+    /// prot.kekulize(true);
+    /// assert_eq!(prot.smiles().unwrap(), "N[C@@H](CC1=CC=CC=C1)C(=O)-O");
+    /// ```
+    pub fn kekulize(&mut self, kekulize: bool) -> &mut Self {
+        self.kekulize = kekulize;
+        self
+    }
+
+    /// Apply a chemical modification to the N-terminal amine.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::NTerm;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly]);
+    /// prot.n_terminus(NTerm::FattyAcyl { carbons: 4, hydroxy: true });
+    /// assert_eq!(prot.smiles().unwrap(), "CC(O)CC(=O)NCC(=O)-O");
+    /// ```
+    pub fn n_terminus(&mut self, n_term: NTerm) -> &mut Self {
+        self.n_term = n_term;
+        self
+    }
+
+    /// Apply a chemical modification to the C-terminal carboxyl group.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CTerm;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly]);
+    /// prot.c_terminus(CTerm::MethylEster);
+    /// assert_eq!(prot.smiles().unwrap(), "NCC(=O)-OC");
+    /// ```
+    pub fn c_terminus(&mut self, c_term: CTerm) -> &mut Self {
+        self.c_term = c_term;
+        self
+    }
+
+    /// Cap both termini with an acetyl group and a carboxamide.
+    ///
+    /// This is a shorthand for `NTerm::Acetyl` and `CTerm::Amide`, which are
+    /// commonly used for the linear precursors of cyclic peptides. Capped
+    /// proteins cannot be cyclized.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly]);
+    /// prot.capped();
+    /// assert_eq!(prot.smiles().unwrap(), "CC(=O)NCC(=O)-N");
+    /// ```
+    pub fn capped(&mut self) -> &mut Self {
+        self.n_terminus(NTerm::Acetyl).c_terminus(CTerm::Amide)
+    }
+
+    /// Cyclize the N-terminal residue into a pyroglutamate.
+    ///
+    /// This is a shorthand for `NTerm::Pyroglutamate`. The first residue of
+    /// the protein must be an L-glutamine or an L-glutamic acid, otherwise
+    /// visiting the protein will fail with `Error::InvalidNTerm`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gln, Gly]);
+    /// prot.pyroglutamate_n_terminus();
+    /// assert_eq!(prot.smiles().unwrap(), "N1[C@@H](CCC1=O)C(=O)NCC(=O)-O");
+    /// ```
+    pub fn pyroglutamate_n_terminus(&mut self) -> &mut Self {
+        self.n_terminus(NTerm::Pyroglutamate)
+    }
+
+    /// Set whether to annotate residues with SMILES atom map numbers.
+    ///
+    /// When enabled, the α carbon of each residue is written as a bracket
+    /// atom carrying the index of the residue as its atom map number, which
+    /// allows reaction tools to track residues through transformations.
+    /// Residues with an index over 999 cannot be annotated.
+    pub fn residue_maps(&mut self, residue_maps: bool) -> &mut Self {
+        self.residue_maps = residue_maps;
+        self
+    }
+
+    /// Write every atom as a bracket atom with an explicit hydrogen count.
+    ///
+    /// The hydrogen counts are computed on the complete molecular graph,
+    /// so that the SMILES can be read without relying on implicit hydrogens
+    /// and always agrees with `Protein::molecular_formula`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly]);
+    /// prot.normalize_hydrogens();
+    /// assert_eq!(prot.smiles().unwrap(), "[NH2][CH2][C](=[O])-[OH]");
+    /// ```
+    pub fn normalize_hydrogens(&mut self) -> &mut Self {
+        self.explicit_hydrogens = true;
+        self
+    }
+
+    /// Set whether to write the protein in its zwitterionic form.
+    ///
+    /// When enabled, the N-terminal amine is protonated and the C-terminal
+    /// carboxylic acid is deprotonated, as found at physiological pH. This
+    /// only applies when both termini are free, or when the N-terminal
+    /// amine is methylated, so it has no effect on head-to-tail cyclized
+    /// or capped proteins.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly]);
+    /// prot.zwitterion(true);
+    /// assert_eq!(prot.smiles().unwrap(), "[NH3+]CC(=O)-[O-]");
+    /// ```
+    pub fn zwitterion(&mut self, zwitterion: bool) -> &mut Self {
+        self.zwitterion = zwitterion;
+        self
+    }
+
+    /// Apply a chemical modification to the residue at the given index.
+    ///
+    /// This replaces any modification previously applied to the residue.
+    pub fn modification(&mut self, index: u16, modification: Modification) -> &mut Self {
+        self.modifications.insert(index, vec![modification]);
+        self
+    }
+
+    /// Add a chemical modification to the residue at the given index.
+    ///
+    /// Modifications are applied to the side chain in the order they were
+    /// added. A deamidation can be followed by a modification of the acid
+    /// it yields, and successive oxidations of a sulfur add up. Any other
+    /// combination targets the same side chain atom, such as an acetylated
+    /// and protonated amine, and is rejected with an
+    /// `Error::InvalidModification` when the protein is visited.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Modification;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Met]);
+    /// prot.add_modification(1, Modification::Oxidized(1));
+    /// prot.add_modification(1, Modification::Oxidized(1));
+    /// assert_eq!(prot.smiles().unwrap(), "N[C@@H](CCS(=O)(=O)C)C(=O)-O");
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Lys]);
+    /// prot.add_modification(1, Modification::Protonated);
+    /// assert!(prot.validate().is_ok());
+    /// prot.add_modification(1, Modification::Protonated);
+    /// assert!(prot.validate().is_err());
+    /// ```
+    pub fn add_modification(&mut self, index: u16, modification: Modification) -> &mut Self {
+        self.modifications
+            .entry(index)
+            .or_default()
+            .push(modification);
+        self
+    }
+
+    /// Replace the peptide bond after the given residue with a thioamide bond.
+    ///
+    /// The carbonyl oxygen of the residue at `index` is replaced with a
+    /// sulfur atom, giving a `C(=S)N` bond with the following residue.
+    /// An index of 0, or one without a following residue, is reported as
+    /// `Error::InvalidThioamide` when the protein is visited.
+    pub fn thioamide_bond(&mut self, index: u16) -> &mut Self {
+        self.thioamides.insert(index);
+        self
+    }
+
+    /// Use the D-enantiomer of the residue at the given index.
+    ///
+    /// Every stereocenter of the residue is inverted, including the β
+    /// carbons of L-threonine and L-isoleucine. Achiral residues such as
+    /// glycine are unaffected.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala]);
+    /// prot.d_amino_acid(1);
+    /// assert_eq!(prot.smiles().unwrap(), "N[C@H](C)C(=O)-O");
+    /// ```
+    pub fn d_amino_acid(&mut self, index: u16) -> &mut Self {
+        self.d_residues.insert(index);
+        self
+    }
+
+    /// Check whether the residue at the given index uses its D-enantiomer.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala, Ala]);
+    /// prot.d_amino_acid(2);
+    /// assert!(!prot.is_d_amino_acid(1));
+    /// assert!(prot.is_d_amino_acid(2));
+    /// ```
+    pub fn is_d_amino_acid(&self, index: u16) -> bool {
+        self.d_residues.contains(&index)
+    }
+
+    /// Get the indices of the residues using their D-enantiomer.
+    ///
+    /// Indices are returned in ascending order, and can be passed back to
+    /// [`Protein::d_amino_acid`] to restore the configuration of every
+    /// residue on another protein.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala, Gly, Ser]);
+    /// prot.d_amino_acid(3).d_amino_acid(1);
+    /// assert_eq!(prot.d_amino_acids(), vec![1, 3]);
+    /// ```
+    pub fn d_amino_acids(&self) -> Vec<u16> {
+        let mut indices = self.d_residues.iter().cloned().collect::<Vec<u16>>();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Use the β³-homologue of the residue at the given index.
+    ///
+    /// A methylene group is inserted between the α carbon and the carbonyl
+    /// carbon of the residue, so that the amine and the side chain are both
+    /// carried by the β carbon. The spatial arrangement of the stereocenter
+    /// is the same as in the parent amino acid. Only the standard amino
+    /// acids have a β³-homologue.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala]);
+    /// prot.beta_amino_acid(1);
+    /// assert_eq!(prot.smiles().unwrap(), "N[C@@H](C)CC(=O)-O");
+    /// ```
+    pub fn beta_amino_acid(&mut self, index: u16) -> &mut Self {
+        self.beta_residues.insert(index);
+        self
+    }
+
+    /// Set the strategy used to render unknown residues.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Masking;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala, Xaa]);
+    /// assert_eq!(prot.clone().smiles().unwrap(), "N[C@@H](C)C(=O)N*C(=O)-O");
+    /// prot.masking(Masking::Spacer);
+    /// assert_eq!(prot.smiles().unwrap(), "N[C@@H](C)C(=O)NCC(=O)-O");
+    /// ```
+    pub fn masking(&mut self, masking: Masking) -> &mut Self {
+        self.masking = masking;
+        self
+    }
+
+    /// Set the geometry of the double bond of the residue at the given index.
+    ///
+    /// Only dehydrobutyrine has a stereogenic double bond, which is rendered
+    /// as the (Z) isomer by default. The geometry is written with directional
+    /// bonds on both sides of the double bond, while the terminal methylene
+    /// of dehydroalanine is never given any.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Geometry;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Dhb]);
+    /// prot.geometry(1, Geometry::E);
+    /// assert_eq!(prot.smiles().unwrap(), "N/C(=C/C)C(=O)-O");
+    /// ```
+    pub fn geometry(&mut self, index: u16, geometry: Geometry) -> &mut Self {
+        self.geometries.insert(index, geometry);
+        self
+    }
+
+    /// Set the configuration of the methyllanthionine at the given index.
+    ///
+    /// The index is the one of the L-threonine involved in the cross-link,
+    /// whose β carbon is rendered in the (3S) configuration by default.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    /// use proteinogenic::MeLanConfiguration;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Thr, Cys]);
+    /// prot.cross_link(CrossLink::MeLan(1, 2)).unwrap();
+    /// prot.melan_configuration(1, MeLanConfiguration::R);
+    /// assert!(prot.smiles().unwrap().starts_with("N[C@@H]([C@H]3C)"));
+    /// ```
+    pub fn melan_configuration(
+        &mut self,
+        index: u16,
+        configuration: MeLanConfiguration,
+    ) -> &mut Self {
+        self.melan_configurations.insert(index, configuration);
+        self
+    }
+
+    /// Link the L-glutamic acid at the given index through its side chain.
+    ///
+    /// The peptide bond with the following residue is formed by the γ
+    /// carboxyl group of the residue at `index`, leaving its α carboxyl
+    /// group free, as found in glutathione.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut glutathione = proteinogenic::Protein::new([Glu, Cys, Gly]);
+    /// glutathione.gamma_glutamyl(1);
+    /// assert_eq!(
+    ///     glutathione.smiles().unwrap(),
+    ///     "N[C@H](C(=O)O)CCC(=O)N[C@@H](CS)C(=O)NCC(=O)-O"
+    /// );
+    /// ```
+    pub fn gamma_glutamyl(&mut self, index: u16) -> &mut Self {
+        self.gamma_glutamyls.insert(index);
+        self
+    }
+
+    /// Link the L-aspartic acid at the given index through its side chain.
+    ///
+    /// The peptide bond with the following residue is formed by the β
+    /// carboxyl group of the residue at `index`, leaving its α carboxyl
+    /// group free. Isoaspartate is a common degradation product of aging
+    /// proteins, formed by the isomerization of aspartate and asparagine.
+    /// A L-asparagine can only be linked this way once marked with
+    /// `Modification::Deamidation`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Asp, Gly]);
+    /// prot.iso_aspartate(1);
+    /// assert_eq!(prot.smiles().unwrap(), "N[C@H](C(=O)O)CC(=O)NCC(=O)-O");
+    /// ```
+    pub fn iso_aspartate(&mut self, index: u16) -> &mut Self {
+        self.iso_aspartyls.insert(index);
+        self
+    }
+
+    /// Mark the peptide bond preceding the L-proline at the given index as cis.
+    ///
+    /// SMILES cannot encode the conformation of an amide bond, so this
+    /// annotation does not change the rendered structure: it is stored
+    /// and validated so that it can be used to generate conformers.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly, Pro]);
+    /// prot.proline_cis(2);
+    /// assert!(prot.is_proline_cis(2));
+    /// assert!(prot.validate().is_ok());
+    /// ```
+    pub fn proline_cis(&mut self, index: u16) -> &mut Self {
+        self.cis_prolines.insert(index);
+        self
+    }
+
+    /// Check whether the peptide bond preceding the residue at the given index is cis.
+    pub fn is_proline_cis(&self, index: u16) -> bool {
+        self.cis_prolines.contains(&index)
+    }
+
+    /// Perform a walk on the atoms and bonds of the amino acid.
+    ///
+    /// The follower must have been initialized with a head. It will finish its
+    /// walk on the β carbon, without visiting the atoms part of the peptidic
+    /// bond.
+    ///
+    /// # Note
+    /// This is implemented as an associated method rather than a function to
+    /// make the borrow-checker happy about us borrowing `self.sequence` mutably
+    /// and `self.cross_links` immutably.
+    ///
+    fn visit_residue<F: Follower>(
+        aa: AminoAcid,
+        follower: &mut F,
+        index: u16,
+        context: &Context,
+    ) -> Result<(), Error> {
+        let cross_links = context.cross_links;
+        let modifications = context.modifications.get(&index).map(Vec::as_slice);
+
+        // apply the modifications of the residue in order: a deamidation
+        // must come first, since it changes the residue being modified, and
+        // successive oxidations of a sulfur add up, but every other
+        // modification targets the same side chain atom and cannot be
+        // combined with another one
+        let mut deamidated = false;
+        let mut effective: Option<Modification> = None;
+        for &next in modifications.unwrap_or_default() {
+            effective = match (effective, next) {
+                (None, Modification::Deamidation) if !deamidated => {
+                    if !matches!(aa, AminoAcid::Asn | AminoAcid::Gln) {
+                        return Err(Error::InvalidModification(index, aa, next));
+                    }
+                    deamidated = true;
+                    None
+                }
+                (None, Modification::Deamidation) => {
+                    return Err(Error::InvalidModification(index, aa, next));
+                }
+                (None, next) => Some(next),
+                (Some(Modification::Oxidized(m)), Modification::Oxidized(n)) => {
+                    Some(Modification::Oxidized(m.saturating_add(n)))
+                }
+                (Some(_), next) => return Err(Error::InvalidModification(index, aa, next)),
+            };
+        }
+        let modification = effective.as_ref();
+
+        // a deamidated residue is written as the acid it yields
+        let aa = match aa {
+            AminoAcid::Asn if deamidated => AminoAcid::Asp,
+            AminoAcid::Gln if deamidated => AminoAcid::Glu,
+            other => other,
+        };
+
+        // the α carbon of the acceptor of a thioether is bonded to the
+        // sulfur, other cross-links involve the side chain of the residue
+        let thioether = match cross_links.get(&index) {
+            Some((rnum, CrossLink::Thioether(_, j))) if *j == index => Some(rnum),
+            _ => None,
+        };
+        let cross_link = cross_links.get(&index).filter(|_| thioether.is_none());
+        let cyclization = context
+            .cyclizations
+            .iter()
+            .find(|(_, cyclization)| cyclization.side_chain() == Some(index));
+
+        const CARBON_TH2: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Element(Element::C),
+            configuration: Some(Configuration::TH2),
+            hcount: Some(VirtualHydrogen::H1),
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+        const CARBON_TH1: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Element(Element::C),
+            configuration: Some(Configuration::TH1),
+            hcount: Some(VirtualHydrogen::H1),
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+        const CARBON_H2: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Element(Element::C),
+            configuration: None,
+            hcount: Some(VirtualHydrogen::H2),
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+        const STAR: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Star,
+            configuration: None,
+            hcount: None,
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+        const CARBON_H0: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Element(Element::C),
+            configuration: None,
+            hcount: None,
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+        const CARBON_H0_TH1: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Element(Element::C),
+            configuration: Some(Configuration::TH1),
+            hcount: None,
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+        const CARBON_H0_TH2: AtomKind = AtomKind::Bracket {
+            symbol: BracketSymbol::Element(Element::C),
+            configuration: Some(Configuration::TH2),
+            hcount: None,
+            isotope: None,
+            charge: None,
+            map: None,
+        };
+
+        // every stereocenter of a D-amino acid is inverted
+        let invert = |kind: AtomKind| match kind {
+            AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration,
+                hcount,
+                charge,
+                map,
+            } if context.d_residues.contains(&index) => AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration: configuration.map(|c| match c {
+                    Configuration::TH1 => Configuration::TH2,
+                    Configuration::TH2 => Configuration::TH1,
+                    other => other,
+                }),
+                hcount,
+                charge,
+                map,
+            },
+            other => other,
+        };
+
+        // the thioether of a sactionine replaces a hydrogen of the alpha
+        // carbon, whose configuration is left unspecified
+        let sactionine = |kind: AtomKind| match kind {
+            AtomKind::Bracket {
+                symbol,
+                isotope,
+                hcount,
+                charge,
+                map,
+                ..
+            } if thioether.is_some() => AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration: None,
+                hcount: match hcount {
+                    Some(VirtualHydrogen::H2) => Some(VirtualHydrogen::H1),
+                    _ => None,
+                },
+                charge,
+                map,
+            },
+            other => other,
+        };
+
+        // the alpha carbon is only written as a bracket atom when it is
+        // chiral or when it carries the residue index as an atom map
+        // achiral residues never get a configuration on their alpha carbon,
+        // whatever the chirality requested for the residue
+        let achiral = |kind: AtomKind| match kind {
+            AtomKind::Bracket {
+                symbol,
+                isotope,
+                hcount,
+                charge,
+                map,
+                ..
+            } if aa.is_achiral() => AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration: None,
+                hcount,
+                charge,
+                map,
+            },
+            other => other,
+        };
+
+        let alpha = |kind: AtomKind| match achiral(invert(sactionine(kind))) {
+            AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration,
+                hcount,
+                charge,
+                ..
+            } if context.residue_maps => AtomKind::Bracket {
+                symbol,
+                isotope,
+                configuration,
+                hcount,
+                charge,
+                map: Number::try_from(index).ok(),
+            },
+            AtomKind::Bracket {
+                symbol: BracketSymbol::Star,
+                configuration: None,
+                ..
+            } => AtomKind::Star,
+            AtomKind::Bracket {
+                configuration: None,
+                ..
+            } => AtomKind::Aliphatic(Aliphatic::C),
+            other => other,
+        };
+
+        // the ring closure of a sactionine is written right after the alpha
+        // carbon, before any of its branches
+        let visit_alpha = |follower: &mut F, kind: AtomKind| {
+            follower.extend(BondKind::Elided, alpha(kind));
+            if let Some(rnum) = thioether {
+                follower.join(BondKind::Elided, rnum.clone());
+            }
+        };
+
+        // an oxidized sulfur carries its oxygens as oxo groups, except for
+        // the hydroxyl of the sulfenic, sulfinic or sulfonic acids
+        let oxygens = match modification {
+            Some(Modification::Oxidized(n)) => *n,
+            _ => 0,
+        };
+        let visit_oxo = |follower: &mut F, count: u8| {
+            for _ in 0..count {
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+            }
+        };
+
+        // an acetylated hydroxyl or amine carries an acetyl group, written
+        // as a continuation of the chain: the number of atoms added to the
+        // chain is returned so that the caller can pop them
+        let visit_acetyl = |follower: &mut F| -> usize {
+            if modification == Some(&Modification::Acetyl) {
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                2
+            } else {
+                0
+            }
+        };
+
+        // a custom linker is written from its first residue as a continuation
+        // of the chain, and bonded to the other residue through the ring
+        // closure of the cross-link: the number of atoms added to the chain
+        // is returned so that the caller can pop them
+        let visit_linker = |follower: &mut F, rnum: &Rnum, i: u16, linker: &str| -> usize {
+            if i == index {
+                linker::visit(linker, follower, rnum.clone(), context.linker_rnum)
+            } else {
+                follower.join(BondKind::Elided, rnum.clone());
+                0
+            }
+        };
+
+        // a phosphorylated group carries a phosphate group, written as a
+        // continuation of the chain like an acetyl group
+        let visit_phospho = |follower: &mut F| -> usize {
+            if modification == Some(&Modification::Phospho) {
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::P));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                2
+            } else {
+                0
+            }
+        };
+
+        // aromatic rings are written with explicit double bonds on demand,
+        // with the double bonds placed on the bonds created by `double`
+        let aromatic = |aromatic: Aromatic| match (context.kekulize, aromatic) {
+            (false, aromatic) => AtomKind::Aromatic(aromatic),
+            (true, Aromatic::C) => AtomKind::Aliphatic(Aliphatic::C),
+            (true, Aromatic::N) => AtomKind::Aliphatic(Aliphatic::N),
+            (true, other) => unreachable!("unsupported aromatic atom: {:?}", other),
+        };
+        let double = || match context.kekulize {
+            false => BondKind::Elided,
+            true => BondKind::Double,
+        };
+        // bonds between two aromatic rings must be explicitly single
+        let single = || match context.kekulize {
+            false => BondKind::Single,
+            true => BondKind::Elided,
+        };
+
+        // stereocenters other than the alpha carbon are only rendered on demand
+        let stereo = |kind: AtomKind| {
+            if context.full_stereo {
+                invert(kind)
+            } else {
+                AtomKind::Aliphatic(Aliphatic::C)
+            }
+        };
+
+        // only some amino-acids can build a cross-link
+        if let Some((_, cross_link)) = cross_links.get(&index) {
+            if !cross_link.accepts(index, aa) {
+                return Err(Error::InvalidCrossLink(index, aa, *cross_link));
+            }
+        }
+
+        // only the standard amino acids have a β³-homologue
+        let beta = context.beta_residues.contains(&index);
+        if beta {
+            match aa {
+                AminoAcid::Dha
+                | AminoAcid::Dhb
+                | AminoAcid::Htp
+                | AminoAcid::Nfk
+                | AminoAcid::Oia
+                | AminoAcid::BAla
+                | AminoAcid::Gaba
+                | AminoAcid::Har
+                | AminoAcid::Phe4F
+                | AminoAcid::Phe4Cl
+                | AminoAcid::Phe4Br
+                | AminoAcid::S5
+                | AminoAcid::R8
+                | AminoAcid::Orn
+                | AminoAcid::Dab
+                | AminoAcid::Dap
+                | AminoAcid::Xaa => return Err(Error::InvalidBetaAminoAcid(index, aa)),
+                AminoAcid::Glu if context.gamma_glutamyls.contains(&index) => {
+                    return Err(Error::InvalidBetaAminoAcid(index, aa));
+                }
+                AminoAcid::Asp if context.iso_aspartyls.contains(&index) => {
+                    return Err(Error::InvalidBetaAminoAcid(index, aa));
+                }
+                _ => (),
+            }
+        }
+
+        // only dehydrobutyrine has a stereogenic double bond
+        let geometry = context.geometries.get(&index).cloned().unwrap_or_default();
+        if geometry != Geometry::Z && aa != AminoAcid::Dhb {
+            return Err(Error::InvalidGeometry(index, aa, geometry));
+        }
+
+        // only the threonine of a methyllanthionine has a configurable β carbon
+        let melan = context
+            .melan_configurations
+            .get(&index)
+            .cloned()
+            .unwrap_or_default();
+        let is_melan = matches!(cross_link, Some((_, CrossLink::MeLan(_, _))));
+        if melan != MeLanConfiguration::S && (aa != AminoAcid::Thr || !is_melan) {
+            return Err(Error::InvalidMeLanConfiguration(index, aa, melan));
+        }
+
+        // only L-glutamic acid can form a γ-glutamyl bond
+        let gamma = context.gamma_glutamyls.contains(&index);
+        if gamma && (aa != AminoAcid::Glu || cyclization.is_some()) {
+            return Err(Error::InvalidGammaGlutamyl(index, aa));
+        }
+
+        // only L-aspartic acid can form an isoaspartyl bond
+        let iso = context.iso_aspartyls.contains(&index);
+        if iso && (aa != AminoAcid::Asp || cyclization.is_some()) {
+            return Err(Error::InvalidIsoAspartyl(index, aa));
+        }
+
+        // only a L-proline preceded by a peptide bond can be marked as cis
+        if context.cis_prolines.contains(&index) {
+            let head_to_tail = context
+                .cyclizations
+                .iter()
+                .any(|(_, cyclization)| *cyclization == Cyclization::HeadToTail);
+            if aa != AminoAcid::Pro || (index == 1 && !head_to_tail) {
+                return Err(Error::InvalidCisProline(index, aa));
+            }
+        }
+
+        // only some amino-acids can be modified
+        if let Some(modification) = modification {
+            match (aa, modification) {
+                (AminoAcid::His, Modification::Protonated) if cross_link.is_none() => (),
+                (AminoAcid::Lys, Modification::Protonated) => (),
+                (AminoAcid::Orn | AminoAcid::Dab | AminoAcid::Dap, Modification::Protonated) => (),
+                (AminoAcid::Arg, Modification::Protonated) => (),
+                (AminoAcid::Har, Modification::Protonated) => (),
+                (AminoAcid::Arg, Modification::Citrullination) => (),
+                (AminoAcid::Met, Modification::Oxidized(1..=2)) => (),
+                (AminoAcid::Ser, Modification::Acetyl) if cross_link.is_none() => (),
+                (AminoAcid::Thr, Modification::Acetyl) if cross_link.is_none() => (),
+                (AminoAcid::Lys, Modification::Acetyl)
+                    if cross_link.is_none() && cyclization.is_none() => {}
+                (AminoAcid::Ser, Modification::Phospho) if cross_link.is_none() => (),
+                (AminoAcid::Thr, Modification::Phospho) if cross_link.is_none() => (),
+                (AminoAcid::Tyr, Modification::Phospho) => (),
+                (AminoAcid::His, Modification::Phospho) if cross_link.is_none() => (),
+                (AminoAcid::Asp, Modification::Phospho) if cyclization.is_none() && !iso => (),
+                (AminoAcid::Cys, Modification::Oxidized(n)) => match cross_link {
+                    None if (1..=3).contains(n) => (),
+                    Some((_, CrossLink::Cystine(_, _)))
+                    | Some((_, CrossLink::SelenylSulfide(_, _))) => {
+                        return Err(Error::InvalidModification(index, aa, *modification));
+                    }
+                    Some(_) if (1..=2).contains(n) => (),
+                    _ => return Err(Error::InvalidModification(index, aa, *modification)),
+                },
+                (other, _) => {
+                    return Err(Error::InvalidModification(index, other, *modification));
+                }
+            }
+        }
+
+        // only some amino-acids can cyclized
+        if let Some(&(_, cyclization)) = cyclization {
+            match (cyclization, aa) {
+                (Cyclization::Lasso(_), AminoAcid::Asp) => (),
+                (Cyclization::Lasso(_), AminoAcid::Glu) => (),
+                (
+                    Cyclization::Branched(_),
+                    AminoAcid::Lys | AminoAcid::Orn | AminoAcid::Dab | AminoAcid::Dap,
+                ) if cross_link.is_none() => {}
+                (_, other) => {
+                    return Err(Error::InvalidCyclization(index, other, cyclization));
+                }
+            }
+        }
+
+        // visit the alpha carbon and the residue
+        match aa {
+            AminoAcid::Dhb => {
+                // alpha carbon
+                follower.extend(BondKind::Up, alpha(CARBON_H0));
+                // residue, with the methyl on the side of the amine for (Z)
+                let bond = match geometry {
+                    Geometry::Z => BondKind::Down,
+                    Geometry::E => BondKind::Up,
+                };
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(bond, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(2);
+            }
+
+            AminoAcid::Dha => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_H0);
+                // residue
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+            }
+
+            AminoAcid::Pyl => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, stereo(CARBON_TH1));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(BondKind::Elided, stereo(CARBON_TH1));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                match cross_link {
+                    None => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::N));
+                    }
+                    // custom linker, added to the imine carbon of the pyrroline
+                    Some((rnum, CrossLink::Custom { i, linker, .. })) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        match visit_linker(follower, rnum, *i, linker) {
+                            0 => (),
+                            depth => follower.pop(depth),
+                        }
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                    }
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                }
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.pop(11);
+            }
+
+            AminoAcid::Gly => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_H2);
+            }
+
+            AminoAcid::Xaa => {
+                // alpha carbon, masked
+                let kind = match context.masking {
+                    Masking::Wildcard => STAR,
+                    Masking::Spacer => CARBON_H2,
+                };
+                visit_alpha(follower, kind);
+            }
+
+            AminoAcid::BAla => {
+                // beta carbon, in the backbone
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                // alpha carbon
+                visit_alpha(follower, CARBON_H2);
+            }
+
+            AminoAcid::Gaba => {
+                // gamma and beta carbons, in the backbone
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                // alpha carbon
+                visit_alpha(follower, CARBON_H2);
+            }
+
+            AminoAcid::Ala => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+            }
+
+            AminoAcid::Pro => {
+                // proline ring, closed on the nitrogen created by the caller, so
+                // that an N-terminal proline yields a secondary amine
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH1);
+                follower.join(BondKind::Elided, Rnum::R1);
+            }
+
+            AminoAcid::Val => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(2);
+            }
+
+            AminoAcid::Leu => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(3);
+            }
+
+            AminoAcid::Met => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                visit_oxo(follower, oxygens);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(4);
+            }
+
+            AminoAcid::Phe => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.pop(7);
+            }
+
+            AminoAcid::Tyr => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                if let Some((rnum, CrossLink::HisTyr(_, _))) = cross_link {
+                    follower.join(single(), rnum.clone());
+                }
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                let phospho = visit_phospho(follower);
+                follower.pop(1 + phospho);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.pop(7);
+            }
+
+            AminoAcid::Phe4F | AminoAcid::Phe4Cl | AminoAcid::Phe4Br => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue, with the halogen in para of the β carbon
+                let halogen = match aa {
+                    AminoAcid::Phe4F => Aliphatic::F,
+                    AminoAcid::Phe4Cl => Aliphatic::Cl,
+                    _ => Aliphatic::Br,
+                };
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(halogen));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.pop(7);
+            }
+
+            AminoAcid::S5 | AminoAcid::R8 => {
+                // alpha carbon, with the methyl group in place of the hydrogen
+                let kind = match aa {
+                    AminoAcid::S5 => CARBON_H0_TH2,
+                    _ => CARBON_H0_TH1,
+                };
+                visit_alpha(follower, kind);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+                // residue, with the terminal alkene replaced by the other
+                // half of the staple after metathesis
+                let methylenes = if aa == AminoAcid::S5 { 3 } else { 6 };
+                for _ in 0..methylenes {
+                    follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                }
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                let alkene = match cross_link {
+                    None => {
+                        follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::C));
+                        1
+                    }
+                    Some((rnum, CrossLink::HydrocarbonStaple(_, _))) => {
+                        follower.join(BondKind::Double, rnum.clone());
+                        0
+                    }
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                };
+                follower.pop(methylenes + 1 + alkene);
+            }
+
+            AminoAcid::Cys => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                match cross_link {
+                    // no cross-link, just add the thiol group.
+                    None if oxygens == 0 => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                        follower.pop(2);
+                    }
+                    // oxidized thiol, add the oxo groups and the hydroxyl
+                    None => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                        visit_oxo(follower, oxygens - 1);
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        follower.pop(3);
+                    }
+                    // cystine, add the first sulfur, the other Cys will add the second one;
+                    // selenylsulfide, the selenocysteine will add the selenium
+                    Some((rnum, CrossLink::Cystine(_, _)))
+                    | Some((rnum, CrossLink::SelenylSulfide(_, _))) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                        follower.join(BondKind::Elided, rnum.clone());
+                        follower.pop(2);
+                    }
+                    // lanthionine, the sulfur comes from the cysteine
+                    Some((rnum, CrossLink::Lan(_, _))) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                        follower.join(BondKind::Elided, rnum.clone());
+                        visit_oxo(follower, oxygens);
+                        follower.pop(2);
+                    }
+                    // methyllanthionine, add the sulfur, the threonine won't add the hydroxy group;
+                    // sactionine, add the sulfur, the other residue will bond its α carbon
+                    Some((rnum, CrossLink::MeLan(_, _)))
+                    | Some((rnum, CrossLink::Thioether(_, _))) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                        follower.join(BondKind::Elided, rnum.clone());
+                        visit_oxo(follower, oxygens);
+                        follower.pop(2);
+                    }
+                    // custom linker, bonded to the sulfur
+                    Some((rnum, CrossLink::Custom { i, linker, .. })) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::S));
+                        visit_oxo(follower, oxygens);
+                        let linker = visit_linker(follower, rnum, *i, linker);
+                        follower.pop(2 + linker);
+                    }
+                    // other cross-links are not permitted
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                }
+            }
+
+            AminoAcid::Ser => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                match cross_link {
+                    // no cross-link, just add the alcohol
+                    None => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        let group = visit_acetyl(follower) + visit_phospho(follower);
+                        follower.pop(2 + group);
+                    }
+                    // lanthionine, selenolanthionine or lysinoalanine, bridge with
+                    // the sulfur, selenium or nitrogen atom
+                    Some((rnum, CrossLink::Lan(_, _)))
+                    | Some((rnum, CrossLink::SeLan(_, _)))
+                    | Some((rnum, CrossLink::Lal(_, _))) => {
+                        follower.join(BondKind::Elided, rnum.clone());
+                        follower.pop(1);
+                    }
+                    // custom linker, bonded to the oxygen
+                    Some((rnum, CrossLink::Custom { i, linker, .. })) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        let linker = visit_linker(follower, rnum, *i, linker);
+                        follower.pop(2 + linker);
+                    }
+                    // other cross-links are not permitted
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                }
+            }
+
+            AminoAcid::Sec => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                // selenium is outside of the organic subset, so the hydrogen
+                // of the selenol group must be given explicitly
+                let hcount = match cross_link {
+                    None => Some(VirtualHydrogen::H1),
+                    Some(_) => None,
+                };
+                follower.extend(
+                    BondKind::Elided,
+                    AtomKind::Bracket {
+                        symbol: BracketSymbol::Element(Element::Se),
+                        isotope: None,
+                        configuration: None,
+                        hcount,
+                        charge: None,
+                        map: None,
+                    },
+                );
+                let linker = match cross_link {
+                    // no cross-link, just the selenol group
+                    None => 0,
+                    // selenolanthionine, the selenium comes from the selenocysteine;
+                    // diselenide or selenylsulfide, bridge with the other residue
+                    Some((rnum, CrossLink::SeLan(_, _)))
+                    | Some((rnum, CrossLink::Diselenide(_, _)))
+                    | Some((rnum, CrossLink::SelenylSulfide(_, _))) => {
+                        follower.join(BondKind::Elided, rnum.clone());
+                        0
+                    }
+                    // custom linker, bonded to the selenium
+                    Some((rnum, CrossLink::Custom { i, linker, .. })) => {
+                        visit_linker(follower, rnum, *i, linker)
+                    }
+                    // other cross-links are not permitted
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                };
+                follower.pop(2 + linker);
+            }
+
+            AminoAcid::Thr => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue, with the β carbon of a methyllanthionine
+                // possibly inverted
+                let beta_carbon = match melan {
+                    MeLanConfiguration::S => CARBON_TH2,
+                    MeLanConfiguration::R => CARBON_TH1,
+                };
+                follower.extend(BondKind::Elided, stereo(beta_carbon));
+                match cross_link {
+                    None => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        follower.pop(1);
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        let group = visit_acetyl(follower) + visit_phospho(follower);
+                        follower.pop(2 + group);
+                    }
+                    Some((rnum, CrossLink::MeLan(_, _))) => {
+                        follower.join(BondKind::Elided, rnum.clone());
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        follower.pop(2);
+                    }
+                    Some((rnum, CrossLink::Custom { i, linker, .. })) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        follower.pop(1);
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        let linker = visit_linker(follower, rnum, *i, linker);
+                        follower.pop(2 + linker);
+                    }
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                }
+            }
+
+            AminoAcid::Asn => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.pop(3);
+            }
+
+            AminoAcid::Gln => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                if index == 1 && context.pyroglutamate {
+                    // pyroglutamate: the amide is formed with the N-terminus
+                    follower.join(BondKind::Elided, Rnum::R1);
+                    follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                } else {
+                    follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                    follower.pop(1);
+                    follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                }
+                follower.pop(4);
+            }
+
+            AminoAcid::Arg | AminoAcid::Har => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue, with an additional methylene for L-homoarginine
+                let methylenes = if aa == AminoAcid::Har { 4 } else { 3 };
+                for _ in 0..methylenes {
+                    follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                }
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                match modification {
+                    // guanidinium, the imine nitrogen is protonated
+                    Some(Modification::Protonated) => {
+                        follower.extend(
+                            BondKind::Double,
+                            AtomKind::Bracket {
+                                symbol: BracketSymbol::Element(Element::N),
+                                isotope: None,
+                                configuration: None,
+                                hcount: Some(VirtualHydrogen::H2),
+                                charge: Some(Charge::One),
+                                map: None,
+                            },
+                        );
+                    }
+                    // ureido, the imine is replaced by a carbonyl
+                    Some(Modification::Citrullination) => {
+                        follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                    }
+                    // guanidine
+                    None => {
+                        follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::N));
+                    }
+                    // other modifications are rejected during validation
+                    Some(Modification::Oxidized(_))
+                    | Some(Modification::Acetyl)
+                    | Some(Modification::Phospho)
+                    | Some(Modification::Deamidation) => {
+                        unreachable!("unsupported modification: {:?}", modification)
+                    }
+                }
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.pop(methylenes + 3);
+            }
+
+            AminoAcid::Lys | AminoAcid::Orn | AminoAcid::Dab | AminoAcid::Dap => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue, with less methylenes for the homologues of L-lysine
+                let methylenes = match aa {
+                    AminoAcid::Orn => 3,
+                    AminoAcid::Dab => 2,
+                    AminoAcid::Dap => 1,
+                    _ => 4,
+                };
+                for _ in 0..methylenes {
+                    follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                }
+                let mut acetyl = 0;
+                match modification {
+                    // ammonium, with one less hydrogen if the amine is cross-linked
+                    Some(Modification::Protonated) => {
+                        let hcount = match (cross_link, cyclization) {
+                            (None, None) => VirtualHydrogen::H3,
+                            _ => VirtualHydrogen::H2,
+                        };
+                        follower.extend(
+                            BondKind::Elided,
+                            AtomKind::Bracket {
+                                symbol: BracketSymbol::Element(Element::N),
+                                isotope: None,
+                                configuration: None,
+                                hcount: Some(hcount),
+                                charge: Some(Charge::One),
+                                map: None,
+                            },
+                        );
+                    }
+                    // primary amine, or acetamide
+                    None | Some(Modification::Acetyl) => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                        acetyl = visit_acetyl(follower);
+                    }
+                    // other modifications are rejected during validation
+                    Some(Modification::Oxidized(_))
+                    | Some(Modification::Phospho)
+                    | Some(Modification::Citrullination)
+                    | Some(Modification::Deamidation) => {
+                        unreachable!("unsupported modification: {:?}", modification)
+                    }
+                }
+                // isopeptide bond with the C-terminus, closed before a custom
+                // linker continues the chain
+                if let Some((rnum, _)) = cyclization {
+                    follower.join(BondKind::Elided, rnum.clone());
+                }
+                let linker = match cross_link {
+                    // no cross-link, nothing to do
+                    None => 0,
+                    // lysinoalanine, bridge with the other residue
+                    Some((rnum, CrossLink::Lal(_, _))) => {
+                        follower.join(BondKind::Elided, rnum.clone());
+                        0
+                    }
+                    // custom linker, bonded to the amine
+                    Some((rnum, CrossLink::Custom { i, linker, .. })) => {
+                        visit_linker(follower, rnum, *i, linker)
+                    }
+                    // other cross-links are not permitted
+                    Some((_, other)) => {
+                        return Err(Error::InvalidCrossLink(index, aa, *other));
+                    }
+                };
+                follower.pop(methylenes + 1 + acetyl + linker);
+            }
+
+            AminoAcid::His => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                match modification {
+                    // imidazolium, the τ nitrogen is protonated
+                    Some(Modification::Protonated) => {
+                        let symbol = match context.kekulize {
+                            false => BracketSymbol::Aromatic(BracketAromatic::N),
+                            true => BracketSymbol::Element(Element::N),
+                        };
+                        follower.extend(
+                            BondKind::Elided,
+                            AtomKind::Bracket {
+                                symbol,
+                                isotope: None,
+                                configuration: None,
+                                hcount: Some(VirtualHydrogen::H1),
+                                charge: Some(Charge::One),
+                                map: None,
+                            },
+                        );
+                    }
+                    // imidazole, possibly phosphorylated or cross-linked
+                    // on the τ nitrogen
+                    None | Some(Modification::Phospho) => {
+                        follower.extend(BondKind::Elided, aromatic(Aromatic::N));
+                        if let Some((rnum, CrossLink::HisTyr(_, _))) = cross_link {
+                            follower.join(single(), rnum.clone());
+                        }
+                        // the ring continues after the nitrogen
+                        match visit_phospho(follower) {
+                            0 => (),
+                            depth => follower.pop(depth),
+                        }
+                    }
+                    // other modifications are rejected during validation
+                    Some(Modification::Oxidized(_))
+                    | Some(Modification::Acetyl)
+                    | Some(Modification::Citrullination)
+                    | Some(Modification::Deamidation) => {
+                        unreachable!("unsupported modification: {:?}", modification)
+                    }
+                }
+                match (modification, cross_link) {
+                    // τ-substituted histidine, the π nitrogen loses its hydrogen
+                    (Some(Modification::Phospho), _) | (_, Some((_, CrossLink::HisTyr(_, _)))) => {
+                        follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                        follower.extend(double(), aromatic(Aromatic::N));
+                    }
+                    (_, _) => {
+                        follower.extend(double(), aromatic(Aromatic::C));
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                    }
+                }
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.pop(6);
+            }
+
+            AminoAcid::Asp if iso => {
+                // alpha carbon, with the side chain listed after the free
+                // α carboxyl group
+                visit_alpha(follower, CARBON_TH1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(2);
+                // residue, the β carboxyl group is added as the backbone carbon
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+            }
+
+            AminoAcid::Asp => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                match cyclization {
+                    Some((rnum, Cyclization::Lasso(_))) => {
+                        follower.join(BondKind::Elided, rnum.clone());
+                    }
+                    _ => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        let phospho = visit_phospho(follower);
+                        follower.pop(1 + phospho);
+                    }
+                }
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(3);
+            }
+
+            AminoAcid::Glu if gamma => {
+                // alpha carbon, with the side chain listed after the free
+                // α carboxyl group
+                visit_alpha(follower, CARBON_TH1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(2);
+                // residue, the γ carboxyl group is added as the backbone carbon
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+            }
+
+            AminoAcid::Glu => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                match cyclization {
+                    Some((rnum, Cyclization::Lasso(_))) => {
+                        follower.join(BondKind::Elided, rnum.clone());
+                    }
+                    // pyroglutamate: the amide is formed with the N-terminus
+                    _ if index == 1 && context.pyroglutamate => {
+                        follower.join(BondKind::Elided, Rnum::R1);
+                    }
+                    _ => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                        follower.pop(1);
+                    }
+                }
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(4);
+            }
+
+            AminoAcid::Ile => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, stereo(CARBON_TH2));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.pop(3);
+            }
+
+            AminoAcid::Trp => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R2);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R2);
+                follower.pop(10);
+            }
+
+            AminoAcid::Htp => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R2);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R2);
+                follower.pop(10);
+            }
+
+            AminoAcid::Nfk => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(3);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.pop(8);
+            }
+
+            AminoAcid::Oia => {
+                // alpha carbon
+                visit_alpha(follower, CARBON_TH2);
+                // residue
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R2);
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R1);
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.extend(BondKind::Elided, aromatic(Aromatic::C));
+                follower.extend(double(), aromatic(Aromatic::C));
+                follower.join(BondKind::Elided, Rnum::R2);
+                follower.pop(10);
+            }
+        }
+
+        // add the methylene of β³-amino acids
+        if beta {
+            follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+        }
+
+        // visit the beta carbon and finish
+        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+        Ok(())
+    }
+}
+
+impl<S, R> Protein<S>
+where
+    S: IntoIterator<Item = R>,
+    R: Residue,
+{
+    /// Create a new `Protein` with the given sequence stream.
+    pub fn new(sequence: S) -> Self {
+        Self {
+            sequence,
+            cyclizations: Vec::new(),
+            cross_links: HashMap::new(),
+            cross_link_num: 3, // R0 is used for cyclization, R1 and R2 in residues
+            ring_numbering: RingNumbering::default(),
+            modifications: HashMap::new(),
+            thioamides: HashSet::new(),
+            gamma_glutamyls: HashSet::new(),
+            iso_aspartyls: HashSet::new(),
+            cis_prolines: HashSet::new(),
+            d_residues: HashSet::new(),
+            beta_residues: HashSet::new(),
+            masking: Masking::default(),
+            geometries: HashMap::new(),
+            melan_configurations: HashMap::new(),
+            n_term: NTerm::default(),
+            c_term: CTerm::default(),
+            full_stereo: true,
+            residue_maps: false,
+            kekulize: false,
+            explicit_hydrogens: false,
+            zwitterion: false,
+        }
+    }
+
+    /// Visit each atom and bond of the sequence using the given follower.
+    pub fn visit<F: Follower>(self, follower: &mut F) -> Result<(), Error> {
+        self.visit_with(follower, &HashMap::new(), 0)
+    }
+
+    /// Visit the protein with the cross-links to other chains of a complex.
+    fn visit_with<F: Follower>(
+        self,
+        follower: &mut F,
+        interchain: &HashMap<u16, (Rnum, CrossLink)>,
+        linker_rnum: u16,
+    ) -> Result<(), Error> {
+        if self.explicit_hydrogens {
+            let mut recorder = Recorder::default();
+            self.walk(&mut recorder, |_| (), interchain, linker_rnum)?;
+            recorder.replay_with_hydrogens(follower);
+            Ok(())
+        } else {
+            self.walk(follower, |_| (), interchain, linker_rnum)
+        }
+    }
+
+    /// Visit the protein, calling `mark` before visiting each residue.
+    ///
+    /// The cross-links with other chains of a complex are given with their
+    /// ring closure number in `interchain`, which must not be used by the
+    /// protein itself. The rings of custom linkers are numbered from at
+    /// least `linker_rnum`, so that they do not reuse the numbers of the
+    /// interchain cross-links still open.
+    fn walk<F, M>(
+        self,
+        follower: &mut F,
+        mut mark: M,
+        interchain: &HashMap<u16, (Rnum, CrossLink)>,
+        linker_rnum: u16,
+    ) -> Result<(), Error>
+    where
+        F: Follower,
+        M: FnMut(&F),
+    {
+        // number the ring closures of every cross-link and cyclization
+        // before emitting anything, so that running out of ring closure
+        // numbers cannot leave the follower with a partial walk
+        let mut cross_links = self.ring_closures()?;
+        let cyclizations = self.cyclization_closures(&cross_links)?;
+        let linker_rnum = self
+            .linker_closures(&cross_links, &cyclizations)?
+            .start
+            .max(linker_rnum);
+        cross_links.extend(interchain.iter().map(|(&k, v)| (k, v.clone())));
+        let context = Context {
+            cross_links: &cross_links,
+            modifications: &self.modifications,
+            gamma_glutamyls: &self.gamma_glutamyls,
+            iso_aspartyls: &self.iso_aspartyls,
+            cis_prolines: &self.cis_prolines,
+            d_residues: &self.d_residues,
+            beta_residues: &self.beta_residues,
+            masking: self.masking,
+            geometries: &self.geometries,
+            melan_configurations: &self.melan_configurations,
+            cyclizations: &cyclizations,
+            linker_rnum,
+            full_stereo: self.full_stereo,
+            residue_maps: self.residue_maps,
+            kekulize: self.kekulize,
+            pyroglutamate: self.n_term == NTerm::Pyroglutamate,
+        };
+
+        // get the cyclizations involving the termini, if any
+        let n_cyclization = cyclizations.iter().find(|(_, c)| c.n_terminal());
+        let c_cyclization = cyclizations.iter().find(|(_, c)| c.c_terminal());
+
+        // check the acyl chain of the N-terminus, if any
+        if let Some((carbons, hydroxy)) = self.n_term.acyl_chain() {
+            if carbons == 0 || (hydroxy && carbons < 3) {
+                return Err(Error::InvalidNTerm(self.n_term));
+            }
+        }
+        if self.n_term != NTerm::Free && n_cyclization.is_some() {
+            return Err(Error::InvalidNTerm(self.n_term));
+        }
+        if self.c_term != CTerm::Free && c_cyclization.is_some() {
+            return Err(Error::InvalidCTerm(self.c_term));
+        }
+
+        // the zwitterion can only be formed with a free or methylated amine
+        // and a free carboxylic acid
+        let zwitterion = self.zwitterion
+            && matches!(self.n_term, NTerm::Free | NTerm::Methyl)
+            && self.c_term == CTerm::Free
+            && n_cyclization.is_none()
+            && c_cyclization.is_none();
+
+        // check the residues of each cross-link together, since residues
+        // accepted on their own can still be an invalid pair, such as the
+        // two L-cysteines of a lanthionine
+        let residues = self.sequence.into_iter().collect::<Vec<R>>();
+        let amino_acid = |index: u16| {
+            let residue = residues.get(usize::from(index).checked_sub(1)?)?;
+            residue.amino_acid()
+        };
+        let mut pairs = self
+            .cross_links
+            .iter()
+            .filter(|(&index, (_, cross_link))| index == cross_link.residues().0)
+            .map(|(_, &(_, cross_link))| cross_link)
+            .collect::<Vec<_>>();
+        pairs.sort_by_key(|cross_link| cross_link.residues());
+        for cross_link in pairs {
+            let (i, j) = cross_link.residues();
+            if let (Some(aa_i), Some(aa_j)) = (amino_acid(i), amino_acid(j)) {
+                cross_link.check_pair(aa_i, aa_j)?;
+            }
+        }
+
+        // visit every amino acid one by one
+        let mut last = 0;
+        let mut aa_iter = residues.into_iter().enumerate();
+        if let Some((index, aa)) = aa_iter.next() {
+            // the lactam can only be formed by an α residue with a side
+            // chain carbonyl, which closes the ring with the N-terminus
+            if context.pyroglutamate {
+                let side_chain = cyclizations.iter().any(|(_, c)| c.side_chain() == Some(1));
+                let valid = matches!(aa.amino_acid(), Some(AminoAcid::Gln | AminoAcid::Glu))
+                    && !context.gamma_glutamyls.contains(&1)
+                    && !context.beta_residues.contains(&1)
+                    && !side_chain;
+                if !valid {
+                    return Err(Error::InvalidNTerm(self.n_term));
+                }
+            }
+
+            // N-terminus: create the N of the amine, or of the amide with
+            // the N-terminal acyl chain, and close the cyclization
+            mark(follower);
+            let proline = aa.amino_acid() == Some(AminoAcid::Pro);
+            let rnum = n_cyclization.map(|(rnum, _)| rnum);
+            Self::emit_n_terminus(follower, self.n_term, rnum, zwitterion, proline);
+
+            // visit residue
+            Self::visit_custom(
+                &aa,
+                follower,
+                index as u16 + 1, // user-provided indices start at 1
+                &context,
+            )?;
+
+            // the carbonyl group is added once the next residue is known,
+            // since the last one may close a cyclization first
+            last = index as u16 + 1;
+            // keep visiting following amino acids.
+            while let Some((index, aa)) = aa_iter.next() {
+                // add the carbonyl group of the previous residue
+                follower.extend(BondKind::Double, Self::carbonyl(&self.thioamides, last));
+                follower.pop(1);
+                // next amino acid: create the N atom of the carboxamide and visit residue.
+                mark(follower);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+                Self::visit_custom(&aa, follower, index as u16 + 1, &context)?;
+                last = index as u16 + 1;
+            }
+
+            // the termini of a single residue cannot be bonded together
+            if last == 1 && c_cyclization.map(|(_, c)| c) == Some(&Cyclization::HeadToTail) {
+                return Err(Error::CyclizationTooSmall);
+            }
+
+            // thioamide bonds can only be formed with a following residue;
+            // report the first invalid one since the set is unordered
+            let linear = c_cyclization.is_none();
+            if let Some(&i) = self
+                .thioamides
+                .iter()
+                .filter(|&&i| i == 0 || i > last || (i == last && linear))
+                .min()
+            {
+                return Err(Error::InvalidThioamide(i));
+            }
+
+            // C-terminus: close the cyclization, or add the rest of the
+            // terminal group to the last carbonyl carbon
+            let rnum = c_cyclization.map(|(rnum, _)| rnum);
+            let carbonyl = Self::carbonyl(&self.thioamides, last);
+            Self::emit_c_terminus(follower, self.c_term, carbonyl, rnum, zwitterion);
+        }
+
+        // cross-links to residues past the end of the sequence would leave
+        // their ring closure open
+        if let Some(&i) = self.cross_links.keys().filter(|&&i| i > last).min() {
+            return Err(Error::InvalidResidue(i));
+        }
+
+        Ok(())
+    }
+
+    /// Visit a residue, checking that custom residues are left unmodified.
+    fn visit_custom<F: Follower>(
+        residue: &R,
+        follower: &mut F,
+        index: u16,
+        context: &Context,
+    ) -> Result<(), Error> {
+        let context = ResidueContext::new(index, context);
+        if residue.amino_acid().is_none() && context.is_modified() {
+            return Err(Error::InvalidCustomResidue(index));
+        }
+        residue.visit(follower, &context)
+    }
+
+    /// Visit a linear acyl chain, from the terminal carbon to the carbonyl.
+    fn acyl<F: Follower>(follower: &mut F, carbons: u8, hydroxy: bool) {
+        follower.root(AtomKind::Aliphatic(Aliphatic::C));
+        for position in (1..carbons).rev() {
+            if hydroxy && position == 2 {
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+            }
+            follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+        }
+        follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+        follower.pop(1);
+    }
+
+    /// Visit the N-terminal group, up to the nitrogen of the first residue.
+    ///
+    /// The nitrogen is written as a primary amine, or as the ammonium of a
+    /// zwitterion, which is secondary when the first residue is a proline,
+    /// or as the amide or amine of the N-terminal modification. The amine
+    /// of a methylated N-terminus is also protonated in a zwitterion. It is then
+    /// bonded to the ring closure of a cyclization if one is given.
+    fn emit_n_terminus<F: Follower>(
+        follower: &mut F,
+        n_term: NTerm,
+        cyclization: Option<&Rnum>,
+        zwitterion: bool,
+        proline: bool,
+    ) {
+        match n_term {
+            NTerm::Free if zwitterion => {
+                let hcount = if proline {
+                    VirtualHydrogen::H2
+                } else {
+                    VirtualHydrogen::H3
+                };
+                follower.root(AtomKind::Bracket {
+                    symbol: BracketSymbol::Element(Element::N),
+                    isotope: None,
+                    configuration: None,
+                    hcount: Some(hcount),
+                    charge: Some(Charge::One),
+                    map: None,
+                });
+            }
+            NTerm::Free => {
+                follower.root(AtomKind::Aliphatic(Aliphatic::N));
+            }
+            NTerm::Acetyl | NTerm::Formyl | NTerm::FattyAcyl { .. } => {
+                let (carbons, hydroxy) = n_term.acyl_chain().unwrap();
+                Self::acyl(follower, carbons, hydroxy);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+            }
+            NTerm::Methyl if zwitterion => {
+                let hcount = if proline {
+                    VirtualHydrogen::H1
+                } else {
+                    VirtualHydrogen::H2
+                };
+                follower.root(AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(
+                    BondKind::Elided,
+                    AtomKind::Bracket {
+                        symbol: BracketSymbol::Element(Element::N),
+                        isotope: None,
+                        configuration: None,
+                        hcount: Some(hcount),
+                        charge: Some(Charge::One),
+                        map: None,
+                    },
+                );
+            }
+            NTerm::Methyl => {
+                follower.root(AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+            }
+            NTerm::Pyroglutamate => {
+                follower.root(AtomKind::Aliphatic(Aliphatic::N));
+                follower.join(BondKind::Elided, Rnum::R1);
+            }
+        }
+        if let Some(rnum) = cyclization {
+            follower.join(BondKind::Elided, rnum.clone());
+        }
+    }
+
+    /// Visit the C-terminal group, from the carbonyl carbon of the last residue.
+    ///
+    /// The carbonyl carbon is bonded to the ring closure of a cyclization
+    /// if one is given, otherwise to the O atom of the carboxylic acid, or
+    /// of the carboxylate of a zwitterion, or to the atoms of the
+    /// carboxamide, hydrazide or ester. The ring closure is written right
+    /// after the carbonyl carbon, before its `carbonyl` atom, since a ring
+    /// bond cannot follow a branch.
+    fn emit_c_terminus<F: Follower>(
+        follower: &mut F,
+        c_term: CTerm,
+        carbonyl: AtomKind,
+        cyclization: Option<&Rnum>,
+        zwitterion: bool,
+    ) {
+        if let Some(rnum) = cyclization {
+            follower.join(BondKind::Elided, rnum.clone());
+            follower.extend(BondKind::Double, carbonyl);
+            return;
+        }
+        follower.extend(BondKind::Double, carbonyl);
+        follower.pop(1);
+        match c_term {
+            CTerm::Free if zwitterion => {
+                let oxygen = AtomKind::Bracket {
+                    symbol: BracketSymbol::Element(Element::O),
+                    isotope: None,
+                    configuration: None,
+                    hcount: None,
+                    charge: Some(Charge::MinusOne),
+                    map: None,
+                };
+                follower.extend(BondKind::Single, oxygen);
+            }
+            CTerm::Free => {
+                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::O));
+            }
+            CTerm::Amide => {
+                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::N));
+            }
+            CTerm::Hydrazide => {
+                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
+            }
+            CTerm::MethylEster => {
+                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::O));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+            }
+            CTerm::MethylAmide => {
+                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+            }
+            CTerm::GpiEthanolamine => {
+                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::N));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::P));
+                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+                follower.pop(1);
+                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
+            }
+        }
+    }
+
+    /// Get the atom of the carbonyl group of the residue at `index`.
+    fn carbonyl(thioamides: &HashSet<u16>, index: u16) -> AtomKind {
+        if thioamides.contains(&index) {
+            AtomKind::Aliphatic(Aliphatic::S)
+        } else {
+            AtomKind::Aliphatic(Aliphatic::O)
+        }
+    }
+
+    /// Get the atoms and bonds of the protein as a sequence of walk events.
+    ///
+    /// This performs the same walk as `Protein::visit`, but without the need
+    /// to implement a `purr` follower to consume it.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::AtomEvent;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Gly]);
+    /// let atoms = prot
+    ///     .events()
+    ///     .unwrap()
+    ///     .filter(|e| matches!(e, AtomEvent::Root(_) | AtomEvent::Extend(_, _)))
+    ///     .count();
+    /// assert_eq!(atoms, 9);
+    /// ```
+    pub fn events(self) -> Result<impl Iterator<Item = AtomEvent>, Error> {
+        let mut recorder = Recorder::default();
+        self.visit(&mut recorder)?;
+        Ok(recorder.events.into_iter().map(|event| match event {
+            Event::Root(atom) => AtomEvent::Root(Atom::from_kind(&atom)),
+            Event::Extend(bond, atom) => {
+                AtomEvent::Extend(Bond::from_kind(&bond), Atom::from_kind(&atom))
+            }
+            Event::Join(bond, rnum) => AtomEvent::Join(Bond::from_kind(&bond), rnum_to_u16(&rnum)),
+            Event::Pop(depth) => AtomEvent::Pop(depth),
+        }))
+    }
+
+    /// Generate a SMILES string for the protein.
+    pub fn smiles(self) -> Result<String, Error> {
+        let mut writer = writer::Writer::new();
+        self.visit(&mut writer)?;
+        Ok(writer.write())
+    }
+}
+
+impl Protein<Vec<AminoAcid>> {
+    /// Create a new `Protein` from a sequence of fallible residues.
+    ///
+    /// The sequence is consumed eagerly, and the first error encountered
+    /// is returned with the position of the residue, starting at 1.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid;
+    /// use proteinogenic::Protein;
+    ///
+    /// let prot = Protein::try_new("GC".chars().map(AminoAcid::from_char)).unwrap();
+    /// assert_eq!(prot.smiles().unwrap(), "NCC(=O)N[C@@H](CS)C(=O)-O");
+    ///
+    /// let err = Protein::try_new("G1C".chars().map(AminoAcid::from_char)).unwrap_err();
+    /// assert_eq!(err.position, Some(2));
+    /// assert_eq!(err.symbol, Some('1'));
+    /// ```
+    pub fn try_new<I>(sequence: I) -> Result<Self, UnknownResidue>
+    where
+        I: IntoIterator<Item = Result<AminoAcid, UnknownResidue>>,
+    {
+        sequence
+            .into_iter()
+            .zip(1..)
+            .map(|(residue, position)| residue.map_err(|e| e.at(position)))
+            .collect::<Result<Vec<AminoAcid>, UnknownResidue>>()
+            .map(Protein::new)
+    }
+}
+
+impl From<&[AminoAcid]> for Protein<Vec<AminoAcid>> {
+    fn from(sequence: &[AminoAcid]) -> Self {
+        Protein::new(sequence.to_vec())
+    }
+}
+
+impl TryFrom<Vec<char>> for Protein<Vec<AminoAcid>> {
+    type Error = UnknownResidue;
+
+    /// Create a new `Protein` from a sequence of 1-letter codes.
+    ///
+    /// The conversion stops at the first character that cannot be decoded
+    /// with `AminoAcid::from_char`, which is reported along with its
+    /// position in the sequence, starting at 1.
+    fn try_from(sequence: Vec<char>) -> Result<Self, Self::Error> {
+        Protein::try_new(sequence.into_iter().map(AminoAcid::from_char))
+    }
+}
+
+impl TryFrom<&[u8]> for Protein<Vec<AminoAcid>> {
+    type Error = UnknownResidue;
+
+    /// Create a new `Protein` from a byte sequence of 1-letter codes.
+    ///
+    /// This is the representation used for sequences by the `bio` crate,
+    /// so a `bio::utils::TextSlice` can be converted directly.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::Protein;
+    ///
+    /// let prot = Protein::try_from(&b"GC"[..]).unwrap();
+    /// assert_eq!(prot.smiles().unwrap(), "NCC(=O)N[C@@H](CS)C(=O)-O");
+    ///
+    /// let err = Protein::try_from(&b"GCB"[..]).unwrap_err();
+    /// assert_eq!(err.position, Some(3));
+    /// assert_eq!(err.symbol, Some('B'));
+    /// ```
+    fn try_from(sequence: &[u8]) -> Result<Self, Self::Error> {
+        sequence
+            .iter()
+            .zip(1..)
+            .map(|(&symbol, position)| {
+                AminoAcid::from_char(symbol as char).map_err(|e| e.at(position))
+            })
+            .collect::<Result<Vec<AminoAcid>, _>>()
+            .map(Protein::new)
+    }
+}
+
+impl<S> TryFrom<Protein<S>> for Vec<purr::graph::Atom>
+where
+    S: IntoIterator<Item = AminoAcid>,
+{
+    type Error = Error;
+
+    /// Build the molecular graph of a protein for use with `purr`.
+    ///
+    /// `purr` walks molecular graphs rather than arbitrary sources, and its
+    /// `Follower` trait cannot report errors, so the protein is visited
+    /// first and any invalid residue or cross-link is returned here,
+    /// before the graph is handed to `purr` utilities such as
+    /// `purr::walk::walk`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly]);
+    /// let atoms = Vec::<purr::graph::Atom>::try_from(prot).unwrap();
+    /// assert_eq!(atoms.len(), 5);
+    /// ```
+    fn try_from(protein: Protein<S>) -> Result<Self, Self::Error> {
+        let mut builder = purr::graph::Builder::new();
+        protein.visit(&mut builder)?;
+        Ok(builder.build()?)
+    }
+}
+
+impl<S> Protein<S>
+where
+    S: IntoIterator<Item = AminoAcid> + Clone,
+{
+    /// Check that the protein can be rendered without visiting it.
+    ///
+    /// This performs a walk on a copy of the protein with a follower that
+    /// discards every atom, so that errors such as invalid cross-links can
+    /// be detected before committing to a rendering.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.clone().visit(&mut Discard)
+    }
+
+    /// Get the index of the residue at the given position of the sequence.
+    ///
+    /// Positions are counted from 0, like in a slice, while the residue
+    /// indices used by cross-links and modifications are counted from 1.
+    /// Returns `None` if the position is past the end of the sequence.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// let sequence = [Gly, Cys, Gly, Cys];
+    /// let mut prot = proteinogenic::Protein::new(sequence);
+    /// let i = prot.residue_index(1).unwrap();
+    /// let j = prot.residue_index(3).unwrap();
+    /// assert_eq!((i, j), (2, 4));
+    /// assert_eq!(prot.residue_index(4), None);
+    /// prot.cross_link(CrossLink::Cystine(i, j)).unwrap();
+    /// ```
+    pub fn residue_index(&self, position: usize) -> Option<u16> {
+        let length = self.sequence.clone().into_iter().count();
+        if position < length {
+            u16::try_from(position + 1).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Check that the SMILES generated for the protein can be read back.
+    ///
+    /// This is a debugging helper which renders the protein to a SMILES
+    /// string, then parses it again with `purr` to make sure it is
+    /// syntactically valid and that every ring closure digit is matched.
+    /// The text of the first error encountered is returned on failure.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Gly, Cys]);
+    /// prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+    /// assert!(prot.validate_smiles().is_ok());
+    /// ```
+    pub fn validate_smiles(&self) -> Result<(), String> {
+        let smiles = self.clone().smiles().map_err(|e| e.to_string())?;
+        let mut builder = purr::graph::Builder::new();
+        purr::read::read(&smiles, &mut builder, None)
+            .map_err(|e| format!("failed to parse {:?}: {:?}", smiles, e))?;
+        builder
+            .build()
+            .map(|_| ())
+            .map_err(|e| format!("failed to build graph from {:?}: {:?}", smiles, e))
+    }
+
+    /// Count the small molecules lost when assembling the protein.
+    ///
+    /// This is the number of condensations needed to build the protein
+    /// from its free residues:
+    ///
+    /// - each peptide bond, including the bonds of γ-glutamyl and
+    ///   isoaspartyl residues, releases a water molecule;
+    /// - each cyclization, head-to-tail, lasso or branched, releases a
+    ///   water molecule;
+    /// - a pyroglutamate N-terminus releases a water molecule from
+    ///   L-glutamic acid, or an ammonia molecule from L-glutamine;
+    /// - lanthionine, methyllanthionine, selenolanthionine and
+    ///   lysinoalanine cross-links release a water molecule, counting the
+    ///   dehydration of the serine or threonine residue;
+    /// - cystine, diselenide, selenyl sulfide, sactionine thioether and
+    ///   histidine-tyrosine cross-links release a dihydrogen molecule;
+    /// - hydrocarbon staples release an ethylene molecule;
+    /// - custom cross-links are counted once, for the hydrogens of the two
+    ///   side chains replaced by the attachment points of the linker.
+    ///
+    /// Terminal caps such as acetylation or amidation are not counted, as
+    /// they add atoms that are not part of any residue. The atoms of the
+    /// linker of a custom cross-link are not part of any residue either.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Gly, Cys]);
+    /// assert_eq!(prot.condensation_count(), 2);
+    /// prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+    /// assert_eq!(prot.condensation_count(), 3);
+    /// ```
+    pub fn condensation_count(&self) -> usize {
+        let peptide_bonds = self.sequence.clone().into_iter().count().saturating_sub(1);
+        let cyclizations = self.cyclizations.len();
+        let pyroglutamate = (self.n_term == NTerm::Pyroglutamate) as usize;
+        let cross_links = self
+            .cross_links
+            .iter()
+            .filter(|(&index, (_, cross_link))| index == cross_link.residues().0)
+            .count();
+        peptide_bonds + cyclizations + pyroglutamate + cross_links
+    }
+
+    /// Count the atoms of the main chain of the protein.
+    ///
+    /// Each α amino acid contributes its amide nitrogen, its α carbon and
+    /// its carbonyl carbon, while backbone spacers such as β-alanine, β³-amino
+    /// acids, isoaspartyl and γ-linked L-glutamic acid residues also contribute
+    /// the additional carbons between their amine and carboxyl groups. Side
+    /// chains, including the ring of proline, and the atoms of
+    /// the carbonyl groups are not counted, so that thioamide bonds do not
+    /// change the backbone length. For a head-to-tail cyclized protein, this
+    /// is the size of the macrocycle.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Pro, BAla]);
+    /// assert_eq!(prot.backbone_atom_count(), 10);
+    /// ```
+    pub fn backbone_atom_count(&self) -> usize {
+        self.sequence
+            .clone()
+            .into_iter()
+            .zip(1..)
+            .map(|(aa, index)| match aa {
+                AminoAcid::BAla => 4,
+                AminoAcid::Gaba => 5,
+                AminoAcid::Glu if self.gamma_glutamyls.contains(&index) => 5,
+                AminoAcid::Asp | AminoAcid::Asn if self.iso_aspartyls.contains(&index) => 4,
+                _ if self.beta_residues.contains(&index) => 4,
+                _ => 3,
+            })
+            .sum()
+    }
+
+    /// Compute the monoisotopic mass of the protein.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly]);
+    /// let mass = prot.monoisotopic_mass().unwrap();
+    /// assert!((mass - 75.032028).abs() < 1e-6);
+    /// ```
+    pub fn monoisotopic_mass(&self) -> Result<f64, Error> {
+        self.atoms().map(|atoms| mass::molecule_mass(&atoms))
+    }
+
+    /// Compute the molecular formula of the protein, in Hill notation.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Sec]);
+    /// assert_eq!(prot.molecular_formula().unwrap(), "C5H10N2O3Se");
+    /// ```
+    pub fn molecular_formula(&self) -> Result<String, Error> {
+        self.atoms().map(|atoms| mass::molecular_formula(&atoms))
+    }
+
+    /// Break down the molecular formula of the protein by residue.
+    ///
+    /// Each residue is listed with its index and the formula of the residue
+    /// as incorporated in a peptide chain, i.e. the formula of the free
+    /// amino acid minus a water molecule. Everything else is attributed to
+    /// a synthetic entry with the index 0, listed first: the water of the
+    /// free termini, the terminal modifications, the cross-links, the
+    /// cyclizations and the side chain modifications. The counts of every
+    /// entry sum up to the molecular formula of the protein, and elements
+    /// with a null count are omitted.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Gly, Cys]);
+    /// prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+    /// let breakdown = prot.formula_breakdown().unwrap();
+    /// assert_eq!(breakdown[0], (0, [("O", 1)].into_iter().collect()));
+    /// assert_eq!(
+    ///     breakdown[2],
+    ///     (2, [("C", 2), ("H", 3), ("N", 1), ("O", 1)].into_iter().collect())
+    /// );
+    /// ```
+    pub fn formula_breakdown(&self) -> Result<Vec<(u16, Formula)>, Error> {
+        let signed = |counts: BTreeMap<&'static str, usize>| -> Formula {
+            counts
+                .into_iter()
+                .map(|(symbol, count)| (symbol, count as i32))
+                .collect()
+        };
+
+        let mut modifications = signed(mass::element_counts(&self.atoms()?));
+        let mut breakdown = vec![];
+        for (index, aa) in self.sequence.clone().into_iter().enumerate() {
+            let mut free = Protein::new([aa]);
+            free.masking(self.masking);
+            let mut formula = signed(mass::element_counts(&free.atoms()?));
+            *formula.entry("H").or_default() -= 2;
+            *formula.entry("O").or_default() -= 1;
+            formula.retain(|_, &mut count| count != 0);
+            for (&symbol, &count) in formula.iter() {
+                *modifications.entry(symbol).or_default() -= count;
+            }
+            breakdown.push((index as u16 + 1, formula));
+        }
+
+        modifications.retain(|_, &mut count| count != 0);
+        breakdown.insert(0, (0, modifications));
+        Ok(breakdown)
+    }
+
+    /// Count the hydrogen bond donors of the protein.
+    ///
+    /// Donors are counted following Lipinski's rule of five, as the number
+    /// of nitrogen and oxygen atoms carrying at least one hydrogen. Groups
+    /// consumed by cross-links, cyclizations or terminal modifications are
+    /// not counted.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Ser]);
+    /// assert_eq!(prot.hbd_count().unwrap(), 4);
+    /// ```
+    pub fn hbd_count(&self) -> Result<usize, Error> {
+        self.atoms().map(|atoms| mass::hbd_count(&atoms))
+    }
+
+    /// Count the hydrogen bond acceptors of the protein.
+    ///
+    /// Acceptors are counted following Lipinski's rule of five, as the
+    /// number of nitrogen and oxygen atoms.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Ser]);
+    /// assert_eq!(prot.hba_count().unwrap(), 6);
+    /// ```
+    pub fn hba_count(&self) -> Result<usize, Error> {
+        self.atoms().map(|atoms| mass::hba_count(&atoms))
+    }
+
+    /// Export the protein as a V2000 MDL Molfile.
+    ///
+    /// Aromatic rings are kekulized, and all atoms are placed at the
+    /// origin since no 2D layout is computed. Stereochemistry is not
+    /// exported, as it cannot be expressed without coordinates.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly]);
+    /// let molfile = prot.molfile().unwrap();
+    /// assert!(molfile.contains("  5  4  0  0  0  0  0  0  0  0999 V2000"));
+    /// assert!(molfile.ends_with("M  END\n"));
+    /// ```
+    pub fn molfile(&self) -> Result<String, Error> {
+        let mut protein = self.clone();
+        protein.kekulize(true);
+        protein
+            .atoms()
+            .and_then(|atoms| molfile::write(&atoms).ok_or(Error::TooManyAtoms))
+    }
+
+    /// Export the heavy atoms and bonds of the protein as flat arrays.
+    ///
+    /// Hydrogens are left implicit. Every bond, including the ones closing
+    /// rings, is listed exactly once, with its kind resolved to a single,
+    /// double, triple or aromatic bond, so stereochemistry is not exported.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use purr::feature::BondKind;
+    /// use purr::feature::Element;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly]);
+    /// prot.zwitterion(true);
+    /// let (elements, charges, bonds) = prot.atom_table().unwrap();
+    /// assert_eq!(elements[0], Some(Element::N));
+    /// assert_eq!(charges, [1, 0, 0, 0, -1]);
+    /// assert_eq!(bonds[2], (2, 3, BondKind::Double));
+    /// ```
+    pub fn atom_table(&self) -> Result<AtomTable, Error> {
+        let atoms = self.atoms()?;
+        let infos = atoms
+            .iter()
+            .map(|atom| Atom::from_kind(&atom.kind))
+            .collect::<Vec<_>>();
+        let elements = atoms.iter().map(|atom| mass::element(&atom.kind)).collect();
+        let charges = infos.iter().map(|info| info.charge).collect();
+        let bonds = atoms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, atom)| {
+                atom.bonds
+                    .iter()
+                    .filter(move |bond| i < bond.tid)
+                    .map(move |bond| (i, bond))
+            })
+            .map(|(i, bond)| {
+                let kind = match bond.kind {
+                    BondKind::Elided if infos[i].aromatic && infos[bond.tid].aromatic => {
+                        BondKind::Aromatic
+                    }
+                    BondKind::Elided | BondKind::Up | BondKind::Down => BondKind::Single,
+                    ref other => other.clone(),
+                };
+                (i, bond.tid, kind)
+            })
+            .collect();
+        Ok((elements, charges, bonds))
+    }
+
+    /// Count the rings of the protein.
+    ///
+    /// The number of rings is given by the cyclomatic number of the
+    /// molecular graph, i.e. the number of bonds minus the number of atoms
+    /// plus the number of connected components. This counts the rings of
+    /// the side chains as well as the rings formed by cross-links and
+    /// cyclizations.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Pro, Cys]);
+    /// assert_eq!(prot.ring_count().unwrap(), 1);
+    /// prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+    /// assert_eq!(prot.ring_count().unwrap(), 2);
+    /// ```
+    pub fn ring_count(&self) -> Result<usize, Error> {
+        let atoms = self.atoms()?;
+        let bonds = atoms.iter().map(|atom| atom.bonds.len()).sum::<usize>() / 2;
+
+        let mut components = 0;
+        let mut visited = vec![false; atoms.len()];
+        for start in 0..atoms.len() {
+            if visited[start] {
+                continue;
+            }
+            components += 1;
+            visited[start] = true;
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                for bond in atoms[i].bonds.iter() {
+                    if !visited[bond.tid] {
+                        visited[bond.tid] = true;
+                        stack.push(bond.tid);
+                    }
+                }
+            }
+        }
+
+        Ok(bonds + components - atoms.len())
+    }
+
+    /// Check whether the protein contains a L-selenocysteine residue.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// assert!(proteinogenic::Protein::new([Gly, Sec]).has_selenocysteine());
+    /// assert!(!proteinogenic::Protein::new([Gly, Cys]).has_selenocysteine());
+    /// ```
+    pub fn has_selenocysteine(&self) -> bool {
+        self.sequence
+            .clone()
+            .into_iter()
+            .any(|aa| aa == AminoAcid::Sec)
+    }
+
+    /// Estimate the net charge of the protein at the given pH.
+    ///
+    /// The charge is computed with the Henderson-Hasselbalch equation from
+    /// the pKa values of EMBOSS for the free termini and the ionizable side
+    /// chains, with a pKa of 5.2 for the selenol of L-selenocysteine, which
+    /// is mostly deprotonated at physiological pH unlike the thiol of
+    /// L-cysteine. Side chains involved in a cross-link or a cyclization
+    /// are not ionizable, and neither are modified side chains, except for
+    /// protonated ones which are counted as fully charged, and deamidated
+    /// ones which are counted as the acids they yield.
+    ///
+    /// The N-terminal amine is secondary when the first residue is a
+    /// L-proline or when the N-terminus is methylated, and is then given a
+    /// pKa of 9.4 rather than 8.6, since secondary amines are more basic
+    /// than primary ones, as seen between free L-proline and L-alanine.
+    /// The phosphate of a GPI anchor stub is counted as a phosphate
+    /// monoester, with the pKa values of methyl phosphate (1.5 and 6.3).
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Sec]);
+    /// assert!((prot.net_charge(7.0) + 1.0).abs() < 0.02);
+    /// let prot = proteinogenic::Protein::new([Gly, Cys]);
+    /// assert!(prot.net_charge(7.0).abs() < 0.1);
+    /// let prot = proteinogenic::Protein::new([Pro, Cys]);
+    /// assert!(prot.net_charge(9.0) > proteinogenic::Protein::new([Gly, Cys]).net_charge(9.0));
+    /// ```
+    pub fn net_charge(&self, ph: f64) -> f64 {
+        let base = |pka: f64| 1.0 / (1.0 + 10f64.powf(ph - pka));
+        let acid = |pka: f64| -1.0 / (1.0 + 10f64.powf(pka - ph));
+
+        let mut charge = 0.0;
+        let amine = matches!(self.n_term, NTerm::Free | NTerm::Methyl);
+        if amine && !self.cyclizations.iter().any(Cyclization::n_terminal) {
+            let first = self.sequence.clone().into_iter().next();
+            let secondary = self.n_term == NTerm::Methyl || first == Some(AminoAcid::Pro);
+            charge += base(if secondary { 9.4 } else { 8.6 });
+        }
+        if self.c_term == CTerm::Free && !self.cyclizations.iter().any(Cyclization::c_terminal) {
+            charge += acid(3.6);
+        }
+        if self.c_term == CTerm::GpiEthanolamine {
+            charge += acid(1.5) + acid(6.3);
+        }
+
+        for (i, aa) in self.sequence.clone().into_iter().enumerate() {
+            let index = i as u16 + 1;
+            let cyclized = self
+                .cyclizations
+                .iter()
+                .any(|cyclization| cyclization.side_chain() == Some(index));
+            if cyclized || self.cross_links.contains_key(&index) {
+                continue;
+            }
+            let modifications = self
+                .modifications
+                .get(&index)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let aa = match modifications {
+                [] => aa,
+                [Modification::Protonated] => {
+                    charge += 1.0;
+                    continue;
+                }
+                [Modification::Deamidation] if aa == AminoAcid::Asn => AminoAcid::Asp,
+                [Modification::Deamidation] if aa == AminoAcid::Gln => AminoAcid::Glu,
+                _ => continue,
+            };
+            charge += match aa {
+                AminoAcid::Asp => acid(3.9),
+                AminoAcid::Glu => acid(4.1),
+                AminoAcid::Cys => acid(8.5),
+                AminoAcid::Tyr => acid(10.1),
+                AminoAcid::Sec => acid(5.2),
+                AminoAcid::His => base(6.5),
+                AminoAcid::Lys | AminoAcid::Orn => base(10.8),
+                AminoAcid::Dab => base(10.4),
+                AminoAcid::Dap => base(9.4),
+                AminoAcid::Arg | AminoAcid::Har => base(12.5),
+                _ => 0.0,
+            };
+        }
+        charge
+    }
+
+    /// Compute the monoisotopic masses of the b and y fragment ions.
+    ///
+    /// The masses are computed for singly-charged ions at each cleavage
+    /// position of the backbone: the first element of each vector is
+    /// obtained by breaking the peptide bond between the first and the
+    /// second residue, and so on. A mass is `None` when the cleavage site
+    /// is spanned by a cross-link or a cyclization, since the cleavage
+    /// would not produce independent fragments.
+    #[allow(clippy::type_complexity)]
+    pub fn fragment_masses(&self) -> Result<(Vec<Option<f64>>, Vec<Option<f64>>), Error> {
+        use purr::feature::Element;
+
+        let sequence = self.sequence.clone().into_iter().collect::<Vec<_>>();
+        let length = sequence.len() as u16;
+        let water =
+            2.0 * mass::monoisotopic_mass(&Element::H) + mass::monoisotopic_mass(&Element::O);
+
+        let mut b_ions = Vec::new();
+        let mut y_ions = Vec::new();
+        for i in 1..length {
+            let prefix = self.fragment(&sequence, 0, i);
+            let suffix = self.fragment(&sequence, i, length);
+            match (prefix, suffix) {
+                (Some(prefix), Some(suffix)) => {
+                    let mut b = prefix.monoisotopic_mass()? - water + mass::PROTON;
+                    if self.thioamides.contains(&i) {
+                        // the acylium ion keeps the sulfur of the thioamide bond
+                        b += mass::monoisotopic_mass(&Element::S)
+                            - mass::monoisotopic_mass(&Element::O);
+                    }
+                    b_ions.push(Some(b));
+                    y_ions.push(Some(suffix.monoisotopic_mass()? + mass::PROTON));
+                }
+                _ => {
+                    b_ions.push(None);
+                    y_ions.push(None);
+                }
+            }
+        }
+
+        Ok((b_ions, y_ions))
+    }
+
+    /// Get the range of atom indices belonging to each residue.
+    ///
+    /// Atoms are indexed in the order they are visited, which is also the
+    /// order in which they appear in the SMILES string of the protein. The
+    /// atoms of the termini are attributed to the first and last residues.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Ala]);
+    /// assert_eq!(prot.atom_map().unwrap(), vec![0..4, 4..10]);
+    /// ```
+    pub fn atom_map(&self) -> Result<Vec<Range<usize>>, Error> {
+        let mut starts = Vec::new();
+        let mut counter = Counter::default();
+        self.clone()
+            .walk(&mut counter, |c| starts.push(c.atoms), &HashMap::new(), 0)?;
+
+        let ends = starts.iter().skip(1).cloned().chain(Some(counter.atoms));
+        Ok(starts.iter().zip(ends).map(|(&s, e)| s..e).collect())
+    }
+
+    /// Generate a canonical SMILES string for the protein.
+    ///
+    /// The atoms are written in an order that only depends on the structure
+    /// of the protein, so that two proteins with the same structure get the
+    /// same canonical SMILES, even when they were described differently,
+    /// for instance with a head-to-tail cyclization starting at another
+    /// residue. Stereocenters are not used to order the atoms.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Cyclization;
+    ///
+    /// let mut a = proteinogenic::Protein::new([Gly, Ala, Ser]);
+    /// a.cyclization(Cyclization::HeadToTail);
+    /// let mut b = proteinogenic::Protein::new([Ala, Ser, Gly]);
+    /// b.cyclization(Cyclization::HeadToTail);
+    /// assert_ne!(a.clone().smiles().unwrap(), b.clone().smiles().unwrap());
+    /// assert_eq!(a.canonical_smiles().unwrap(), b.canonical_smiles().unwrap());
+    /// ```
+    pub fn canonical_smiles(&self) -> Result<String, Error> {
+        let mut writer = writer::Writer::new();
+        self.canonical_visit(&mut writer)?;
+        Ok(writer.write())
+    }
+
+    /// Get the order in which atoms are written in the canonical SMILES.
+    ///
+    /// The element at position `i` is the index of the `i`-th atom of the
+    /// canonical SMILES string among the atoms of `Protein::smiles`, which
+    /// can then be traced back to its residue with `Protein::atom_map`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Gly, Ala]);
+    /// let order = prot.canonical_atom_order().unwrap();
+    /// let map = prot.atom_map().unwrap();
+    /// let residues = order
+    ///     .iter()
+    ///     .map(|&atom| map.iter().position(|range| range.contains(&atom)).unwrap())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(residues.len(), 10);
+    /// assert_eq!(residues.iter().filter(|&&r| r == 0).count(), 4);
+    /// ```
+    pub fn canonical_atom_order(&self) -> Result<Vec<usize>, Error> {
+        self.canonical_visit(&mut Discard)
+    }
+
+    /// Visit the protein in canonical order, returning the order of the atoms.
+    fn canonical_visit<F: Follower>(&self, follower: &mut F) -> Result<Vec<usize>, Error> {
+        let mut graph = reference::Graph::default();
+        self.clone().visit(&mut graph)?;
+        canonical::visit(&graph, follower)
+    }
+
+    /// Get the configuration of the α carbon of every residue.
+    ///
+    /// The configurations are read from the atoms rendered by the walk, and
+    /// compared to the ones rendered when every residue is an L-amino acid.
+    /// This can be used to check that D-amino acids were set as expected.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::StereoLabel;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Ala, Gly, Pro]);
+    /// prot.d_amino_acid(3);
+    /// assert_eq!(
+    ///     prot.stereocenters().unwrap(),
+    ///     vec![(1, StereoLabel::L), (2, StereoLabel::Achiral), (3, StereoLabel::D)],
+    /// );
+    /// ```
+    pub fn stereocenters(&self) -> Result<Vec<(u16, StereoLabel)>, Error> {
+        let mut reference = self.clone();
+        reference.d_residues.clear();
+
+        let actual = self.alpha_configurations()?;
+        let reference = reference.alpha_configurations()?;
+        Ok(actual
+            .into_iter()
+            .zip(reference)
+            .zip(1..)
+            .map(|((actual, reference), index)| match actual {
+                None => (index, StereoLabel::Achiral),
+                _ if actual == reference => (index, StereoLabel::L),
+                _ => (index, StereoLabel::D),
+            })
+            .collect())
+    }
+
+    /// Get the configuration of the α carbon rendered for every residue.
+    ///
+    /// The α carbon is the first stereocenter visited for each residue,
+    /// since residues without a chiral α carbon have no stereocenter.
+    fn alpha_configurations(&self) -> Result<Vec<Option<Configuration>>, Error> {
+        let mut starts = Vec::new();
+        let mut recorder = Recorder::default();
+        self.clone().walk(
+            &mut recorder,
+            |r| starts.push(r.events.len()),
+            &HashMap::new(),
+            0,
+        )?;
+
+        let ends = starts
+            .iter()
+            .skip(1)
+            .cloned()
+            .chain(Some(recorder.events.len()));
+        Ok(starts
+            .iter()
+            .zip(ends)
+            .map(|(&s, e)| {
+                recorder.events[s..e].iter().find_map(|event| match event {
+                    Event::Root(AtomKind::Bracket { configuration, .. })
+                    | Event::Extend(_, AtomKind::Bracket { configuration, .. }) => {
+                        configuration.clone()
+                    }
+                    _ => None,
+                })
+            })
+            .collect())
+    }
+
+    /// Build the molecular graph of the protein.
+    fn atoms(&self) -> Result<Vec<purr::graph::Atom>, Error> {
+        Vec::try_from(self.clone())
+    }
+
+    /// Extract the residues between `start` (excluded) and `end` (included).
+    ///
+    /// Returns `None` if a cross-link or a cyclization bridges a residue
+    /// of the fragment to a residue outside of it.
+    fn fragment(
+        &self,
+        sequence: &[AminoAcid],
+        start: u16,
+        end: u16,
+    ) -> Option<Protein<Vec<AminoAcid>>> {
+        let inside = |i: u16| i > start && i <= end;
+        let mut protein = Protein::new(sequence[start as usize..end as usize].to_vec());
+        protein.full_stereo(self.full_stereo);
+        if start == 0 {
+            protein.n_terminus(self.n_term);
+        }
+        if end as usize == sequence.len() {
+            protein.c_terminus(self.c_term);
+        }
+
+        for &cyclization in self.cyclizations.iter() {
+            match cyclization {
+                Cyclization::None => (),
+                Cyclization::HeadToTail => return None,
+                Cyclization::Lasso(k) => match (inside(1), inside(k)) {
+                    (true, true) => {
+                        protein.add_cyclization(Cyclization::Lasso(k)).ok()?;
+                    }
+                    (false, false) => (),
+                    _ => return None,
+                },
+                Cyclization::Branched(k) => match (inside(k), end as usize == sequence.len()) {
+                    (true, true) => {
+                        protein
+                            .add_cyclization(Cyclization::Branched(k - start))
+                            .ok()?;
+                    }
+                    (false, false) => (),
+                    _ => return None,
+                },
+            }
+        }
+
+        for (&index, (_, cross_link)) in self.cross_links.iter() {
+            let (i, j) = cross_link.residues();
+            match (inside(i), inside(j)) {
+                (true, true) if index == i => {
+                    let cross_link = cross_link.with_residues(i - start, j - start);
+                    protein.cross_link(cross_link).ok()?;
+                }
+                (true, true) | (false, false) => (),
+                _ => return None,
+            }
+        }
+
+        for (&i, stack) in self.modifications.iter().filter(|(&i, _)| inside(i)) {
+            for &modification in stack {
+                protein.add_modification(i - start, modification);
+            }
+        }
+        for &i in self.thioamides.iter().filter(|&&i| inside(i) && i != end) {
+            protein.thioamide_bond(i - start);
+        }
+        for &i in self.gamma_glutamyls.iter().filter(|&&i| inside(i)) {
+            protein.gamma_glutamyl(i - start);
+        }
+        for &i in self.iso_aspartyls.iter().filter(|&&i| inside(i)) {
+            protein.iso_aspartate(i - start);
+        }
+        for &i in self
+            .cis_prolines
+            .iter()
+            .filter(|&&i| inside(i) && i != start + 1)
+        {
+            protein.proline_cis(i - start);
+        }
+        for &i in self.d_residues.iter().filter(|&&i| inside(i)) {
+            protein.d_amino_acid(i - start);
+        }
+        protein.masking(self.masking);
+        for &i in self.beta_residues.iter().filter(|&&i| inside(i)) {
+            protein.beta_amino_acid(i - start);
+        }
+        for (&i, &geometry) in self.geometries.iter().filter(|(&i, _)| inside(i)) {
+            protein.geometry(i - start, geometry);
+        }
+        for (&i, &melan) in self.melan_configurations.iter().filter(|(&i, _)| inside(i)) {
+            protein.melan_configuration(i - start, melan);
+        }
+
+        Some(protein)
+    }
+
+    /// Create a SMILES string for the backbone of the protein.
+    ///
+    /// Side chains are replaced with a methyl stub, keeping the
+    /// configuration of the α carbon, while glycine, β-alanine and GABA
+    /// residues are left unchanged. L-proline keeps its ring, since it
+    /// constrains the backbone, and the side chains of γ-glutamyl,
+    /// isoaspartyl and pyroglutamate residues are kept as they take part
+    /// in the main chain. Dehydrobutyrine is rendered as dehydroalanine,
+    /// and unknown residues (`Xaa`) are rendered as L-alanine like any other
+    /// side chain. Cross-links, side chain cyclizations and modifications
+    /// are omitted.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Ser, Pro, Gly]);
+    /// assert_eq!(
+    ///     prot.backbone_smiles().unwrap(),
+    ///     "N[C@@H](C)C(=O)N1CCC[C@H]1C(=O)NCC(=O)-O"
+    /// );
+    /// ```
+    pub fn backbone_smiles(&self) -> Result<String, Error> {
+        self.validate()?;
+        let sequence = (1..)
+            .zip(self.sequence.clone())
+            .map(|(index, aa)| match aa {
+                AminoAcid::Gly
+                | AminoAcid::Pro
+                | AminoAcid::BAla
+                | AminoAcid::Gaba
+                | AminoAcid::Dha => aa,
+                AminoAcid::Dhb => AminoAcid::Dha,
+                AminoAcid::Glu if self.gamma_glutamyls.contains(&index) => aa,
+                AminoAcid::Asp | AminoAcid::Asn if self.iso_aspartyls.contains(&index) => {
+                    AminoAcid::Asp
+                }
+                AminoAcid::Glu | AminoAcid::Gln
+                    if index == 1 && self.n_term == NTerm::Pyroglutamate =>
+                {
+                    aa
+                }
+                _ => AminoAcid::Ala,
+            })
+            .collect::<Vec<AminoAcid>>();
+        let mut protein = Protein::new(sequence);
+        protein
+            .ring_numbering(self.ring_numbering)
+            .full_stereo(self.full_stereo)
+            .residue_maps(self.residue_maps)
+            .kekulize(self.kekulize)
+            .masking(self.masking)
+            .zwitterion(self.zwitterion)
+            .n_terminus(self.n_term)
+            .c_terminus(self.c_term);
+        protein.explicit_hydrogens = self.explicit_hydrogens;
+        if self.cyclizations.contains(&Cyclization::HeadToTail) {
+            protein.cyclization(Cyclization::HeadToTail);
+        }
+        for &i in self.thioamides.iter() {
+            protein.thioamide_bond(i);
+        }
+        for &i in self.gamma_glutamyls.iter() {
+            protein.gamma_glutamyl(i);
+        }
+        for &i in self.iso_aspartyls.iter() {
+            protein.iso_aspartate(i);
+        }
+        for &i in self.cis_prolines.iter() {
+            protein.proline_cis(i);
+        }
+        for &i in self.d_residues.iter() {
+            protein.d_amino_acid(i);
+        }
+        for &i in self.beta_residues.iter() {
+            protein.beta_amino_acid(i);
+        }
+        protein.smiles()
+    }
+
+    /// Generate the retro-inverso analog of the protein.
+    ///
+    /// The sequence is reversed and every residue is replaced with its
+    /// enantiomer, so that the side chains keep their orientation while the
+    /// direction of every peptide bond is inverted. Cross-links, thioamide
+    /// bonds and modifications are remapped to the reversed sequence.
+    ///
+    /// # Errors
+    /// Features that involve a single terminus cannot be reversed: an error
+    /// is returned if the protein has a modified terminus, a lasso or a
+    /// branched cyclization, or a γ-glutamyl bond. Cis peptide bonds cannot
+    /// be kept either, since the bond preceding each L-proline is replaced.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Ala, Gly, Ser]);
+    /// let retro = prot.retro_inverso().unwrap();
+    /// assert_eq!(
+    ///     retro.smiles().unwrap(),
+    ///     "N[C@H](CO)C(=O)NCC(=O)N[C@H](C)C(=O)-O"
+    /// );
+    /// ```
+    pub fn retro_inverso(&self) -> Result<Protein<Vec<AminoAcid>>, Error> {
+        let mut sequence = self.sequence.clone().into_iter().collect::<Vec<_>>();
+        sequence.reverse();
+        let length = sequence.len() as u16;
+        let reverse = |i: u16| length + 1 - i;
+
+        if self.n_term != NTerm::Free {
+            return Err(Error::InvalidNTerm(self.n_term));
+        }
+        if self.c_term != CTerm::Free {
+            return Err(Error::InvalidCTerm(self.c_term));
+        }
+        // report the residue actually found at the first offending index
+        let residue = |i: u16| sequence[(reverse(i) - 1) as usize];
+        if let Some(&i) = self.gamma_glutamyls.iter().filter(|&&i| i <= length).min() {
+            return Err(Error::InvalidGammaGlutamyl(i, residue(i)));
+        }
+        if let Some(&i) = self.iso_aspartyls.iter().filter(|&&i| i <= length).min() {
+            return Err(Error::InvalidIsoAspartyl(i, residue(i)));
+        }
+        if let Some(&i) = self.cis_prolines.iter().filter(|&&i| i <= length).min() {
+            return Err(Error::InvalidCisProline(i, residue(i)));
+        }
+
+        let mut protein = Protein::new(sequence);
+        protein
+            .ring_numbering(self.ring_numbering)
+            .full_stereo(self.full_stereo)
+            .residue_maps(self.residue_maps)
+            .kekulize(self.kekulize)
+            .masking(self.masking);
+        protein.explicit_hydrogens = self.explicit_hydrogens;
+        protein.zwitterion(self.zwitterion);
+
+        for &cyclization in self.cyclizations.iter() {
+            match cyclization {
+                Cyclization::None | Cyclization::HeadToTail => {
+                    protein.add_cyclization(cyclization)?;
+                }
+                Cyclization::Lasso(k) | Cyclization::Branched(k) => {
+                    let aa = protein.sequence[(reverse(k) - 1) as usize];
+                    return Err(Error::InvalidCyclization(k, aa, cyclization));
+                }
+            }
+        }
+
+        // add the cross-links in their original order to keep the numbering
+        let mut cross_links = self
+            .cross_links
+            .iter()
+            .filter(|(&index, (_, cross_link))| index == cross_link.residues().0)
+            .map(|(_, &(num, cross_link))| (num, cross_link))
+            .collect::<Vec<_>>();
+        cross_links.sort_unstable_by_key(|&(num, _)| num);
+        for (_, cross_link) in cross_links {
+            let (i, j) = cross_link.residues();
+            protein.cross_link(cross_link.with_residues(reverse(i), reverse(j)))?;
+        }
+
+        for (&i, stack) in self.modifications.iter() {
+            for &modification in stack {
+                protein.add_modification(reverse(i), modification);
+            }
+        }
+        // the bond after residue `i` is now formed by the carbonyl of the
+        // residue preceding it in the reversed sequence
+        for &i in self.thioamides.iter() {
+            protein.thioamide_bond(if i == length { length } else { length - i });
+        }
+        for (&i, &geometry) in self.geometries.iter() {
+            protein.geometry(reverse(i), geometry);
+        }
+        for (&i, &melan) in self.melan_configurations.iter() {
+            protein.melan_configuration(reverse(i), melan);
+        }
+        for &i in self.beta_residues.iter() {
+            protein.beta_amino_acid(reverse(i));
+        }
+        for i in (1..=length).filter(|i| !self.d_residues.contains(i)) {
+            protein.d_amino_acid(reverse(i));
+        }
+
+        Ok(protein)
+    }
+
+    /// Extract the residues at the given positions of the sequence.
+    ///
+    /// Positions are counted from 0, like in a slice, and the residues of
+    /// the subsequence are re-indexed from 1 along with their cross-links,
+    /// modifications and other per-residue features. This is useful to
+    /// render a single domain of a large protein.
+    ///
+    /// Cross-links and cyclizations spanning the boundary of the range are
+    /// dropped, and returned with their original residue indices so that
+    /// the cut can be reported. Terminal modifications are only kept if
+    /// the termini they modify are part of the range, and the termini
+    /// created by the cut are left free.
+    ///
+    /// # Errors
+    /// An `Error::InvalidRange` is returned if the start of the range is
+    /// after its end, and an `Error::InvalidResidue` is returned with the
+    /// index of the last residue of the range if the range is past the end
+    /// of the sequence.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::CrossLink;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Cys, Cys, Gly, Cys, Cys]);
+    /// prot.disulfides("1-4, 2-5").unwrap();
+    /// let (domain, cut, _) = prot.subsequence(1..5).unwrap();
+    /// assert_eq!(cut, vec![CrossLink::Cystine(1, 4)]);
+    /// assert_eq!(domain.smiles().unwrap().matches('3').count(), 2);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn subsequence(
+        &self,
+        range: Range<usize>,
+    ) -> Result<(Protein<Vec<AminoAcid>>, Vec<CrossLink>, Vec<Cyclization>), Error> {
+        let invalid_range = || Error::InvalidRange(range.start, range.end);
+        if range.start > range.end {
+            return Err(invalid_range());
+        }
+        let end = u16::try_from(range.end).map_err(|_| invalid_range())?;
+        let offset = u16::try_from(range.start).map_err(|_| invalid_range())?;
+        let sequence = self.sequence.clone().into_iter().collect::<Vec<_>>();
+        let residues = match sequence.get(range.clone()) {
+            Some(residues) => residues.to_vec(),
+            None => return Err(Error::InvalidResidue(end)),
+        };
+        let shift = |i: u16| match usize::from(i).checked_sub(1) {
+            Some(position) if range.contains(&position) => Some(i - offset),
+            _ => None,
+        };
+
+        let mut protein = Protein::new(residues);
+        protein
+            .ring_numbering(self.ring_numbering)
+            .full_stereo(self.full_stereo)
+            .residue_maps(self.residue_maps)
+            .kekulize(self.kekulize)
+            .masking(self.masking);
+        protein.explicit_hydrogens = self.explicit_hydrogens;
+        protein.zwitterion(self.zwitterion);
+
+        let n_terminal = range.start == 0;
+        let c_terminal = range.end == sequence.len();
+        if n_terminal {
+            protein.n_terminus(self.n_term);
+        }
+        if c_terminal {
+            protein.c_terminus(self.c_term);
+        }
+        // a cyclization is kept if both of its ends are part of the range,
+        // or cut if only one of them is
+        let mut cut_cyclizations = Vec::new();
+        for &cyclization in self.cyclizations.iter() {
+            let (start, end, kept) = match cyclization {
+                Cyclization::None => continue,
+                Cyclization::HeadToTail => (n_terminal, c_terminal, cyclization),
+                Cyclization::Lasso(k) => match shift(k) {
+                    Some(k) => (n_terminal, true, Cyclization::Lasso(k)),
+                    None => (n_terminal, false, cyclization),
+                },
+                Cyclization::Branched(k) => match shift(k) {
+                    Some(k) => (true, c_terminal, Cyclization::Branched(k)),
+                    None => (false, c_terminal, cyclization),
+                },
+            };
+            match (start, end) {
+                (true, true) => {
+                    protein.add_cyclization(kept)?;
+                }
+                (false, false) => (),
+                _ => cut_cyclizations.push(cyclization),
+            }
+        }
+
+        // add the cross-links in their original order to keep the numbering
+        let mut cross_links = self
+            .cross_links
+            .iter()
+            .filter(|(&index, (_, cross_link))| index == cross_link.residues().0)
+            .map(|(_, &(num, cross_link))| (num, cross_link))
+            .collect::<Vec<_>>();
+        cross_links.sort_unstable_by_key(|&(num, _)| num);
+        let mut cut = Vec::new();
+        for (_, cross_link) in cross_links {
+            let (i, j) = cross_link.residues();
+            match (shift(i), shift(j)) {
+                (Some(i), Some(j)) => {
+                    protein.cross_link(cross_link.with_residues(i, j))?;
+                }
+                (None, None) => (),
+                _ => cut.push(cross_link),
+            }
+        }
+
+        for (i, stack) in self
+            .modifications
+            .iter()
+            .filter_map(|(&i, m)| Some((shift(i)?, m)))
+        {
+            for &modification in stack {
+                protein.add_modification(i, modification);
+            }
+        }
+        // the bond after the last residue of the range is cut, unless it
+        // is the C-terminal thioacid of the whole sequence
+        for &i in self.thioamides.iter() {
+            if usize::from(i) < range.end || usize::from(i) == sequence.len() {
+                if let Some(i) = shift(i) {
+                    protein.thioamide_bond(i);
+                }
+            }
+        }
+        for (i, &geometry) in self
+            .geometries
+            .iter()
+            .filter_map(|(&i, g)| Some((shift(i)?, g)))
+        {
+            protein.geometry(i, geometry);
+        }
+        for (i, &melan) in self
+            .melan_configurations
+            .iter()
+            .filter_map(|(&i, m)| Some((shift(i)?, m)))
+        {
+            protein.melan_configuration(i, melan);
+        }
+        for i in self.gamma_glutamyls.iter().filter_map(|&i| shift(i)) {
+            protein.gamma_glutamyl(i);
+        }
+        for i in self.iso_aspartyls.iter().filter_map(|&i| shift(i)) {
+            protein.iso_aspartate(i);
+        }
+        for i in self.cis_prolines.iter().filter_map(|&i| shift(i)) {
+            protein.proline_cis(i);
+        }
+        for i in self.beta_residues.iter().filter_map(|&i| shift(i)) {
+            protein.beta_amino_acid(i);
+        }
+        for i in self.d_residues.iter().filter_map(|&i| shift(i)) {
+            protein.d_amino_acid(i);
+        }
+
+        Ok((protein, cut, cut_cyclizations))
+    }
+
+    /// Generate every disulfide connectivity isomer of the protein.
+    ///
+    /// The cysteine residues not yet involved in a cross-link are paired
+    /// with `CrossLink::Cystine` in every possible way. When the number of
+    /// free cysteines is odd, exactly one of them is left unpaired in each
+    /// isomer. Cross-links already present on the protein are retained.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    ///
+    /// let prot = proteinogenic::Protein::new([Cys, Gly, Cys, Cys, Cys]);
+    /// assert_eq!(prot.disulfide_isomers().count(), 3);
+    /// ```
+    pub fn disulfide_isomers(&self) -> impl Iterator<Item = Protein<S>> + '_ {
+        let free = self
+            .sequence
+            .clone()
+            .into_iter()
+            .enumerate()
+            .map(|(index, aa)| (index as u16 + 1, aa))
+            .filter(|(index, aa)| *aa == AminoAcid::Cys && !self.cross_links.contains_key(index))
+            .map(|(index, _)| index)
+            .collect::<Vec<u16>>();
+
+        let mut pairings = Vec::new();
+        disulfide_pairings(&free, free.len() % 2, &mut Vec::new(), &mut pairings);
+
+        pairings.into_iter().map(move |pairs| {
+            let mut protein = self.clone();
+            for (i, j) in pairs {
+                protein
+                    .cross_link(CrossLink::Cystine(i, j))
+                    .expect("free cysteines cannot be involved in a cross-link");
+            }
+            protein
+        })
+    }
+
+    /// Get the linear precursor of a cyclic peptide.
+    ///
+    /// Every cyclization is removed, whether it is formed by the backbone
+    /// or by a side chain lactam, which restores the free termini and side
+    /// chains involved. Cross-links and other modifications are retained.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use proteinogenic::Cyclization;
+    ///
+    /// let mut prot = proteinogenic::Protein::new([Gly, Ala]);
+    /// prot.cyclization(Cyclization::HeadToTail);
+    /// let linear = prot.linearize();
+    /// assert_eq!(linear.smiles(), proteinogenic::smiles([Gly, Ala]));
+    /// ```
+    pub fn linearize(&self) -> Protein<S> {
+        let mut protein = self.clone();
+        protein.cyclizations.clear();
+        protein
+    }
+}
+
+/// Get the number of a ring closure.
+pub(crate) fn rnum_to_u16(rnum: &Rnum) -> u16 {
+    rnum.to_string()
+        .trim_start_matches('%')
+        .parse()
+        .expect("ring closure numbers are written as integers")
+}
+
+/// Enumerate the pairings of `free`, leaving at most `unpaired` left out.
+fn disulfide_pairings(
+    free: &[u16],
+    unpaired: usize,
+    current: &mut Vec<(u16, u16)>,
+    pairings: &mut Vec<Vec<(u16, u16)>>,
+) {
+    match free.split_first() {
+        None => pairings.push(current.clone()),
+        Some((&first, rest)) => {
+            for (k, &other) in rest.iter().enumerate() {
+                let remaining = rest[..k]
+                    .iter()
+                    .chain(&rest[k + 1..])
+                    .cloned()
+                    .collect::<Vec<u16>>();
+                current.push((first, other));
+                disulfide_pairings(&remaining, unpaired, current, pairings);
+                current.pop();
+            }
+            if unpaired > 0 {
+                disulfide_pairings(rest, unpaired - 1, current, pairings);
+            }
+        }
+    }
+}
+
+/// A follower discarding every atom and bond it visits.
+struct Discard;
+
+impl Follower for Discard {
+    fn root(&mut self, _root: AtomKind) {}
+    fn extend(&mut self, _bond_kind: BondKind, _atom_kind: AtomKind) {}
+    fn join(&mut self, _bond_kind: BondKind, _rnum: Rnum) {}
+    fn pop(&mut self, _depth: usize) {}
+}
+
+/// A follower counting the atoms it visits.
+#[derive(Clone, Debug, Default)]
+struct Counter {
+    atoms: usize,
+}
+
+impl Follower for Counter {
+    fn root(&mut self, _root: AtomKind) {
+        self.atoms += 1;
+    }
+
+    fn extend(&mut self, _bond_kind: BondKind, _atom_kind: AtomKind) {
+        self.atoms += 1;
+    }
+
+    fn join(&mut self, _bond_kind: BondKind, _rnum: Rnum) {}
+
+    fn pop(&mut self, _depth: usize) {}
+}
+
+/// A single step of a walk on the atoms and bonds of a molecule.
+#[derive(Debug, PartialEq)]
+enum Event {
+    Root(AtomKind),
+    Extend(BondKind, AtomKind),
+    Join(BondKind, Rnum),
+    Pop(usize),
+}
+
+/// A follower recording every step of the walk it follows.
+#[derive(Debug, Default, PartialEq)]
+struct Recorder {
+    events: Vec<Event>,
+}
+
+impl Recorder {
+    /// Replay the recorded walk with another follower.
+    fn replay<F: Follower>(&self, follower: &mut F) {
+        for event in self.events.iter() {
+            match event {
+                Event::Root(kind) => follower.root(copy::atom_kind(kind)),
+                Event::Extend(bond, kind) => follower.extend(bond.clone(), copy::atom_kind(kind)),
+                Event::Join(bond, rnum) => follower.join(bond.clone(), rnum.clone()),
+                Event::Pop(depth) => follower.pop(*depth),
+            }
+        }
+    }
+
+    /// Replay the recorded walk with explicit hydrogen counts.
+    ///
+    /// Every atom except wildcards is replaced with a bracket atom whose
+    /// hydrogen count is computed on the molecular graph built from the
+    /// recorded walk.
+    fn replay_with_hydrogens<F: Follower>(&self, follower: &mut F) {
+        let mut builder = purr::graph::Builder::new();
+        self.replay(&mut builder);
+        let atoms = builder
+            .build()
+            .expect("protein walk should produce a valid graph");
+
+        let mut index = 0;
+        let mut bracket = |kind: &AtomKind| {
+            let hcount = match mass::hydrogens(&atoms, index) {
+                0 => None,
+                1 => Some(VirtualHydrogen::H1),
+                2 => Some(VirtualHydrogen::H2),
+                3 => Some(VirtualHydrogen::H3),
+                _ => Some(VirtualHydrogen::H4),
+            };
+            index += 1;
+            let symbol = match kind {
+                AtomKind::Star => return AtomKind::Star,
+                AtomKind::Bracket {
+                    symbol,
+                    isotope,
+                    configuration,
+                    charge,
+                    map,
+                    ..
+                } => {
+                    return AtomKind::Bracket {
+                        symbol: copy::bracket_symbol(symbol),
+                        isotope: isotope.as_ref().map(copy::number),
+                        configuration: configuration.clone(),
+                        hcount,
+                        charge: charge.as_ref().map(copy::charge),
+                        map: map.as_ref().map(copy::number),
+                    }
+                }
+                AtomKind::Aromatic(aromatic) => BracketSymbol::Aromatic(match aromatic {
+                    Aromatic::B => BracketAromatic::B,
+                    Aromatic::C => BracketAromatic::C,
+                    Aromatic::N => BracketAromatic::N,
+                    Aromatic::O => BracketAromatic::O,
+                    Aromatic::S => BracketAromatic::S,
+                    Aromatic::P => BracketAromatic::P,
+                }),
+                AtomKind::Aliphatic(_) => BracketSymbol::Element(
+                    mass::element(kind).expect("aliphatic atoms have an element"),
+                ),
+            };
+            AtomKind::Bracket {
+                symbol,
+                isotope: None,
+                configuration: None,
+                hcount,
+                charge: None,
+                map: None,
+            }
+        };
+
+        for event in self.events.iter() {
+            match event {
+                Event::Root(kind) => follower.root(bracket(kind)),
+                Event::Extend(bond, kind) => follower.extend(bond.clone(), bracket(kind)),
+                Event::Join(bond, rnum) => follower.join(bond.clone(), rnum.clone()),
+                Event::Pop(depth) => follower.pop(*depth),
+            }
+        }
+    }
+
+    /// Replay the side chain of a single recorded residue.
+    ///
+    /// The recorded walk must start on the amine nitrogen and end on the
+    /// carbonyl carbon, as done by `Protein::visit_residue`. Both atoms are
+    /// discarded, together with the ring closures they are involved in, and
+    /// the α carbon is replaced with a dummy atom used as the root.
+    fn replay_side_chain<F: Follower>(&self, follower: &mut F) {
+        let mut kinds = Vec::new();
+        let mut neighbors: Vec<Vec<(usize, BondKind)>> = Vec::new();
+        let mut joins: Vec<(usize, BondKind, Rnum)> = Vec::new();
+        let mut stack = Vec::new();
+
+        // rebuild the tree of atoms from the recorded events
+        for event in self.events.iter() {
+            match event {
+                Event::Root(kind) => {
+                    kinds.push(copy::atom_kind(kind));
+                    neighbors.push(Vec::new());
+                    stack.push(kinds.len() - 1);
+                }
+                Event::Extend(bond, kind) => {
+                    let parent = *stack.last().expect("walk must start with a root");
+                    let id = kinds.len();
+                    kinds.push(copy::atom_kind(kind));
+                    neighbors.push(vec![(parent, bond.clone())]);
+                    neighbors[parent].push((id, bond.clone()));
+                    stack.push(id);
+                }
+                Event::Join(bond, rnum) => {
+                    let id = *stack.last().expect("walk must start with a root");
+                    joins.push((id, bond.clone(), rnum.clone()));
+                }
+                Event::Pop(depth) => {
+                    stack.truncate(stack.len() - depth);
+                }
+            }
+        }
+
+        // the nitrogen is the first atom, the carbonyl carbon the last one
+        let carbonyl = kinds.len() - 1;
+        let alpha = neighbors[carbonyl][0].0;
+        let removed = |id: usize| id == 0 || id == carbonyl;
+
+        // only keep ring closures between two atoms of the side chain
+        let mut kept = Vec::new();
+        let mut open: Vec<(usize, &Rnum)> = Vec::new();
+        for (k, (id, _, rnum)) in joins.iter().enumerate() {
+            match open.iter().position(|(_, r)| *r == rnum) {
+                Some(pos) => {
+                    let (start, _) = open.remove(pos);
+                    if !removed(joins[start].0) && !removed(*id) {
+                        kept.push(start);
+                        kept.push(k);
+                    }
+                }
+                None => open.push((k, rnum)),
+            }
+        }
+
+        // build the tree of the side chain rooted on the α carbon, in the
+        // order the atoms were recorded
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); kinds.len()];
+        let mut order = Vec::new();
+        let mut todo = vec![(alpha, alpha)];
+        while let Some((id, from)) = todo.pop() {
+            order.push(id);
+            for (n, _) in neighbors[id].iter().rev() {
+                if *n != from && !removed(*n) {
+                    children[id].insert(0, *n);
+                    todo.push((*n, id));
+                }
+            }
+        }
+
+        // count the atoms written on the main chain of each subtree, which
+        // is the depth to pop when the subtree is written as a branch
+        let mut chain = vec![1; kinds.len()];
+        for &id in order.iter().rev() {
+            if let Some(&last) = children[id].last() {
+                chain[id] += chain[last];
+            }
+        }
+
+        // walk the side chain starting from the α carbon, writing every
+        // child but the last one in a branch
+        enum Step {
+            Visit(usize, usize),
+            Pop(usize),
+        }
+        follower.root(AtomKind::Star);
+        let mut todo = vec![Step::Visit(alpha, alpha)];
+        while let Some(step) = todo.pop() {
+            let (id, from) = match step {
+                Step::Pop(depth) => {
+                    follower.pop(depth);
+                    continue;
+                }
+                Step::Visit(id, from) => (id, from),
+            };
+            if id != alpha {
+                let bond = neighbors[id]
+                    .iter()
+                    .find(|(n, _)| *n == from)
+                    .map(|(_, b)| b.clone())
+                    .unwrap();
+                follower.extend(bond, copy::atom_kind(&kinds[id]));
+            }
+            for k in kept.iter().filter(|k| joins[**k].0 == id) {
+                follower.join(joins[*k].1.clone(), joins[*k].2.clone());
+            }
+            for (i, &child) in children[id].iter().enumerate().rev() {
+                if i + 1 < children[id].len() {
+                    todo.push(Step::Pop(chain[child]));
+                }
+                todo.push(Step::Visit(child, id));
+            }
+        }
+    }
+}
+
+impl Follower for Recorder {
+    fn root(&mut self, root: AtomKind) {
+        self.events.push(Event::Root(root));
+    }
+
+    fn extend(&mut self, bond_kind: BondKind, atom_kind: AtomKind) {
+        self.events.push(Event::Extend(bond_kind, atom_kind));
+    }
+
+    fn join(&mut self, bond_kind: BondKind, rnum: Rnum) {
+        self.events.push(Event::Join(bond_kind, rnum));
+    }
+
+    fn pop(&mut self, depth: usize) {
+        self.events.push(Event::Pop(depth));
+    }
+}
+
+/// Perform a walk on the atoms and bonds of the protein.
+pub fn visit<'aa, S, F>(sequence: S, follower: &mut F) -> Result<(), Error>
+where
+    S: IntoIterator<Item = AminoAcid>,
+    F: Follower,
+{
+    Protein::new(sequence).visit(follower)
+}
+
+/// Create a SMILES string for the given amino-acid sequence.
+///
+/// The sequence is collected first, so that the output string can be
+/// allocated once for the whole protein.
+pub fn smiles<'aa, S>(sequence: S) -> Result<String, Error>
+where
+    S: IntoIterator<Item = AminoAcid>,
+{
+    let sequence = sequence.into_iter().collect::<Vec<_>>();
+    let mut writer = writer::Writer::with_residues(sequence.len());
+    Protein::new(sequence).visit(&mut writer)?;
+    Ok(writer.write())
+}
+
+/// Create SMILES strings for several amino-acid sequences.
+///
+/// Sequences are processed lazily, one at a time, as the returned iterator
+/// is advanced. A single writer is reused for every sequence, and cleared
+/// between them, even when a sequence fails to be written. The writer is
+/// owned by the iterator and never shared: the iterator is `Send` whenever
+/// the input iterator is, and batches can be processed in parallel by
+/// splitting the input, with one iterator per thread.
+///
+/// # Example
+/// ```rust
+/// use proteinogenic::AminoAcid::*;
+///
+/// let seqs = vec![vec![Gly], vec![Gly, Gly]];
+/// let smiles = proteinogenic::smiles_batch(seqs)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(smiles, ["NCC(=O)-O", "NCC(=O)NCC(=O)-O"]);
+/// ```
+pub fn smiles_batch<I>(sequences: I) -> impl Iterator<Item = Result<String, Error>>
+where
+    I: IntoIterator<Item = Vec<AminoAcid>>,
+{
+    let mut writer = writer::Writer::new();
+    sequences.into_iter().map(move |sequence| {
+        let result = Protein::new(sequence).visit(&mut writer);
+        let smiles = writer.flush();
+        result.map(|_| smiles)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let s = smiles([]).unwrap();
+        assert_eq!(s, "");
+    }
+
+    #[test]
+    fn n_terminal_proline() {
+        let s = smiles([AminoAcid::Pro]).unwrap();
+        assert_eq!(s, "N1CCC[C@H]1C(=O)-O");
+
+        let mut builder = purr::graph::Builder::new();
+        visit([AminoAcid::Pro, AminoAcid::Gly], &mut builder).unwrap();
+        let atoms = builder.build().unwrap();
+        assert_eq!(atoms[0].kind, AtomKind::Aliphatic(Aliphatic::N));
+        assert_eq!(atoms[0].bonds.len(), 2);
+    }
+
+    #[test]
+    fn disulfide_isomers() {
+        use AminoAcid::*;
+
+        let prot = Protein::new([Cys, Cys, Cys, Cys]);
+        let isomers = prot.disulfide_isomers().collect::<Vec<_>>();
+        assert_eq!(isomers.len(), 3);
+        for isomer in isomers {
+            assert!(isomer.validate().is_ok());
+        }
+
+        let prot = Protein::new([Cys, Ala, Cys, Cys]);
+        assert_eq!(prot.disulfide_isomers().count(), 3);
+
+        let mut prot = Protein::new([Cys, Cys, Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 4)).unwrap();
+        assert_eq!(prot.disulfide_isomers().count(), 1);
+    }
+
+    #[test]
+    fn protein_from() {
+        let residues = [AminoAcid::Gly, AminoAcid::Cys];
+        let prot = Protein::from(&residues[..]);
+        assert_eq!(prot, Protein::new(residues.to_vec()));
+
+        let prot = Protein::try_from(vec!['G', 'C']);
+        assert_eq!(prot, Ok(Protein::new(residues.to_vec())));
+        let prot = Protein::try_from(vec!['G', 'Z', 'C', 'B']);
+        assert_eq!(
+            prot,
+            Err(UnknownResidue {
+                position: Some(2),
+                symbol: Some('Z')
+            })
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_with_weights() {
+        use rand::distributions::WeightedError;
+        use rand::SeedableRng;
+        use AminoAcid::*;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let prot = Protein::random_with_weights(4, &[(Cys, 0.0), (Ala, 2.0)], &mut rng);
+        assert_eq!(prot.unwrap().sequence, vec![Ala; 4]);
+        assert_eq!(Protein::random(12, &mut rng).sequence.len(), 12);
+
+        // invalid weight tables are reported rather than panicking
+        let invalid = |weights: &[(AminoAcid, f64)]| {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+            Protein::random_with_weights(4, weights, &mut rng).unwrap_err()
+        };
+        assert!(matches!(invalid(&[]), WeightedError::NoItem));
+        assert!(matches!(
+            invalid(&[(Gly, 1.0), (Ala, -1.0)]),
+            WeightedError::InvalidWeight
+        ));
+        assert!(matches!(
+            invalid(&[(Gly, f64::NAN)]),
+            WeightedError::InvalidWeight
+        ));
+        assert!(matches!(
+            invalid(&[(Gly, 0.0), (Ala, 0.0)]),
+            WeightedError::AllWeightsZero
+        ));
+    }
+
+    #[test]
+    fn protein_try_into_atoms() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Gly, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        let atoms = Vec::<purr::graph::Atom>::try_from(prot).unwrap();
+        assert_eq!(atoms.len(), 17);
+
+        let mut prot = Protein::new([Cys, Gly, Cys]);
+        prot.add_modification(2, Modification::Deamidation);
+        assert_eq!(
+            Vec::<purr::graph::Atom>::try_from(prot),
+            Err(Error::InvalidModification(
+                2,
+                Gly,
+                Modification::Deamidation
+            ))
+        );
+
+        // graph errors are reported instead of panicking
+        assert_eq!(
+            Error::from(purr::graph::Error::Rnum(4)),
+            Error::InvalidGraph(4)
+        );
+        assert_eq!(
+            Error::from(purr::graph::Error::Join(2, 2)),
+            Error::InvalidGraph(2)
+        );
+    }
+
+    #[test]
+    fn protein_try_from_bytes() {
+        let residues = vec![AminoAcid::Gly, AminoAcid::Cys, AminoAcid::Sec];
+        let prot = Protein::try_from(&b"GCU"[..]);
+        assert_eq!(prot, Ok(Protein::new(residues)));
+        assert_eq!(Protein::try_from(&b""[..]), Ok(Protein::new(vec![])));
+
+        // the first unknown residue is reported with its position
+        let err = Protein::try_from(&b"GCgZ"[..]).unwrap_err();
+        assert_eq!(
+            err,
+            UnknownResidue {
+                position: Some(3),
+                symbol: Some('g')
+            }
+        );
+        assert_eq!(err.to_string(), "unknown residue 'g' found at position 3");
+    }
+
+    #[test]
+    fn selenolanthionine() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Sec, Gly, Ser]);
+        prot.cross_link(CrossLink::SeLan(1, 3)).unwrap();
+        assert!(prot.validate().is_ok());
+
+        let mut builder = purr::graph::Builder::new();
+        prot.visit(&mut builder).unwrap();
+        let atoms = builder.build().unwrap();
+        let se = atoms
+            .iter()
+            .find(|atom| {
+                matches!(
+                    atom.kind,
+                    AtomKind::Bracket {
+                        symbol: BracketSymbol::Element(Element::Se),
+                        ..
+                    }
+                )
+            })
+            .unwrap();
+        assert_eq!(se.bonds.len(), 2);
+
+        let mut prot = Protein::new([Cys, Gly, Ser]);
+        prot.cross_link(CrossLink::SeLan(1, 3)).unwrap();
+        assert!(matches!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(1, Cys, _))
+        ));
+
+        // each residue is accepted on its own, but not the pair
+        let mut prot = Protein::new([Sec, Gly, Sec]);
+        prot.cross_link(CrossLink::SeLan(1, 3)).unwrap();
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(3, Sec, CrossLink::SeLan(1, 3)))
+        );
+        let mut prot = Protein::new([Cys, Gly, Cys]);
+        prot.cross_link(CrossLink::Lan(1, 3)).unwrap();
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(3, Cys, CrossLink::Lan(1, 3)))
+        );
+        let mut prot = Protein::new([Ser, Gly, Ser]);
+        prot.cross_link(CrossLink::Lan(3, 1)).unwrap();
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(3, Ser, CrossLink::Lan(1, 3)))
+        );
+
+        // the same applies to the cross-links between chains of a complex
+        let chains = vec![Protein::new(vec![Cys]); 2];
+        let mut complex = Complex::new(chains);
+        complex.cross_link(CrossLink::Lan(1, 1), (0, 1)).unwrap();
+        assert_eq!(
+            complex.smiles(),
+            Err(Error::InvalidCrossLink(1, Cys, CrossLink::Lan(1, 1)))
+        );
+    }
+
+    #[test]
+    fn diselenide() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Sec, Gly, Sec]);
+        prot.cross_link(CrossLink::Diselenide(1, 3)).unwrap();
+        assert!(prot.validate().is_ok());
+        assert_eq!(
+            prot.smiles().unwrap(),
+            "N[C@@H](C[Se]3)C(=O)NCC(=O)N[C@@H](C[Se]3)C(=O)-O"
+        );
+
+        let mut prot = Protein::new([Sec, Gly, Cys]);
+        prot.cross_link(CrossLink::Diselenide(1, 3)).unwrap();
+        assert!(matches!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(3, Cys, _))
+        ));
+    }
+
+    #[test]
+    fn selenylsulfide() {
+        use AminoAcid::*;
+
+        for (residues, cross_link) in [
+            ([Sec, Gly, Cys], CrossLink::SelenylSulfide(1, 3)),
+            ([Cys, Gly, Sec], CrossLink::SelenylSulfide(3, 1)),
+        ] {
+            let mut prot = Protein::new(residues);
+            prot.cross_link(cross_link).unwrap();
+            assert!(prot.validate().is_ok());
+            assert_eq!(prot.molecular_formula().unwrap(), "C8H13N3O4SSe");
+        }
+
+        let mut prot = Protein::new([Sec, Gly, Cys]);
+        prot.cross_link(CrossLink::SelenylSulfide(1, 3)).unwrap();
+        prot.modification(3, Modification::Oxidized(1));
+        assert!(prot.validate().is_err());
+
+        // the cross-link needs one residue of each
+        for aa in [Cys, Sec] {
+            let mut prot = Protein::new([aa, Gly, aa]);
+            prot.cross_link(CrossLink::SelenylSulfide(1, 3)).unwrap();
+            assert_eq!(
+                prot.validate(),
+                Err(Error::InvalidCrossLink(
+                    3,
+                    aa,
+                    CrossLink::SelenylSulfide(1, 3)
+                ))
+            );
+        }
+        assert_eq!(
+            "SelenylSulfide(1-3)".parse(),
+            Ok(CrossLink::SelenylSulfide(1, 3))
+        );
+    }
+
+    #[test]
+    fn net_charge() {
+        use AminoAcid::*;
+
+        // selenocysteine is deprotonated at neutral pH, unlike cysteine
+        let sec = Protein::new([Gly, Sec, Gly]);
+        let cys = Protein::new([Gly, Cys, Gly]);
+        assert!(sec.has_selenocysteine());
+        assert!(!cys.has_selenocysteine());
+        assert!(sec.net_charge(7.0) < cys.net_charge(7.0) - 0.9);
+
+        // bridged side chains and blocked termini do not contribute
+        let mut prot = Protein::new([Sec, Gly, Sec]);
+        prot.cross_link(CrossLink::Diselenide(1, 3)).unwrap();
+        prot.n_terminus(NTerm::Acetyl).c_terminus(CTerm::Amide);
+        assert_eq!(prot.net_charge(7.0), 0.0);
+
+        let prot = Protein::new([Lys, Arg, Asp, Glu]);
+        assert!((prot.net_charge(7.0) - 0.0).abs() < 0.05);
+        assert!((prot.net_charge(1.0) - 3.0).abs() < 0.01);
+        assert!((prot.net_charge(14.0) + 3.0).abs() < 0.05);
+
+        let mut prot = Protein::new([Gly, Asn, Lys]);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert!((prot.net_charge(7.0) - 1.0).abs() < 0.01);
+        prot.modification(2, Modification::Deamidation);
+        assert!(prot.net_charge(7.0).abs() < 0.01);
+        prot.modification(3, Modification::Protonated);
+        assert!(prot.net_charge(12.0).abs() < 0.01);
+
+        // the phosphate of a GPI anchor stub is mostly dianionic
+        let mut gpi = Protein::new([Ala, Gly]);
+        gpi.n_terminus(NTerm::Acetyl)
+            .c_terminus(CTerm::GpiEthanolamine);
+        assert!((gpi.net_charge(7.0) + 1.83).abs() < 0.01);
+        assert!((gpi.net_charge(4.0) + 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn net_charge_secondary_amine() {
+        use AminoAcid::*;
+
+        // the N-terminal amine of proline is secondary, and more basic
+        let pro = Protein::new([Pro, Gly]);
+        let ala = Protein::new([Ala, Gly]);
+        let amine = |pka: f64, ph: f64| 1.0 / (1.0 + 10f64.powf(ph - pka));
+        let carboxyl = -1.0 / (1.0 + 10f64.powf(3.6 - 9.0));
+        assert!((pro.net_charge(9.0) - amine(9.4, 9.0) - carboxyl).abs() < 1e-9);
+        assert!((ala.net_charge(9.0) - amine(8.6, 9.0) - carboxyl).abs() < 1e-9);
+
+        // so is the amine of a methylated N-terminus
+        let mut methyl = ala.clone();
+        methyl.n_terminus(NTerm::Methyl);
+        assert_eq!(methyl.net_charge(9.0), pro.net_charge(9.0));
+
+        // a proline elsewhere in the sequence does not change the N-terminus
+        let gly = Protein::new([Gly, Pro]);
+        assert_eq!(gly.net_charge(9.0), ala.net_charge(9.0));
+    }
+
+    #[test]
+    fn side_chain_smiles() {
+        assert_eq!(AminoAcid::Gly.side_chain_smiles(), "*");
+        assert_eq!(AminoAcid::Ala.side_chain_smiles(), "*C");
+        assert_eq!(AminoAcid::Ser.side_chain_smiles(), "*CO");
+        assert_eq!(AminoAcid::Pro.side_chain_smiles(), "*CCC");
+        assert_eq!(AminoAcid::Val.side_chain_smiles(), "*C(C)C");
+        assert_eq!(AminoAcid::Phe.side_chain_smiles(), "*Cc1ccccc1");
+        assert_eq!(AminoAcid::Htp.side_chain_smiles(), "*Cc1cNc2c1cc(O)cc2");
+        assert_eq!(AminoAcid::Nfk.side_chain_smiles(), "*CC(=O)c1c(NC=O)cccc1");
+        assert_eq!(AminoAcid::Oia.side_chain_smiles(), "*CC1C(=O)Nc2c1cccc2");
+        assert_eq!(AminoAcid::S5.side_chain_smiles(), "*(C)CCCC=C");
+        assert_eq!(AminoAcid::R8.side_chain_smiles(), "*(C)CCCCCCC=C");
+        // backbone methylenes of the β- and γ-amino acids
+        assert_eq!(AminoAcid::BAla.side_chain_smiles(), "*C");
+        assert_eq!(AminoAcid::Gaba.side_chain_smiles(), "*CC");
+    }
+
+    #[test]
+    fn thioamide_bond() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.thioamide_bond(1);
+        let s = prot.clone().smiles().unwrap();
+        assert!(s.starts_with("NCC(=S)N"));
+
+        let mut builder = purr::graph::Builder::new();
+        purr::read::read(&s, &mut builder, None).unwrap();
+        assert_eq!(builder.build().unwrap().len(), 10);
+
+        prot.thioamide_bond(2);
+        assert_eq!(prot.clone().smiles(), Err(Error::InvalidThioamide(2)));
+        prot.cyclization(Cyclization::HeadToTail);
+        assert!(prot.clone().smiles().is_ok());
+
+        // residue indices start at 1, and the smallest invalid index is
+        // always reported
+        prot.thioamide_bond(0);
+        assert_eq!(prot.clone().smiles(), Err(Error::InvalidThioamide(0)));
+        let mut prot = Protein::new([Gly, Ala]);
+        for i in (2..40).rev() {
+            prot.thioamide_bond(i);
+        }
+        assert_eq!(prot.smiles(), Err(Error::InvalidThioamide(2)));
+    }
+
+    #[test]
+    fn protonated() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([His]);
+        prot.modification(1, Modification::Protonated);
+        assert_eq!(prot.smiles().unwrap(), "N[C@@H](Cc1c[nH+]cN1)C(=O)-O");
+
+        let mut prot = Protein::new([Lys]);
+        prot.modification(1, Modification::Protonated);
+        assert_eq!(prot.smiles().unwrap(), "N[C@@H](CCCC[NH3+])C(=O)-O");
+
+        let mut prot = Protein::new([Arg]);
+        prot.modification(1, Modification::Protonated);
+        assert_eq!(prot.smiles().unwrap(), "N[C@@H](CCCNC(=[NH2+])N)C(=O)-O");
+
+        let mut prot = Protein::new([Gly, Ser]);
+        prot.modification(2, Modification::Protonated);
+        assert!(matches!(
+            prot.smiles(),
+            Err(Error::InvalidModification(2, Ser, Modification::Protonated))
+        ));
+    }
+
+    #[test]
+    fn full_stereo() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Thr, Ile]);
+        let s = prot.clone().smiles().unwrap();
+        assert_eq!(s.matches("[C@").count(), 4);
+
+        prot.full_stereo(false);
+        let s = prot.smiles().unwrap();
+        assert_eq!(s.matches("[C@").count(), 2);
+        assert!(s.starts_with("N[C@@H](C(C)O)"));
+    }
+
+    #[test]
+    fn monoisotopic_mass() {
+        use AminoAcid::*;
+
+        let prot = Protein::new([Gly, Gly]);
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 132.053492).abs() < 1e-5);
+
+        let prot = Protein::new([Trp]);
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 204.089878).abs() < 1e-5);
+
+        let prot = Protein::new([His]);
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 155.069477).abs() < 1e-5);
+
+        let mut prot = Protein::new([Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 222.013284).abs() < 1e-5);
+
+        let mut prot = Protein::new([Ser, Cys]);
+        prot.cross_link(CrossLink::Lan(2, 1)).unwrap();
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 190.041213).abs() < 1e-5);
+
+        let mut prot = Protein::new([Gly, Gly]);
+        prot.cyclization(Cyclization::HeadToTail);
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 114.042927).abs() < 1e-5);
+    }
+
+    #[test]
+    fn fragment_masses() {
+        use AminoAcid::*;
+
+        let prot = Protein::new([Gly, Ala, Gly]);
+        let (b, y) = prot.fragment_masses().unwrap();
+        assert_eq!(b.len(), 2);
+        assert!((b[0].unwrap() - 58.028740).abs() < 1e-5);
+        assert!((b[1].unwrap() - 129.065854).abs() < 1e-5);
+        assert!((y[0].unwrap() - 147.076419).abs() < 1e-5);
+        assert!((y[1].unwrap() - 76.039305).abs() < 1e-5);
+
+        let mut prot = Protein::new([Cys, Gly, Cys, Gly]);
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        let (b, y) = prot.fragment_masses().unwrap();
+        assert_eq!(b[0], None);
+        assert_eq!(y[1], None);
+        assert!(b[2].is_some());
+        assert!(y[2].is_some());
+    }
+
+    #[test]
+    fn attachment_atom() {
+        assert_eq!(
+            AminoAcid::Lys.attachment_atom(),
+            Some(AttachmentAtom::Nitrogen)
+        );
+        assert_eq!(
+            AminoAcid::Ser.attachment_atom(),
+            Some(AttachmentAtom::Oxygen)
+        );
+        assert_eq!(
+            AminoAcid::Cys.attachment_atom(),
+            Some(AttachmentAtom::Sulfur)
+        );
+        assert_eq!(
+            AminoAcid::Sec.attachment_atom(),
+            Some(AttachmentAtom::Selenium)
+        );
+        assert_eq!(AminoAcid::Gly.attachment_atom(), None);
+        assert_eq!(AminoAcid::Ala.attachment_atom(), None);
+    }
+
+    #[test]
+    fn backbone_spacers() {
+        use AminoAcid::*;
+
+        let s = smiles([Gly, BAla, Gaba, Gly]).unwrap();
+        assert_eq!(s, "NCC(=O)NCCC(=O)NCCCC(=O)NCC(=O)-O");
+        assert_eq!(AminoAcid::from_code("bAla"), Ok(BAla));
+        assert_eq!(AminoAcid::from_code("Gaba"), Ok(Gaba));
+    }
+
+    #[test]
+    fn ring_numbering() {
+        let mut prot = Protein::new([AminoAcid::Cys; 200]);
+        for i in 0..100 {
+            prot.cross_link(CrossLink::Cystine(2 * i + 1, 2 * i + 2))
+                .unwrap();
+        }
+        assert_eq!(prot.clone().smiles(), Err(Error::TooManyCrossLinks));
+
+        prot.ring_numbering(RingNumbering::Reuse);
+        let s = prot.smiles().unwrap();
+        assert!(!s.contains('4'));
+        assert!(!s.contains('%'));
+
+        let mut sequence = vec![AminoAcid::Gly, AminoAcid::Asp, AminoAcid::Lys];
+        sequence.extend([AminoAcid::Cys; 196]);
+        let mut prot = Protein::new(sequence);
+        prot.add_cyclization(Cyclization::Lasso(2)).unwrap();
+        prot.add_cyclization(Cyclization::Branched(3)).unwrap();
+        for i in 0..96 {
+            prot.cross_link(CrossLink::Cystine(2 * i + 4, 2 * i + 5))
+                .unwrap();
+        }
+        assert!(prot.validate().is_ok());
+        prot.cross_link(CrossLink::Cystine(196, 197)).unwrap();
+        let mut builder = purr::graph::Builder::new();
+        assert_eq!(
+            prot.clone().visit(&mut builder),
+            Err(Error::TooManyCrossLinks)
+        );
+        assert_eq!(builder.build(), Ok(Vec::new()));
+        prot.ring_numbering(RingNumbering::Reuse);
+        assert!(prot.validate().is_ok());
+
+        let mut prot = Protein::new([AminoAcid::Cys; 4]);
+        prot.ring_numbering(RingNumbering::Reuse);
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        prot.cross_link(CrossLink::Cystine(2, 4)).unwrap();
+        let s = prot.smiles().unwrap();
+        assert_eq!(s.matches('3').count(), 2);
+        assert_eq!(s.matches('4').count(), 2);
+    }
+
+    #[test]
+    fn writer() {
+        use AminoAcid::*;
+
+        // the writer must agree with the `purr` writer, including on
+        // branches, ring closures and disconnected components
+        let mut prot = Protein::new([Cys, Trp, Lys, Pro, Cys, Asp]);
+        prot.cross_link(CrossLink::Cystine(1, 5)).unwrap();
+        prot.cyclization(Cyclization::Branched(3));
+        let a = Protein::new([Gly, Cys]);
+        let b = Protein::new([Cys, Ala]);
+        let mut complex = Complex::new([a, b]);
+        complex
+            .cross_link(CrossLink::Cystine(2, 1), (0, 1))
+            .unwrap();
+
+        let mut expected = purr::write::Writer::new();
+        let mut actual = writer::Writer::new();
+        prot.clone().visit(&mut expected).unwrap();
+        prot.visit(&mut actual).unwrap();
+        assert_eq!(actual.write(), expected.write());
+
+        let mut expected = purr::write::Writer::new();
+        let mut actual = writer::Writer::with_residues(4);
+        complex.clone().visit(&mut expected).unwrap();
+        complex.visit(&mut actual).unwrap();
+        assert_eq!(actual.write(), expected.write());
+    }
+
+    #[test]
+    fn smiles_batch() {
+        use AminoAcid::*;
+
+        // the writer is cleared between sequences, including empty ones
+        let seqs = vec![vec![Cys, Gly], vec![Trp; 20], vec![], vec![Pro, His]];
+        let mut batch = super::smiles_batch(seqs.clone());
+        for seq in seqs {
+            assert_eq!(batch.next(), Some(smiles(seq)));
+        }
+        assert_eq!(batch.next(), None);
+    }
+
+    #[test]
+    fn canonical_smiles() {
+        use AminoAcid::*;
+
+        // the canonical SMILES describes the same structure, stereocenters
+        // included, and does not depend on where the cycle starts
+        let sequence = [Cys, Trp, His, Pro, Gly, Dhb, Cys, Thr];
+        let mut smiles = HashSet::new();
+        for k in 0..sequence.len() {
+            let mut rotated = sequence;
+            rotated.rotate_left(k);
+            let index = |i: usize| ((i + sequence.len() - k) % sequence.len()) as u16 + 1;
+            let mut prot = Protein::new(rotated);
+            prot.cyclization(Cyclization::HeadToTail);
+            prot.d_amino_acid(index(0)).geometry(index(5), Geometry::E);
+            let canonical = prot.canonical_smiles().unwrap();
+            let plain = prot.clone().smiles().unwrap();
+            assert_eq!(reference::same_structure(&canonical, &plain), Ok(true));
+            smiles.insert(canonical);
+        }
+        assert_eq!(smiles.len(), 1);
+
+        // bracket hydrogens and cross-links are kept
+        let mut prot = Protein::new([Cys, Ala, Cys, Lys]);
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        prot.normalize_hydrogens();
+        let canonical = prot.canonical_smiles().unwrap();
+        let plain = prot.clone().smiles().unwrap();
+        assert_eq!(reference::same_structure(&canonical, &plain), Ok(true));
+
+        // every atom is written once, with the same element
+        let atoms = Vec::<purr::graph::Atom>::try_from(prot.clone()).unwrap();
+        let order = prot.canonical_atom_order().unwrap();
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..atoms.len()).collect::<Vec<_>>());
+        let mut reader = purr::graph::Builder::new();
+        purr::read::read(&canonical, &mut reader, None).unwrap();
+        let written = reader.build().unwrap();
+        for (atom, &index) in written.iter().zip(order.iter()) {
+            assert_eq!(mass::element(&atom.kind), mass::element(&atoms[index].kind));
+        }
+    }
+
+    #[test]
+    fn complex() {
+        use AminoAcid::*;
+
+        // human insulin, with one intrachain and two interchain disulfides
+        let a = Protein::try_from(&b"GIVEQCCTSICSLYQLENYCN"[..]).unwrap();
+        let b = Protein::try_from(&b"FVNQHLCGSHLVEALYLVCGERGFFYTPKT"[..]).unwrap();
+        let mut insulin = Complex::new([a, b]);
+        insulin
+            .cross_link(CrossLink::Cystine(6, 11), (0, 0))
+            .unwrap();
+        insulin
+            .cross_link(CrossLink::Cystine(7, 7), (0, 1))
+            .unwrap();
+        insulin
+            .cross_link(CrossLink::Cystine(20, 19), (0, 1))
+            .unwrap();
+        assert_eq!(
+            insulin.chains()[0].cyclization_kind(),
+            CyclizationKind::Heterodetic
+        );
+
+        assert_eq!(insulin.molecular_formula().unwrap(), "C257H383N65O77S6");
+        let mass = insulin.monoisotopic_mass().unwrap();
+        assert!((mass - 5803.637649).abs() < 1e-5);
+        let s = insulin.smiles().unwrap();
+        assert_eq!(s.matches('.').count(), 1);
+        assert_eq!(s.matches('4').count(), 2);
+        assert_eq!(s.matches('5').count(), 2);
+
+        // endpoints must exist and be free
+        let chains = vec![Protein::new(vec![Cys, Cys]), Protein::new(vec![Ala])];
+        let mut complex = Complex::new(chains);
+        complex
+            .cross_link(CrossLink::Cystine(1, 2), (0, 0))
+            .unwrap();
+        assert_eq!(
+            complex.cross_link(CrossLink::Cystine(2, 1), (0, 1)),
+            Err(Error::DuplicateChainCrossLink(0, 2))
+        );
+        assert_eq!(
+            complex.cross_link(CrossLink::Cystine(3, 1), (0, 1)),
+            Err(Error::InvalidChainResidue(0, 3))
+        );
+        assert_eq!(
+            complex.cross_link(CrossLink::Cystine(1, 1), (0, 2)),
+            Err(Error::InvalidChainResidue(2, 1))
+        );
+
+        // residues are validated when the complex is visited
+        let chains = vec![Protein::new(vec![Cys]), Protein::new(vec![Ala])];
+        let mut complex = Complex::new(chains);
+        complex
+            .cross_link(CrossLink::Cystine(1, 1), (0, 1))
+            .unwrap();
+        assert!(matches!(
+            complex.smiles(),
+            Err(Error::InvalidCrossLink(1, Ala, _))
+        ));
+
+        // the rings of a linker between chains do not reuse its closure
+        let chains = vec![Protein::new(vec![Cys]), Protein::new(vec![Cys])];
+        let mut complex = Complex::new(chains);
+        complex
+            .cross_link(
+                CrossLink::Custom {
+                    i: 1,
+                    j: 1,
+                    linker: "*c1ccc(*)cc1",
+                },
+                (0, 1),
+            )
+            .unwrap();
+        assert_eq!(
+            complex.clone().smiles().unwrap(),
+            "N[C@@H](CSc4ccc3cc4)C(=O)-O.N[C@@H](CS3)C(=O)-O"
+        );
+        assert_eq!(complex.molecular_formula().unwrap(), "C12H16N2O4S2");
+    }
+
+    #[test]
+    fn linearize() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Gly, Asp, Cys, Gly]);
+        prot.cross_link(CrossLink::Cystine(1, 4)).unwrap();
+        prot.cyclization(Cyclization::HeadToTail);
+
+        let linear = prot.linearize();
+        assert_eq!(linear.cyclization_kind(), CyclizationKind::Heterodetic);
+        assert_eq!(linear.condensation_count() + 1, prot.condensation_count());
+        assert_eq!(linear.linearize(), linear);
+
+        let mut cyclic = linear.clone();
+        cyclic.cyclization(Cyclization::HeadToTail);
+        assert_eq!(cyclic.smiles(), prot.clone().smiles());
+
+        // side chain lactams are opened as well
+        prot.cyclization(Cyclization::Lasso(3));
+        let mut lasso = prot.linearize();
+        assert_eq!(lasso.clone().smiles(), linear.clone().smiles());
+        lasso.cyclization(Cyclization::Lasso(3));
+        assert_eq!(lasso.smiles(), prot.smiles());
+    }
+
+    #[test]
+    fn hydrocarbon_staple() {
+        use AminoAcid::*;
+
+        assert_eq!(S5.smiles().unwrap(), "N[C@@](C)(CCCC=C)C(=O)-O");
+        assert_eq!(R8.smiles().unwrap(), "N[C@](C)(CCCCCCC=C)C(=O)-O");
+
+        // i, i+4 staple, with the loss of ethylene
+        let mut prot = Protein::new([S5, Ala, Ala, Ala, S5]);
+        assert_eq!(prot.molecular_formula().unwrap(), "C25H43N5O6");
+        prot.cross_link(CrossLink::HydrocarbonStaple(1, 5)).unwrap();
+        assert!(prot.validate_smiles().is_ok());
+        assert_eq!(prot.molecular_formula().unwrap(), "C23H39N5O6");
+
+        // i, i+7 staple
+        let mut prot = Protein::new([R8, Ala, Ala, Ala, Ala, Ala, Ala, S5]);
+        prot.cross_link(CrossLink::HydrocarbonStaple(1, 8)).unwrap();
+        assert!(prot.smiles().unwrap().starts_with("N[C@](C)(CCCCCCC=3)"));
+
+        // only stapling residues can be bridged
+        let staple = CrossLink::HydrocarbonStaple(1, 2);
+        let mut prot = Protein::new([S5, Ala]);
+        prot.cross_link(staple).unwrap();
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(2, Ala, staple))
+        );
+        assert_eq!(CrossLink::parse("HydrocarbonStaple(1-2)"), Ok(staple));
+    }
+
+    #[test]
+    fn custom_cross_link() {
+        use AminoAcid::*;
+
+        // m-xylene staple between two cysteines
+        let mut prot = Protein::new([Cys, Gly, Cys]);
+        let xylene = CrossLink::Custom {
+            i: 1,
+            j: 3,
+            linker: "*Cc1cccc(C*)c1",
+        };
+        prot.cross_link(xylene).unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CSCc4cccc(C3)c4)C(=O)NCC(=O)N[C@@H](CS3)C(=O)-O"
+        );
+        assert!(prot.validate_smiles().is_ok());
+
+        // the linker is written from the first residue, whatever its position
+        let mut prot = Protein::new([Lys, Gly, Ser]);
+        let ether = CrossLink::Custom {
+            i: 3,
+            j: 1,
+            linker: "*C(=O)CC*",
+        };
+        prot.cross_link(ether).unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CCCCN3)C(=O)NCC(=O)N[C@@H](COC(=O)CC3)C(=O)-O"
+        );
+
+        // pyrrolysine is linked through the imine carbon of its pyrroline
+        let mut prot = Protein::new([Pyl, Cys]);
+        prot.cross_link(CrossLink::Custom {
+            i: 2,
+            j: 1,
+            linker: "*CC*",
+        })
+        .unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CCCCNC(=O)[C@H]1[C@H](C)CC3N1)C(=O)N[C@@H](CSCC3)C(=O)-O"
+        );
+        assert!(prot.validate_smiles().is_ok());
+        assert_eq!(prot.molecular_formula().unwrap(), "C17H30N4O4S");
+        let mut prot = Protein::new([Pyl, Cys]);
+        prot.cross_link(CrossLink::Custom {
+            i: 1,
+            j: 2,
+            linker: "*CC*",
+        })
+        .unwrap();
+        assert!(prot.validate_smiles().is_ok());
+        assert_eq!(prot.molecular_formula().unwrap(), "C17H30N4O4S");
+        let mut prot = Protein::new([Pyl, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        assert!(matches!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(1, Pyl, _))
+        ));
+
+        // linker rings do not clash with the rings still open
+        let mut prot = Protein::new([Cys, Cys, Cys, Cys]);
+        prot.ring_numbering(RingNumbering::Reuse);
+        prot.cross_link(CrossLink::Cystine(1, 4)).unwrap();
+        prot.cross_link(CrossLink::Custom {
+            i: 2,
+            j: 3,
+            linker: "*c1ccc(*)cc1",
+        })
+        .unwrap();
+        assert!(prot.validate_smiles().is_ok());
+        assert!(prot.smiles().unwrap().contains("CSc5ccc4cc5"));
+
+        // linker rings count against the ring closure numbers
+        let mut prot = Protein::new([Cys; 194]);
+        prot.ring_numbering(RingNumbering::Reuse);
+        for k in 1..=96 {
+            prot.cross_link(CrossLink::Cystine(k, 195 - k)).unwrap();
+        }
+        let mut staple = prot.clone();
+        staple
+            .cross_link(CrossLink::Custom {
+                i: 97,
+                j: 98,
+                linker: "*CC*",
+            })
+            .unwrap();
+        assert!(staple.validate().is_ok());
+        prot.cross_link(CrossLink::Custom {
+            i: 97,
+            j: 98,
+            linker: "*c1ccc(*)cc1",
+        })
+        .unwrap();
+        assert_eq!(prot.validate(), Err(Error::TooManyCrossLinks));
+
+        // only some side chains can be linked
+        let mut prot = Protein::new([Cys, Ala]);
+        let link = CrossLink::Custom {
+            i: 1,
+            j: 2,
+            linker: "*CC*",
+        };
+        prot.cross_link(link).unwrap();
+        assert_eq!(prot.validate(), Err(Error::InvalidCrossLink(2, Ala, link)));
+        assert!(link.is_compatible(Cys, Thr));
+        assert!(!link.is_compatible(Cys, Ala));
+
+        // linkers must have two attachment points, starting with the first
+        for linker in ["CC", "*CC", "C*C*", "*C(*)*", "*C*.C", "*1CC1*", "*C1C*"] {
+            let link = CrossLink::Custom { i: 1, j: 2, linker };
+            assert_eq!(
+                Protein::new([Cys, Cys]).cross_link(link),
+                Err(Error::InvalidLinker(linker))
+            );
+        }
+
+        // linkers must only use atoms and bonds supported by the crate
+        let unsupported = [
+            "*[Si](C)(C)*",
+            "*C[Na]C*",
+            "*[P@TB1](F)(F)(F)*",
+            "*[S@OH1](F)(F)(F)(F)*",
+            "*C$C*",
+            "*[13CH2]*",
+        ];
+        for linker in unsupported {
+            let link = CrossLink::Custom { i: 1, j: 2, linker };
+            assert_eq!(
+                Protein::new([Cys, Cys]).cross_link(link),
+                Err(Error::InvalidLinker(linker))
+            );
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(CrossLink::Cystine(3, 15).to_string(), "Cystine(3-15)");
+        assert_eq!(CrossLink::MeLan(1, 4).to_string(), "MeLan(1-4)");
+        assert_eq!(Cyclization::HeadToTail.to_string(), "HeadToTail");
+        assert_eq!(Cyclization::Lasso(8).to_string(), "Lasso(1-8)");
+
+        let err = Error::InvalidCrossLink(1, AminoAcid::Ala, CrossLink::Cystine(1, 2));
+        assert_eq!(
+            err.to_string(),
+            "invalid cross-link Cystine(1-2) for residue 1: expected Cys, found Ala"
+        );
+
+        let err = Error::InvalidCrossLink(2, AminoAcid::Ala, CrossLink::Lan(1, 2));
+        assert!(err.to_string().ends_with("expected Cys or Ser, found Ala"));
+        let err = Error::InvalidCrossLink(2, AminoAcid::Dha, CrossLink::Thioether(1, 2));
+        assert!(err
+            .to_string()
+            .ends_with("expected a residue with an α hydrogen, found Dha"));
+    }
+
+    #[test]
+    fn atom_map() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Pro, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        let map = prot.atom_map().unwrap();
+        assert_eq!(map, vec![0..6, 6..13, 13..20]);
+
+        let prot = Protein::new(Vec::new());
+        assert!(prot.atom_map().unwrap().is_empty());
+    }
+
+    #[test]
+    fn residue_maps() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Ala, Dha]);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "NCC(=O)N[C@@H](C)C(=O)NC(=C)C(=O)-O"
+        );
+        prot.residue_maps(true);
+        assert_eq!(
+            prot.smiles().unwrap(),
+            "N[CH2:1]C(=O)N[C@@H:2](C)C(=O)N[C:3](=C)C(=O)-O"
+        );
+    }
+
+    #[test]
+    fn normalize_hydrogens() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([His, Pro, Sec]);
+        prot.modification(1, Modification::Protonated);
+        let formula = prot.molecular_formula().unwrap();
+
+        prot.normalize_hydrogens();
+        assert_eq!(prot.molecular_formula().unwrap(), formula);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            concat!(
+                "[NH2][C@@H]([CH2][c]1[cH][nH+][cH][NH]1)[C](=[O])[N]1[CH2][CH2][CH2]",
+                "[C@H]1[C](=[O])[NH][C@@H]([CH2][SeH])[C](=[O])-[OH]",
+            )
+        );
+        assert_eq!(prot.validate_smiles(), Ok(()));
+    }
+
+    #[test]
+    fn n_terminus() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.n_terminus(NTerm::Acetyl);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "CC(=O)NCC(=O)N[C@@H](C)C(=O)-O"
+        );
+        assert_eq!(prot.atom_map().unwrap(), vec![0..7, 7..13]);
+
+        prot.n_terminus(NTerm::FattyAcyl {
+            carbons: 6,
+            hydroxy: true,
+        });
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "CCCC(O)CC(=O)NCC(=O)N[C@@H](C)C(=O)-O"
+        );
+
+        prot.n_terminus(NTerm::FattyAcyl {
+            carbons: 2,
+            hydroxy: true,
+        });
+        assert!(matches!(prot.clone().smiles(), Err(Error::InvalidNTerm(_))));
+
+        prot.n_terminus(NTerm::Acetyl);
+        prot.cyclization(Cyclization::Lasso(2));
+        assert!(matches!(
+            prot.smiles(),
+            Err(Error::InvalidNTerm(NTerm::Acetyl))
+        ));
+    }
+
+    #[test]
+    fn emit_n_terminus() {
+        let emit = |n_term: NTerm, cyclization: Option<&Rnum>, zwitterion: bool, proline: bool| {
+            let mut writer = purr::write::Writer::new();
+            Protein::<Vec<AminoAcid>>::emit_n_terminus(
+                &mut writer,
+                n_term,
+                cyclization,
+                zwitterion,
+                proline,
+            );
+            writer.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+            writer.write()
+        };
+
+        assert_eq!(emit(NTerm::Free, None, false, false), "NC");
+        assert_eq!(emit(NTerm::Free, None, true, false), "[NH3+]C");
+        assert_eq!(emit(NTerm::Free, None, true, true), "[NH2+]C");
+        assert_eq!(emit(NTerm::Free, Some(&Rnum::R3), false, false), "N3C");
+        assert_eq!(emit(NTerm::Acetyl, None, false, false), "CC(=O)NC");
+        assert_eq!(emit(NTerm::Formyl, None, false, false), "C(=O)NC");
+        assert_eq!(emit(NTerm::Methyl, None, false, false), "CNC");
+        assert_eq!(emit(NTerm::Methyl, None, true, false), "C[NH2+]C");
+        assert_eq!(emit(NTerm::Methyl, None, true, true), "C[NH+]C");
+        let fatty_acyl = NTerm::FattyAcyl {
+            carbons: 4,
+            hydroxy: true,
+        };
+        assert_eq!(emit(fatty_acyl, None, false, false), "CC(O)CC(=O)NC");
+        assert_eq!(emit(NTerm::Pyroglutamate, None, false, false), "N1C");
+    }
+
+    #[test]
+    fn n_terminus_formyl_methyl() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Met, Gly]);
+        prot.n_terminus(NTerm::Formyl);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "C(=O)N[C@@H](CCSC)C(=O)NCC(=O)-O"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C8H14N2O4S");
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.n_terminus(NTerm::Methyl);
+        assert_eq!(prot.clone().smiles().unwrap(), "CNCC(=O)N[C@@H](C)C(=O)-O");
+        assert_eq!(prot.molecular_formula().unwrap(), "C6H12N2O3");
+        assert!(prot.net_charge(7.0).abs() < 0.05);
+
+        // the methylated amine is protonated in a zwitterion
+        prot.zwitterion(true);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "C[NH2+]CC(=O)N[C@@H](C)C(=O)-[O-]"
+        );
+        assert!(prot.validate_smiles().is_ok());
+        let mut pro = Protein::new([Pro]);
+        pro.n_terminus(NTerm::Methyl).zwitterion(true);
+        assert!(pro.clone().smiles().unwrap().starts_with("C[NH+]1"));
+        assert!(pro.validate_smiles().is_ok());
+
+        // but it cannot form a cyclization
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.smiles(), Err(Error::InvalidNTerm(NTerm::Methyl)));
+    }
+
+    #[test]
+    fn formula_breakdown() {
+        use AminoAcid::*;
+
+        let formula = |counts: &[(&'static str, i32)]| counts.iter().cloned().collect::<Formula>();
+
+        let prot = Protein::new([Gly, Ala]);
+        let breakdown = prot.formula_breakdown().unwrap();
+        assert_eq!(breakdown.len(), 3);
+        assert_eq!(breakdown[0], (0, formula(&[("H", 2), ("O", 1)])));
+        assert_eq!(
+            breakdown[1],
+            (1, formula(&[("C", 2), ("H", 3), ("N", 1), ("O", 1)]))
+        );
+        assert_eq!(
+            breakdown[2],
+            (2, formula(&[("C", 3), ("H", 5), ("N", 1), ("O", 1)]))
+        );
+
+        // the water of the termini is lost in a cyclic peptide
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.formula_breakdown().unwrap()[0], (0, formula(&[])));
+
+        // terminal and side chain modifications are attributed to the synthetic entry
+        let mut prot = Protein::new([Ser, Cys, Lys, Cys]);
+        prot.cross_link(CrossLink::Cystine(2, 4)).unwrap();
+        prot.modification(1, Modification::Phospho);
+        prot.n_terminus(NTerm::Acetyl).c_terminus(CTerm::Amide);
+        let breakdown = prot.formula_breakdown().unwrap();
+        assert_eq!(
+            breakdown[0],
+            (
+                0,
+                formula(&[("C", 2), ("H", 4), ("N", 1), ("O", 4), ("P", 1)])
+            )
+        );
+        let mut total = Formula::new();
+        for (_, formula) in breakdown {
+            for (symbol, count) in formula {
+                *total.entry(symbol).or_default() += count;
+            }
+        }
+        let atoms = prot.atoms().unwrap();
+        let expected = mass::element_counts(&atoms)
+            .into_iter()
+            .map(|(symbol, count)| (symbol, count as i32))
+            .collect::<Formula>();
+        assert_eq!(total, expected);
+    }
+
+    #[test]
+    fn backbone_atom_count() {
+        use AminoAcid::*;
+
+        assert_eq!(Protein::new([]).backbone_atom_count(), 0);
+        assert_eq!(Protein::new([Gly, Ala, Pro]).backbone_atom_count(), 9);
+        assert_eq!(Protein::new([Gaba, BAla]).backbone_atom_count(), 9);
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.thioamide_bond(1);
+        assert_eq!(prot.backbone_atom_count(), 6);
+    }
+
+    #[test]
+    fn molecular_formula() {
+        use AminoAcid::*;
+
+        let prot = Protein::new([Gly, Gly]);
+        assert_eq!(prot.molecular_formula().unwrap(), "C4H8N2O3");
+
+        let prot = Protein::new([Sec]);
+        assert!(prot.clone().smiles().unwrap().contains("[SeH]"));
+        assert_eq!(prot.molecular_formula().unwrap(), "C3H7NO2Se");
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((mass - 168.964198).abs() < 1e-5);
+
+        let mut prot = Protein::new([Sec, Gly, Ser]);
+        prot.cross_link(CrossLink::SeLan(1, 3)).unwrap();
+        assert!(prot.clone().smiles().unwrap().contains("[Se]"));
+        assert_eq!(prot.molecular_formula().unwrap(), "C8H13N3O4Se");
+    }
+
+    #[test]
+    fn lanthionine_donor() {
+        use AminoAcid::*;
+
+        let mut forward = Protein::new([Cys, Ala, Ser]);
+        forward.cross_link(CrossLink::Lan(1, 3)).unwrap();
+        let mut reverse = Protein::new([Ser, Ala, Cys]);
+        reverse.cross_link(CrossLink::Lan(1, 3)).unwrap();
+        assert_eq!(
+            forward.clone().smiles().unwrap(),
+            "N[C@@H](CS3)C(=O)N[C@@H](C)C(=O)N[C@@H](C3)C(=O)-O"
+        );
+        assert_eq!(
+            reverse.clone().smiles().unwrap(),
+            "N[C@@H](C3)C(=O)N[C@@H](C)C(=O)N[C@@H](CS3)C(=O)-O"
+        );
+        assert_eq!(forward.molecular_formula(), reverse.molecular_formula());
+    }
+
+    #[test]
+    fn c_terminus() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.c_terminus(CTerm::Hydrazide);
+        assert_eq!(prot.clone().smiles().unwrap(), "NCC(=O)N[C@@H](C)C(=O)-NN");
+        assert_eq!(prot.molecular_formula().unwrap(), "C5H12N4O2");
+
+        prot.c_terminus(CTerm::MethylEster);
+        assert_eq!(prot.molecular_formula().unwrap(), "C6H12N2O3");
+
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.smiles(), Err(Error::InvalidCTerm(CTerm::MethylEster)));
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.c_terminus(CTerm::MethylAmide);
+        assert_eq!(prot.molecular_formula().unwrap(), "C6H13N3O2");
+
+        let mut prot = Protein::new([Ser]);
+        prot.c_terminus(CTerm::GpiEthanolamine);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CO)C(=O)-NCCOP(=O)(O)O"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C5H13N2O6P");
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidCTerm(CTerm::GpiEthanolamine))
+        );
+    }
+
+    #[test]
+    fn emit_c_terminus() {
+        let emit = |c_term: CTerm, cyclization: Option<&Rnum>, zwitterion: bool| {
+            let mut writer = purr::write::Writer::new();
+            writer.root(AtomKind::Aliphatic(Aliphatic::C));
+            Protein::<Vec<AminoAcid>>::emit_c_terminus(
+                &mut writer,
+                c_term,
+                AtomKind::Aliphatic(Aliphatic::O),
+                cyclization,
+                zwitterion,
+            );
+            writer.write()
+        };
+
+        assert_eq!(emit(CTerm::Free, None, false), "C(=O)-O");
+        assert_eq!(emit(CTerm::Free, None, true), "C(=O)-[O-]");
+        assert_eq!(emit(CTerm::Amide, None, false), "C(=O)-N");
+        assert_eq!(emit(CTerm::Amide, None, true), "C(=O)-N");
+        assert_eq!(emit(CTerm::Hydrazide, None, false), "C(=O)-NN");
+        assert_eq!(emit(CTerm::MethylEster, None, false), "C(=O)-OC");
+        assert_eq!(emit(CTerm::MethylAmide, None, false), "C(=O)-NC");
+        assert_eq!(
+            emit(CTerm::GpiEthanolamine, None, false),
+            "C(=O)-NCCOP(=O)(O)O"
+        );
+        let mut writer = purr::write::Writer::new();
+        writer.root(AtomKind::Aliphatic(Aliphatic::N));
+        writer.join(BondKind::Elided, Rnum::R1);
+        writer.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+        Protein::<Vec<AminoAcid>>::emit_c_terminus(
+            &mut writer,
+            CTerm::Free,
+            AtomKind::Aliphatic(Aliphatic::O),
+            Some(&Rnum::R1),
+            false,
+        );
+        // the ring bond is written before the carbonyl oxygen
+        assert_eq!(writer.write(), "N1C1=O");
+    }
+
+    #[test]
+    fn capped() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Ala]);
+        prot.capped();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "CC(=O)NCC(=O)N[C@@H](C)C(=O)-N"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C7H13N3O3");
+
+        prot.n_terminus(NTerm::Free);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert!(matches!(
+            prot.smiles(),
+            Err(Error::InvalidCTerm(CTerm::Amide))
+        ));
+    }
+
+    #[test]
+    fn bicyclic() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Asp, Lys, Gly]);
+        prot.add_cyclization(Cyclization::Lasso(2)).unwrap();
+        prot.add_cyclization(Cyclization::Branched(3)).unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N0CC(=O)N[C@@H](CC0=O)C(=O)N[C@@H](CCCCN3)C(=O)NCC3=O"
+        );
+        assert_eq!(prot.validate_smiles(), Ok(()));
+        assert_eq!(
+            prot.add_cyclization(Cyclization::HeadToTail).err(),
+            Some(Error::DuplicateCyclization(Cyclization::HeadToTail))
+        );
+
+        prot.cyclization(Cyclization::Branched(2));
+        assert_eq!(
+            prot.clone().smiles(),
+            Err(Error::InvalidCyclization(2, Asp, Cyclization::Branched(2)))
+        );
+
+        prot.cyclization(Cyclization::None);
+        assert!(prot.smiles().unwrap().ends_with("C(=O)-O"));
+    }
+
+    #[test]
+    fn cyclization_too_small() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Ala]);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.validate(), Err(Error::CyclizationTooSmall));
+        assert_eq!(prot.clone().smiles(), Err(Error::CyclizationTooSmall));
+        assert!(prot.molecular_formula().is_err());
+
+        // a diketopiperazine is the smallest head-to-tail cyclic peptide
+        let mut prot = Protein::new([Ala, Gly]);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.clone().smiles().unwrap(), "N0[C@@H](C)C(=O)NCC0=O");
+        assert_eq!(prot.validate_smiles(), Ok(()));
+    }
+
+    #[test]
+    fn kekulize() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([His, Tyr, Trp, Htp, Nfk, Oia]);
+        prot.modification(1, Modification::Protonated);
+        let formula = prot.molecular_formula().unwrap();
+
+        prot.kekulize(true);
+        assert_eq!(prot.molecular_formula().unwrap(), formula);
+        let s = prot.clone().smiles().unwrap();
+        assert!(!s.contains('c'));
+        assert!(s.starts_with("N[C@@H](CC1=C[NH+]=CN1)"));
+    }
+
+    #[test]
+    fn events() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Pro]);
+        prot.residue_maps(true);
+        let events = prot.events().unwrap().collect::<Vec<_>>();
+        assert_eq!(events.len(), 11);
+        assert!(matches!(events[1], AtomEvent::Join(Bond::Implicit, 1)));
+
+        let alpha = match &events[5] {
+            AtomEvent::Extend(_, atom) => atom.clone(),
+            other => panic!("unexpected event: {:?}", other),
+        };
+        assert_eq!(alpha.element, Some("C"));
+        assert_eq!(alpha.hydrogens, Some(1));
+        assert_eq!(alpha.chirality, Some(Chirality::Anticlockwise));
+        assert_eq!(alpha.map, Some(1));
+    }
+
+    #[test]
+    fn gamma_glutamyl() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Glu, Cys, Gly]);
+        let formula = prot.molecular_formula().unwrap();
+        prot.gamma_glutamyl(1);
+        assert_eq!(prot.molecular_formula().unwrap(), formula);
+        assert_eq!(prot.backbone_atom_count(), 11);
+
+        prot.thioamide_bond(1);
+        let s = prot.clone().smiles().unwrap();
+        assert!(s.starts_with("N[C@H](C(=O)O)CCC(=S)N"));
+
+        let mut prot = Protein::new([Gly, Glu, Gly]);
+        prot.cyclization(Cyclization::Lasso(2));
+        prot.gamma_glutamyl(2);
+        assert_eq!(prot.smiles(), Err(Error::InvalidGammaGlutamyl(2, Glu)));
+    }
+
+    #[test]
+    fn retro_inverso() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Thr, Gly, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 4)).unwrap();
+        prot.thioamide_bond(1);
+        prot.d_amino_acid(2);
+
+        let retro = prot.retro_inverso().unwrap();
+        assert_eq!(retro.d_amino_acids(), vec![1, 2, 4]);
+        assert_eq!(retro.thioamides, [3].iter().cloned().collect());
+        assert_eq!(
+            retro.clone().smiles().unwrap(),
+            "N[C@H](CS3)C(=O)NCC(=O)N[C@@H]([C@@H](C)O)C(=S)N[C@H](CS3)C(=O)-O"
+        );
+        let back = retro.retro_inverso().unwrap();
+        assert_eq!(back.smiles().unwrap(), prot.smiles().unwrap());
+
+        let mut prot = Protein::new([Gly, Asp, Gly]);
+        prot.cyclization(Cyclization::Lasso(2));
+        assert!(matches!(
+            prot.retro_inverso(),
+            Err(Error::InvalidCyclization(2, Asp, _))
+        ));
+
+        // the residue found at the offending index is reported, even when
+        // it is not the one the bond expects
+        let mut prot = Protein::new([Gly, Ala, Glu, Asp, Ser]);
+        prot.gamma_glutamyls.insert(2);
+        prot.gamma_glutamyls.insert(3);
+        assert_eq!(
+            prot.retro_inverso(),
+            Err(Error::InvalidGammaGlutamyl(2, Ala))
+        );
+        let mut prot = Protein::new([Gly, Ala, Glu, Asp, Ser]);
+        prot.iso_aspartyls.insert(5);
+        prot.iso_aspartyls.insert(4);
+        assert_eq!(prot.retro_inverso(), Err(Error::InvalidIsoAspartyl(4, Asp)));
+        let mut prot = Protein::new([Gly, Ala, Glu, Asp, Ser]);
+        prot.cis_prolines.insert(3);
+        assert_eq!(prot.retro_inverso(), Err(Error::InvalidCisProline(3, Glu)));
+    }
+
+    #[test]
+    fn subsequence() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Thr, Cys, Gly, Cys, Asp, Gly]);
+        prot.cross_link(CrossLink::Cystine(1, 5)).unwrap();
+        prot.cross_link(CrossLink::Cystine(3, 5)).unwrap_err();
+        prot.cyclization(Cyclization::Lasso(6));
+        prot.c_terminus(CTerm::Amide);
+        prot.thioamide_bond(2).thioamide_bond(3).d_amino_acid(2);
+        prot.add_modification(3, Modification::Oxidized(3));
+
+        let (domain, cut, cut_cyclizations) = prot.subsequence(1..3).unwrap();
+        assert_eq!(cut, vec![]);
+        assert_eq!(cut_cyclizations, vec![]);
+        assert_eq!(domain.sequence, vec![Thr, Cys]);
+        assert_eq!(domain.d_amino_acids(), vec![1]);
+        assert_eq!(domain.thioamides, [1].iter().cloned().collect());
+        assert_eq!(domain.modifications[&2], vec![Modification::Oxidized(3)]);
+        assert_eq!(domain.c_term, CTerm::Free);
+        assert!(domain.cyclizations.is_empty());
+
+        let (domain, cut, cut_cyclizations) = prot.subsequence(0..6).unwrap();
+        assert_eq!(cut, vec![]);
+        assert_eq!(cut_cyclizations, vec![]);
+        assert_eq!(domain.cyclizations, vec![Cyclization::Lasso(6)]);
+        assert_eq!(domain.c_term, CTerm::Free);
+        assert!(domain.validate().is_ok());
+
+        let (domain, cut, cut_cyclizations) = prot.subsequence(2..7).unwrap();
+        assert_eq!(cut, vec![CrossLink::Cystine(1, 5)]);
+        assert_eq!(cut_cyclizations, vec![Cyclization::Lasso(6)]);
+        assert!(domain.cross_links.is_empty());
+        assert_eq!(domain.c_term, CTerm::Amide);
+        assert!(domain.validate().is_ok());
+
+        assert_eq!(prot.subsequence(0..0).unwrap().0.sequence, vec![]);
+        assert_eq!(prot.subsequence(5..8), Err(Error::InvalidResidue(8)));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..1;
+        assert_eq!(prot.subsequence(reversed), Err(Error::InvalidRange(3, 1)));
+        assert_eq!(
+            prot.subsequence(0..70000),
+            Err(Error::InvalidRange(0, 70000))
+        );
+
+        // cyclizations are kept or cut like cross-links
+        let mut prot = Protein::new([Gly, Lys, Gly, Gly]);
+        prot.cyclization(Cyclization::Branched(2));
+        let (domain, _, cut_cyclizations) = prot.subsequence(1..4).unwrap();
+        assert_eq!(domain.cyclizations, vec![Cyclization::Branched(1)]);
+        assert_eq!(cut_cyclizations, vec![]);
+        let (domain, _, cut_cyclizations) = prot.subsequence(1..3).unwrap();
+        assert!(domain.cyclizations.is_empty());
+        assert_eq!(cut_cyclizations, vec![Cyclization::Branched(2)]);
+        let mut prot = Protein::new([Gly, Gly, Gly]);
+        prot.cyclization(Cyclization::HeadToTail);
+        let (_, _, cut_cyclizations) = prot.subsequence(0..2).unwrap();
+        assert_eq!(cut_cyclizations, vec![Cyclization::HeadToTail]);
+    }
+
+    #[test]
+    fn geometry() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Dhb, Ala]);
+        assert!(prot.clone().smiles().unwrap().starts_with("N/C(=C\\C)"));
+        prot.geometry(1, Geometry::Z);
+        assert!(prot.clone().smiles().unwrap().starts_with("N/C(=C\\C)"));
+        prot.geometry(1, Geometry::E);
+        assert!(prot.clone().smiles().unwrap().starts_with("N/C(=C/C)"));
+
+        prot.geometry(2, Geometry::E);
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidGeometry(2, Ala, Geometry::E))
+        );
+    }
+
+    #[test]
+    fn dehydro_bonds() {
+        use AminoAcid::*;
+
+        // every (Z)/(E) double bond is surrounded by directional bonds
+        let prot = Protein::new([Dhb, Dhb]);
+        assert_eq!(prot.smiles().unwrap(), "N/C(=C\\C)C(=O)N/C(=C\\C)C(=O)-O");
+        let mut prot = Protein::new([Dhb, Gly]);
+        prot.zwitterion(true).thioamide_bond(1);
+        assert_eq!(prot.smiles().unwrap(), "[NH3+]/C(=C\\C)C(=S)NCC(=O)-[O-]");
+        let mut prot = Protein::new([Gly, Dhb]);
+        prot.normalize_hydrogens();
+        assert_eq!(
+            prot.smiles().unwrap(),
+            "[NH2][CH2][C](=[O])[NH]/[C](=[CH]\\[CH3])[C](=[O])-[OH]"
+        );
+
+        // the methylene of dehydroalanine is not stereogenic
+        let prot = Protein::new([Dha, Dhb, Dha]);
+        assert_eq!(
+            prot.smiles().unwrap(),
+            "NC(=C)C(=O)N/C(=C\\C)C(=O)NC(=C)C(=O)-O"
+        );
+    }
+
+    #[test]
+    fn beta_amino_acid() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Pro, Phe]);
+        prot.beta_amino_acid(1).beta_amino_acid(2);
+        assert_eq!(prot.backbone_atom_count(), 11);
+        assert!(prot
+            .clone()
+            .smiles()
+            .unwrap()
+            .starts_with("NCCC(=O)N1CCC[C@H]1CC(=O)N"));
+
+        let mut prot = Protein::new([Gly, Phe]);
+        prot.beta_amino_acid(1);
+        let mut bala = Protein::new([BAla, Phe]);
+        let mass = bala.monoisotopic_mass().unwrap();
+        assert!((prot.monoisotopic_mass().unwrap() - mass).abs() < 1e-9);
+        bala.beta_amino_acid(1);
+        assert_eq!(bala.smiles(), Err(Error::InvalidBetaAminoAcid(1, BAla)));
+    }
+
+    #[test]
+    fn ring_count() {
+        use AminoAcid::*;
+
+        assert_eq!(Protein::new([]).ring_count().unwrap(), 0);
+        assert_eq!(Protein::new([Gly, Ala]).ring_count().unwrap(), 0);
+        assert_eq!(Protein::new([Pro]).ring_count().unwrap(), 1);
+        assert_eq!(Protein::new([Trp, His]).ring_count().unwrap(), 3);
+
+        let mut prot = Protein::new([Gly, Cys, Cys, Gly, Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(2, 5)).unwrap();
+        prot.cross_link(CrossLink::Cystine(3, 6)).unwrap();
+        assert_eq!(prot.ring_count().unwrap(), 2);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.ring_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn homoarginine() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Har]);
+        assert_eq!(prot.molecular_formula().unwrap(), "C7H16N4O2");
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CCCCNC(=N)N)C(=O)-O"
+        );
+        prot.modification(1, Modification::Protonated);
+        assert_eq!(prot.smiles().unwrap(), "N[C@@H](CCCCNC(=[NH2+])N)C(=O)-O");
+        assert_eq!(AminoAcid::from_code("Har"), Ok(Har));
+        assert_eq!(Har.side_chain_smiles(), "*CCCCNC(=N)N");
+        assert_eq!(Arg.side_chain_smiles(), "*CCCNC(=N)N");
+    }
+
+    #[test]
+    fn lysine_homologues() {
+        use AminoAcid::*;
+
+        assert_eq!(Orn.side_chain_smiles(), "*CCCN");
+        assert_eq!(Dab.side_chain_smiles(), "*CCN");
+        assert_eq!(Dap.side_chain_smiles(), "*CN");
+        assert_eq!(
+            Protein::new([Orn]).molecular_formula().unwrap(),
+            "C5H12N2O2"
+        );
+        assert_eq!(AminoAcid::from_code("DAB"), Ok(Dab));
+
+        // lariat closed by the C-terminus on the δ-amine of ornithine
+        let mut prot = Protein::new([Gly, Orn, Ala, Gly]);
+        prot.cyclization(Cyclization::Branched(2));
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "NCC(=O)N[C@@H](CCCN0)C(=O)N[C@@H](C)C(=O)NCC0=O"
+        );
+        assert_eq!(prot.validate_smiles(), Ok(()));
+
+        // every side chain amine is a donor, with one hydrogen less
+        // when protonated and cyclized
+        for aa in [Orn, Dab, Dap] {
+            let mut prot = Protein::new([aa, Gly, Gly]);
+            prot.cyclization(Cyclization::Branched(1));
+            prot.modification(1, Modification::Protonated);
+            assert!(prot.clone().smiles().unwrap().contains("[NH2+]0"));
+            assert_eq!(prot.validate_smiles(), Ok(()));
+        }
+
+        let mut prot = Protein::new([Dap, Ala]);
+        prot.cyclization(Cyclization::Branched(2));
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidCyclization(2, Ala, Cyclization::Branched(2)))
+        );
+    }
+
+    #[test]
+    fn halogenated_phenylalanine() {
+        use AminoAcid::*;
+
+        let prot = Protein::new([Phe4F]);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](Cc1ccc(F)cc1)C(=O)-O"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C9H10FNO2");
+        let prot = Protein::new([Phe4Cl, Phe4Br]);
+        assert_eq!(prot.molecular_formula().unwrap(), "C18H18BrClN2O3");
+        assert_eq!(
+            prot.smiles().unwrap(),
+            "N[C@@H](Cc1ccc(Cl)cc1)C(=O)N[C@@H](Cc1ccc(Br)cc1)C(=O)-O"
+        );
+        assert_eq!(AminoAcid::from_code("Phe4Cl"), Ok(Phe4Cl));
+        assert_eq!(Phe4F.side_chain_smiles(), "*Cc1ccc(F)cc1");
+        assert_eq!(Phe4Br.side_chain_smiles(), "*Cc1ccc(Br)cc1");
+    }
+
+    #[test]
+    fn masking() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::try_from(vec!['G', 'X', 'G']).unwrap();
+        assert_eq!(prot.backbone_atom_count(), 9);
+        assert_eq!(prot.atom_map().unwrap()[1], 4..8);
+        prot.residue_maps(true);
+        assert!(prot.clone().smiles().unwrap().contains("N[*:2]C(=O)"));
+
+        prot.masking(Masking::Spacer);
+        let gly = Protein::new([Gly, Gly, Gly]);
+        assert_eq!(prot.molecular_formula(), gly.molecular_formula());
+        assert_eq!(Xaa.side_chain_smiles(), "*");
+    }
+
+    #[test]
+    fn parse_cross_link() {
+        let cross_links = [
+            CrossLink::Cystine(3, 15),
+            CrossLink::Lan(1, 2),
+            CrossLink::MeLan(4, 9),
+            CrossLink::Lal(10, 2),
+            CrossLink::SeLan(5, 6),
+        ];
+        for cross_link in cross_links {
+            assert_eq!(cross_link.to_string().parse(), Ok(cross_link));
+        }
+
+        assert_eq!(CrossLink::parse("Lys10-Ser2"), Ok(CrossLink::Lal(10, 2)));
+        assert_eq!(CrossLink::parse("Sec5-Ser6"), Ok(CrossLink::SeLan(5, 6)));
+        assert_eq!(CrossLink::parse("Cys3-Ala15"), Err(ParseCrossLinkError));
+        assert_eq!(CrossLink::parse("Cys0-Cys15"), Err(ParseCrossLinkError));
+        assert_eq!(CrossLink::parse("Lan(1-2"), Err(ParseCrossLinkError));
+        assert_eq!(CrossLink::parse("Cys3"), Err(ParseCrossLinkError));
+    }
+
+    #[test]
+    fn validate_smiles() {
+        let mut prot = Protein::new([
+            AminoAcid::Cys,
+            AminoAcid::Cys,
+            AminoAcid::Gly,
+            AminoAcid::Cys,
+            AminoAcid::Cys,
+            AminoAcid::Lys,
+            AminoAcid::Ser,
+        ]);
+        prot.cyclization(Cyclization::HeadToTail);
+        prot.cross_link(CrossLink::Cystine(1, 4)).unwrap();
+        prot.cross_link(CrossLink::Cystine(2, 5)).unwrap();
+        prot.cross_link(CrossLink::Lal(6, 7)).unwrap();
+        assert_eq!(prot.validate_smiles(), Ok(()));
+
+        let mut prot = Protein::new([AminoAcid::Cys, AminoAcid::Gly]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        assert!(prot.validate_smiles().is_err());
+    }
+
+    #[test]
+    fn pyroglutamate() {
+        let mut gln = Protein::new([AminoAcid::Gln, AminoAcid::His]);
+        gln.pyroglutamate_n_terminus();
+        let mut glu = Protein::new([AminoAcid::Glu, AminoAcid::His]);
+        glu.pyroglutamate_n_terminus();
+        assert_eq!(gln.clone().smiles(), glu.clone().smiles());
+        assert_eq!(gln.molecular_formula().unwrap(), "C11H14N4O4");
+        assert_eq!(gln.validate_smiles(), Ok(()));
+
+        let mut prot = Protein::new([AminoAcid::Gly, AminoAcid::Gln]);
+        prot.pyroglutamate_n_terminus();
+        assert!(matches!(
+            prot.smiles(),
+            Err(Error::InvalidNTerm(NTerm::Pyroglutamate))
+        ));
+
+        glu.gamma_glutamyl(1);
+        assert!(glu.validate().is_err());
+
+        gln.cyclization(Cyclization::HeadToTail);
+        assert!(gln.validate().is_err());
+    }
+
+    #[test]
+    fn stereocenters() {
+        let mut prot = Protein::new([
+            AminoAcid::Thr,
+            AminoAcid::Dhb,
+            AminoAcid::Glu,
+            AminoAcid::Ile,
+            AminoAcid::BAla,
+        ]);
+        prot.full_stereo(true);
+        prot.gamma_glutamyl(3);
+        prot.d_amino_acid(1).d_amino_acid(4);
+        assert_eq!(
+            prot.stereocenters().unwrap(),
+            vec![
+                (1, StereoLabel::D),
+                (2, StereoLabel::Achiral),
+                (3, StereoLabel::L),
+                (4, StereoLabel::D),
+                (5, StereoLabel::Achiral),
+            ]
+        );
+    }
+
+    #[test]
+    fn thioether() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Cys, Phe, Gly, Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 5)).unwrap();
+        prot.cross_link(CrossLink::Thioether(2, 3)).unwrap();
+        assert_eq!(
+            prot.cross_link(CrossLink::Thioether(6, 5)).unwrap_err(),
+            Error::DuplicateCrossLink(5)
+        );
+        prot.cross_link(CrossLink::Thioether(6, 4)).unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            concat!(
+                "N[C@@H](CS3)C(=O)N[C@@H](CS4)C(=O)NC4(Cc1ccccc1)C(=O)NC5C(=O)",
+                "N[C@@H](CS3)C(=O)N[C@@H](CS5)C(=O)-O",
+            )
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C23H28N6O7S4");
+        assert_eq!(prot.validate_smiles(), Ok(()));
+
+        let mut prot = Protein::new([Cys, Dha]);
+        prot.cross_link(CrossLink::Thioether(1, 2)).unwrap();
+        assert!(matches!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(2, Dha, _))
+        ));
+    }
+
+    #[test]
+    fn amino_acid_smiles() {
+        use AminoAcid::*;
+
+        assert_eq!(Gly.smiles().unwrap(), "NCC(=O)-O");
+        assert_eq!(Pro.smiles().unwrap(), "N1CCC[C@H]1C(=O)-O");
+        assert_eq!(Dha.smiles().unwrap(), "NC(=C)C(=O)-O");
+        assert_eq!(Dhb.smiles().unwrap(), "N/C(=C\\C)C(=O)-O");
+    }
+
+    #[test]
+    fn cross_link_indices() {
+        use AminoAcid::*;
+
+        // residues are indexed from 1: the first residue is residue 1
+        let mut prot = Protein::new([Cys, Cys, Ala]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        assert_eq!(
+            prot.smiles().unwrap(),
+            "N[C@@H](CS3)C(=O)N[C@@H](CS3)C(=O)N[C@@H](C)C(=O)-O"
+        );
+
+        let mut prot = Protein::new([Ala, Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(2, 3)).unwrap();
+        assert!(prot.validate().is_ok());
+        let mut prot = Protein::new([Ala, Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        assert!(matches!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(1, Ala, _))
+        ));
+
+        // residue 0 and residues past the end do not exist
+        let mut prot = Protein::new([Cys, Cys]);
+        assert_eq!(
+            prot.cross_link(CrossLink::Cystine(0, 1)),
+            Err(Error::InvalidResidue(0))
+        );
+        assert_eq!(
+            prot.cross_link(CrossLink::Cystine(2, 0)),
+            Err(Error::InvalidResidue(0))
+        );
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        assert_eq!(prot.validate(), Err(Error::InvalidResidue(3)));
+        assert_eq!(prot.smiles(), Err(Error::InvalidResidue(3)));
+
+        let mut prot = Protein::new([]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        assert_eq!(prot.validate(), Err(Error::InvalidResidue(1)));
+
+        // positions in the sequence are converted to residue indices
+        let sequence = [Gly, Cys, Gly, Cys];
+        let prot = Protein::new(sequence);
+        for (position, aa) in sequence.iter().enumerate() {
+            let index = prot.residue_index(position).unwrap();
+            assert_eq!(usize::from(index), position + 1);
+            assert_eq!(sequence[usize::from(index) - 1], *aa);
+        }
+        assert_eq!(prot.residue_index(sequence.len()), None);
+    }
+
+    #[test]
+    fn capped_smiles() {
+        use AminoAcid::*;
+
+        assert_eq!(Gly.capped_smiles().unwrap(), "CC(=O)NCC(=O)-NC");
+        assert_eq!(Pro.capped_smiles().unwrap(), "CC(=O)N1CCC[C@H]1C(=O)-NC");
+
+        let mut prot = Protein::new([Ser]);
+        prot.n_terminus(NTerm::Acetyl)
+            .c_terminus(CTerm::MethylAmide);
+        assert_eq!(prot.molecular_formula().unwrap(), "C6H12N2O3");
+        assert_eq!(Ser.capped_smiles(), prot.smiles());
+    }
+
+    #[test]
+    fn custom_residue() {
+        use AminoAcid::*;
+
+        // α-aminoisobutyric acid, an achiral residue with two methyl groups
+        #[derive(Clone, Copy)]
+        enum Residues {
+            Standard(AminoAcid),
+            Aib,
+        }
+
+        impl Residue for Residues {
+            fn visit<F: Follower>(
+                &self,
+                follower: &mut F,
+                context: &ResidueContext,
+            ) -> Result<(), Error> {
+                match self {
+                    Residues::Standard(aa) => aa.visit(follower, context),
+                    Residues::Aib => {
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        for _ in 0..2 {
+                            follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                            follower.pop(1);
+                        }
+                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                        Ok(())
                     }
                 }
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(3);
             }
 
-            AminoAcid::Glu => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                match cyclization {
-                    Cyclization::Lasso(n) if n == &index => {
-                        follower.join(BondKind::Elided, Rnum::R0);
-                    }
-                    _ => {
-                        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::O));
-                        follower.pop(1);
-                    }
+            fn amino_acid(&self) -> Option<AminoAcid> {
+                match self {
+                    Residues::Standard(aa) => Some(*aa),
+                    Residues::Aib => None,
                 }
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(4);
             }
+        }
 
-            AminoAcid::Ile => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(1);
+        let sequence = [
+            Residues::Aib,
+            Residues::Standard(Cys),
+            Residues::Standard(Cys),
+        ];
+        let mut prot = Protein::new(sequence);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "NC(C)(C)C(=O)N[C@@H](CS)C(=O)N[C@@H](CS)C(=O)-O"
+        );
+        prot.zwitterion(true)
+            .cross_link(CrossLink::Cystine(2, 3))
+            .unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "[NH3+]C(C)(C)C(=O)N[C@@H](CS3)C(=O)N[C@@H](CS3)C(=O)-[O-]"
+        );
+
+        // custom residues cannot be modified
+        let mut prot = Protein::new(sequence);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        assert_eq!(prot.smiles(), Err(Error::InvalidCustomResidue(1)));
+        let mut prot = Protein::new(sequence);
+        prot.modification(1, Modification::Acetyl);
+        assert_eq!(prot.smiles(), Err(Error::InvalidCustomResidue(1)));
+    }
+
+    #[test]
+    fn custom_residue_kekulize() {
+        // phenylglycine, written with a kekulized ring when requested
+        #[derive(Clone, Copy)]
+        struct Phg;
+
+        impl Residue for Phg {
+            fn visit<F: Follower>(
+                &self,
+                follower: &mut F,
+                context: &ResidueContext,
+            ) -> Result<(), Error> {
                 follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+                for i in 0..6 {
+                    let (bond, atom) = match context.kekulize() {
+                        false => (BondKind::Elided, AtomKind::Aromatic(Aromatic::C)),
+                        true if i % 2 == 1 => (BondKind::Double, AtomKind::Aliphatic(Aliphatic::C)),
+                        true => (BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C)),
+                    };
+                    follower.extend(bond, atom);
+                    if i == 0 || i == 5 {
+                        follower.join(BondKind::Elided, Rnum::R1);
+                    }
+                }
+                follower.pop(6);
                 follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.pop(3);
+                Ok(())
             }
+        }
 
-            AminoAcid::Trp => {
-                // alpha carbon
-                follower.extend(BondKind::Elided, CARBON_TH2);
-                // residue
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R2);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R1);
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.extend(BondKind::Elided, AtomKind::Aromatic(Aromatic::C));
-                follower.join(BondKind::Elided, Rnum::R2);
-                follower.pop(10);
-            }
+        let mut prot = Protein::new([Phg]);
+        assert_eq!(prot.clone().smiles().unwrap(), "NC(c1ccccc1)C(=O)-O");
+        prot.kekulize(true);
+        assert_eq!(prot.smiles().unwrap(), "NC(C1=CC=CC=C1)C(=O)-O");
+    }
+
+    #[test]
+    fn his_tyr() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([His, Gly, Tyr]);
+        prot.cross_link(CrossLink::HisTyr(1, 3)).unwrap();
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](Cc1cn-3cn1)C(=O)NCC(=O)N[C@@H](Cc1cc-3c(O)cc1)C(=O)-O"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C17H19N5O5");
+        assert!(prot.validate_smiles().is_ok());
+        prot.kekulize(true);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CC1=CN3C=N1)C(=O)NCC(=O)N[C@@H](CC1=CC3=C(O)C=C1)C(=O)-O"
+        );
+
+        // the residues can be given in any order
+        let mut prot = Protein::new([Tyr, His]);
+        prot.cross_link(CrossLink::HisTyr(1, 2)).unwrap();
+        assert!(prot.validate().is_ok());
+        assert_eq!(
+            CrossLink::parse("Tyr244-His240"),
+            Ok(CrossLink::HisTyr(244, 240))
+        );
+        assert_eq!(
+            CrossLink::parse("HisTyr(240-244)"),
+            Ok(CrossLink::HisTyr(240, 244))
+        );
+
+        // the τ nitrogen of the histidine cannot be modified further
+        prot.modification(2, Modification::Protonated);
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(2, His, Modification::Protonated))
+        );
+
+        let mut prot = Protein::new([His, Phe]);
+        prot.cross_link(CrossLink::HisTyr(1, 2)).unwrap();
+        assert!(matches!(
+            prot.validate(),
+            Err(Error::InvalidCrossLink(2, Phe, _))
+        ));
+
+        // the cross-link needs one residue of each
+        for aa in [His, Tyr] {
+            let mut prot = Protein::new([aa, Gly, aa]);
+            prot.cross_link(CrossLink::HisTyr(1, 3)).unwrap();
+            assert_eq!(
+                prot.validate(),
+                Err(Error::InvalidCrossLink(3, aa, CrossLink::HisTyr(1, 3)))
+            );
         }
+    }
 
-        // visit the beta carbon and finish
-        follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
-        Ok(())
+    #[test]
+    fn disulfides() {
+        use AminoAcid::*;
+
+        let sequence = [Cys, Cys, Gly, Cys, Cys, Gly, Cys, Cys];
+        let mut expected = Protein::new(sequence);
+        expected
+            .cross_link(CrossLink::Cystine(1, 4))
+            .unwrap()
+            .cross_link(CrossLink::Cystine(2, 7))
+            .unwrap()
+            .cross_link(CrossLink::Cystine(5, 8))
+            .unwrap();
+        let mut prot = Protein::new(sequence);
+        prot.disulfides("1-4,2-7, 5-8").unwrap();
+        assert_eq!(prot, expected);
+
+        // the faulty pair is reported and the protein is left unchanged
+        let mut prot = Protein::new(sequence);
+        prot.disulfides("1-4").unwrap();
+        let before = prot.clone();
+        assert_eq!(
+            prot.disulfides("2-7,5"),
+            Err(DisulfideError::Parse(String::from("5")))
+        );
+        assert_eq!(
+            prot.disulfides("2-7,0-8"),
+            Err(DisulfideError::Parse(String::from("0-8")))
+        );
+        assert_eq!(
+            prot.disulfides("2-7,5-8,8-2"),
+            Err(DisulfideError::Invalid(
+                CrossLink::Cystine(8, 2),
+                Error::DuplicateCrossLink(2)
+            ))
+        );
+        assert_eq!(prot, before);
+        assert_eq!(
+            prot.disulfides(""),
+            Err(DisulfideError::Parse(String::new()))
+        );
     }
-}
 
-impl<S> Protein<S>
-where
-    S: IntoIterator<Item = AminoAcid>,
-{
-    /// Create a new `Protein` with the given sequence stream.
-    pub fn new(sequence: S) -> Self {
-        Self {
-            sequence,
-            cyclization: Cyclization::default(),
-            cross_links: HashMap::new(),
-            cross_link_num: 3, // R0 is used for cyclization, R1 and R2 in residues
+    #[test]
+    fn cross_link_rnum() {
+        use AminoAcid::*;
+
+        // only the cross-links past the last ring closure number are missing
+        let mut prot = Protein::new(vec![Cys; 200]);
+        for i in 0..98 {
+            prot.cross_link(CrossLink::Cystine(2 * i + 1, 2 * i + 2))
+                .unwrap();
         }
+        assert_eq!(prot.cross_link_rnum(1), Rnum::try_from(3).ok());
+        assert_eq!(prot.cross_link_rnum(194), Rnum::try_from(99).ok());
+        assert_eq!(prot.cross_link_rnum(195), None);
+        assert_eq!(prot.clone().smiles(), Err(Error::TooManyCrossLinks));
+
+        // rings closed before the next one opens share the same number
+        prot.ring_numbering(RingNumbering::Reuse);
+        assert_eq!(prot.cross_link_rnum(195), Rnum::try_from(3).ok());
     }
 
-    /// Visit each atom and bond of the sequence using the given follower.
-    pub fn visit<F: Follower>(self, follower: &mut F) -> Result<(), Error> {
-        // visit every amino acid one by one
-        let mut aa_iter = self.sequence.into_iter().enumerate();
-        if let Some((index, aa)) = aa_iter.next() {
-            // N-terminus: create a the N of the primary amine.
-            follower.root(AtomKind::Aliphatic(Aliphatic::N));
-            if matches!(
-                self.cyclization,
-                Cyclization::HeadToTail | Cyclization::Lasso(_)
-            ) {
-                follower.join(BondKind::Elided, Rnum::R0);
-            }
+    #[test]
+    fn duplicate_cross_link() {
+        use AminoAcid::*;
 
-            // visit residue
-            Self::visit_residue(
-                aa,
-                follower,
-                index as u16 + 1, // user-provided indices start at 1
-                &self.cross_links,
-                &self.cyclization,
-            )?;
+        // the same bond can be added again, in any order
+        let mut prot = Protein::new([Cys, Ser, Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(3, 1)).unwrap();
+        let before = prot.clone();
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        prot.cross_link(CrossLink::Cystine(3, 1)).unwrap();
+        assert_eq!(prot, before);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CS3)C(=O)N[C@@H](CO)C(=O)N[C@@H](CS3)C(=O)N[C@@H](CS)C(=O)-O"
+        );
 
-            // add the carboxy group to the β carbon.
-            follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-            follower.pop(1);
-            // keep visiting following amino acids.
-            while let Some((index, aa)) = aa_iter.next() {
-                // next amino acid: create the N atom of the carboxamide and visit residue.
-                follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::N));
-                Self::visit_residue(
-                    aa,
-                    follower,
-                    index as u16 + 1,
-                    &self.cross_links,
-                    &self.cyclization,
-                )?;
-                // add the carboxy group to the β carbon.
-                follower.extend(BondKind::Double, AtomKind::Aliphatic(Aliphatic::O));
-                follower.pop(1);
-            }
+        // reusing a residue in another bond is still an error
+        assert_eq!(
+            prot.cross_link(CrossLink::Cystine(3, 4)),
+            Err(Error::DuplicateCrossLink(3))
+        );
+        assert_eq!(
+            prot.cross_link(CrossLink::Lan(2, 1)),
+            Err(Error::DuplicateCrossLink(1))
+        );
 
-            // C-terminus: create the O atom of the carboxylic acid.
-            if self.cyclization == Cyclization::HeadToTail {
-                follower.join(BondKind::Elided, Rnum::R0);
-            } else {
-                follower.extend(BondKind::Single, AtomKind::Aliphatic(Aliphatic::O));
-            }
+        // the order of the residues of a thioether is meaningful
+        let mut prot = Protein::new([Cys, Ala]);
+        prot.cross_link(CrossLink::Thioether(1, 2)).unwrap();
+        assert_eq!(
+            prot.cross_link(CrossLink::Thioether(2, 1)),
+            Err(Error::DuplicateCrossLink(2))
+        );
+
+        // the same applies to the cross-links between chains of a complex
+        let chains = vec![Protein::new(vec![Cys, Cys, Cys]); 2];
+        let mut complex = Complex::new(chains);
+        complex
+            .cross_link(CrossLink::Cystine(1, 2), (0, 1))
+            .unwrap()
+            .cross_link(CrossLink::Cystine(2, 1), (1, 0))
+            .unwrap()
+            .cross_link(CrossLink::Cystine(3, 2), (0, 0))
+            .unwrap()
+            .cross_link(CrossLink::Cystine(2, 3), (0, 0))
+            .unwrap();
+        let s = complex.clone().smiles().unwrap();
+        assert_eq!(s.matches('3').count(), 2);
+        assert_eq!(s.matches('4').count(), 2);
+        assert_eq!(
+            complex.cross_link(CrossLink::Cystine(1, 1), (1, 0)),
+            Err(Error::DuplicateChainCrossLink(0, 1))
+        );
+    }
+
+    #[test]
+    fn citrullination() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Arg, Gly]);
+        prot.modification(1, Modification::Citrullination);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CCCNC(=O)N)C(=O)NCC(=O)-O"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C8H16N4O4");
+
+        let mut prot = Protein::new([Arg, Har]);
+        prot.modification(2, Modification::Citrullination);
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidModification(
+                2,
+                Har,
+                Modification::Citrullination
+            ))
+        );
+
+        let mut prot = Protein::new([Arg]);
+        prot.modification(1, Modification::Citrullination)
+            .add_modification(1, Modification::Protonated);
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidModification(1, Arg, Modification::Protonated))
+        );
+    }
+
+    #[test]
+    fn deamidation() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Asn, Gln]);
+        let mass = prot.monoisotopic_mass().unwrap();
+        prot.modification(1, Modification::Deamidation)
+            .modification(2, Modification::Deamidation);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            Protein::new([Asp, Glu]).smiles().unwrap()
+        );
+        let shift = prot.monoisotopic_mass().unwrap() - mass;
+        assert!((shift - 2.0 * 0.984016).abs() < 1e-5);
+
+        // deamidated asparagine can be isomerized to isoaspartate
+        let mut prot = Protein::new([Asn, Gly]);
+        prot.iso_aspartate(1);
+        assert_eq!(prot.validate(), Err(Error::InvalidIsoAspartyl(1, Asn)));
+        prot.modification(1, Modification::Deamidation);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@H](C(=O)O)CC(=O)NCC(=O)-O"
+        );
+        assert_eq!(prot.backbone_atom_count(), 7);
+        assert_eq!(
+            prot.backbone_smiles().unwrap(),
+            "N[C@H](C(=O)O)CC(=O)NCC(=O)-O"
+        );
+
+        // only the amides can be deamidated
+        let mut prot = Protein::new([Asp]);
+        prot.modification(1, Modification::Deamidation);
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(
+                1,
+                Asp,
+                Modification::Deamidation
+            ))
+        );
+    }
+
+    #[test]
+    fn atom_table() {
+        use AminoAcid::*;
+
+        // ring closures are listed once, like other bonds
+        let mut prot = Protein::new([Cys, Phe, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        let (elements, charges, bonds) = prot.atom_table().unwrap();
+        assert_eq!(elements.len(), 24);
+        assert_eq!(charges, vec![0; 24]);
+        assert_eq!(bonds.len(), 25);
+        let mut pairs = bonds.iter().map(|&(i, j, _)| (i, j)).collect::<Vec<_>>();
+        pairs.sort_unstable();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 25);
+        assert!(bonds.iter().all(|&(i, j, _)| i < j));
+
+        let count = |kind: BondKind| bonds.iter().filter(|b| b.2 == kind).count();
+        assert_eq!(count(BondKind::Aromatic), 6);
+        assert_eq!(count(BondKind::Double), 3);
+        assert_eq!(count(BondKind::Single), 16);
+        let sulfurs = (0..24)
+            .filter(|&i| elements[i] == Some(Element::S))
+            .collect::<Vec<_>>();
+        assert_eq!(sulfurs.len(), 2);
+        assert!(bonds
+            .iter()
+            .any(|&(i, j, _)| (i, j) == (sulfurs[0], sulfurs[1])));
+
+        // directional bonds are exported as single bonds
+        let (_, _, bonds) = Protein::new([Dhb]).atom_table().unwrap();
+        assert!(bonds
+            .iter()
+            .all(|b| matches!(b.2, BondKind::Single | BondKind::Double)));
+
+        let (elements, _, _) = Protein::new([Xaa]).atom_table().unwrap();
+        assert_eq!(elements[1], None);
+    }
+
+    #[test]
+    fn glycine() {
+        use AminoAcid::*;
+
+        // glycine has no side chain, the carbonyl is bonded to the alpha carbon
+        assert_eq!(smiles([Gly]).unwrap(), "NCC(=O)-O");
+        assert_eq!(smiles([Gly, Gly]).unwrap(), "NCC(=O)NCC(=O)-O");
+        assert_eq!(
+            smiles([Ala, Gly, Ala]).unwrap(),
+            "N[C@@H](C)C(=O)NCC(=O)N[C@@H](C)C(=O)-O"
+        );
+
+        let prot = Protein::new([Gly]);
+        assert_eq!(prot.molecular_formula().unwrap(), "C2H5NO2");
+        let prot = Protein::new([Gly, Gly, Gly]);
+        assert_eq!(prot.molecular_formula().unwrap(), "C6H11N3O4");
+    }
+
+    #[test]
+    fn iso_aspartate() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Asp, Gly]);
+        let formula = prot.molecular_formula().unwrap();
+        prot.iso_aspartate(2);
+        assert_eq!(prot.molecular_formula().unwrap(), formula);
+        assert_eq!(prot.backbone_atom_count(), 10);
+        assert_eq!(prot.stereocenters().unwrap()[1], (2, StereoLabel::L));
+
+        let mut prot = Protein::new([Gly, Asp, Gly]);
+        prot.cyclization(Cyclization::Lasso(2));
+        prot.iso_aspartate(2);
+        assert_eq!(prot.smiles(), Err(Error::InvalidIsoAspartyl(2, Asp)));
+    }
+
+    #[test]
+    fn achiral_residues() {
+        use AminoAcid::*;
+
+        let residues = [Gly, Dha, Dhb, BAla, Gaba, Xaa];
+        for (index, aa) in (1..).zip(residues) {
+            assert!(aa.is_achiral());
+            let mut prot = Protein::new(residues);
+            prot.d_amino_acid(index).residue_maps(true);
+            assert_eq!(
+                prot.stereocenters().unwrap()[index as usize - 1].1,
+                StereoLabel::Achiral
+            );
+            let s = prot.smiles().unwrap();
+            assert!(!s.contains('@'), "{:?} has a stereocenter in {:?}", aa, s);
         }
+    }
 
-        Ok(())
+    #[test]
+    fn zwitterion() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Pro, Gly]);
+        let formula = prot.molecular_formula().unwrap();
+        prot.zwitterion(true);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "[NH2+]1CCC[C@H]1C(=O)NCC(=O)-[O-]"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), formula);
+
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.clone().smiles().unwrap(), "N01CCC[C@H]1C(=O)NCC0=O");
+
+        // the ring closes on the thiocarbonyl carbon, not on the sulfur
+        prot.thioamide_bond(2);
+        assert_eq!(prot.smiles().unwrap(), "N01CCC[C@H]1C(=O)NCC0=S");
+
+        let mut prot = Protein::new([Gly]);
+        prot.zwitterion(true).c_terminus(CTerm::Amide);
+        assert_eq!(prot.smiles().unwrap(), "NCC(=O)-N");
     }
 
-    /// Generate a SMILES string for the protein.
-    pub fn smiles(self) -> Result<String, Error> {
-        let mut writer = purr::write::Writer::new();
-        self.visit(&mut writer)?;
-        Ok(writer.write())
+    #[test]
+    fn melan_configuration() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Thr, Ala, Cys]);
+        prot.cross_link(CrossLink::MeLan(1, 3)).unwrap();
+        let formula = prot.molecular_formula().unwrap();
+        assert!(prot
+            .clone()
+            .smiles()
+            .unwrap()
+            .starts_with("N[C@@H]([C@@H]3C)"));
+
+        prot.melan_configuration(1, MeLanConfiguration::S);
+        assert!(prot
+            .clone()
+            .smiles()
+            .unwrap()
+            .starts_with("N[C@@H]([C@@H]3C)"));
+        prot.melan_configuration(1, MeLanConfiguration::R);
+        assert!(prot
+            .clone()
+            .smiles()
+            .unwrap()
+            .starts_with("N[C@@H]([C@H]3C)"));
+        assert_eq!(prot.molecular_formula().unwrap(), formula);
+
+        prot.melan_configuration(2, MeLanConfiguration::R);
+        assert_eq!(
+            prot.clone().smiles(),
+            Err(Error::InvalidMeLanConfiguration(
+                2,
+                Ala,
+                MeLanConfiguration::R
+            ))
+        );
+
+        let mut prot = Protein::new([Thr]);
+        prot.melan_configuration(1, MeLanConfiguration::R);
+        assert!(prot.validate().is_err());
     }
-}
 
-/// Perform a walk on the atoms and bonds of the protein.
-pub fn visit<'aa, S, F>(sequence: S, follower: &mut F) -> Result<(), Error>
-where
-    S: IntoIterator<Item = AminoAcid>,
-    F: Follower,
-{
-    Protein::new(sequence).visit(follower)
-}
+    #[test]
+    fn condensation_count() {
+        use AminoAcid::*;
 
-/// Create a SMILES string for the given amino-acid sequence.
-pub fn smiles<'aa, S>(sequence: S) -> Result<String, Error>
-where
-    S: IntoIterator<Item = AminoAcid>,
-{
-    Protein::new(sequence).smiles()
-}
+        let residues = [Cys, Ala, Ser, Lys, Cys, Asp, Cys]
+            .iter()
+            .map(|aa| Protein::new([*aa]).monoisotopic_mass().unwrap())
+            .sum::<f64>();
+        let water = 18.010565;
+        let hydrogen = 2.015650;
 
-#[cfg(test)]
-mod tests {
+        let mut prot = Protein::new([Cys, Ala, Ser, Lys, Cys, Asp, Cys]);
+        assert_eq!(prot.condensation_count(), 6);
+        prot.cross_link(CrossLink::Lan(1, 3)).unwrap();
+        prot.cross_link(CrossLink::Cystine(5, 7)).unwrap();
+        prot.cyclization(Cyclization::Lasso(6));
+        assert_eq!(prot.condensation_count(), 9);
+        let mass = prot.monoisotopic_mass().unwrap();
+        assert!((residues - 8.0 * water - hydrogen - mass).abs() < 1e-5);
 
-    use super::*;
+        let mut prot = Protein::new([Gln, Gly]);
+        prot.pyroglutamate_n_terminus();
+        assert_eq!(prot.condensation_count(), 2);
+    }
 
     #[test]
-    fn empty() {
-        let s = smiles([]).unwrap();
-        assert_eq!(s, "");
+    fn backbone_smiles() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Lys, Pro, Cys, Dhb]);
+        prot.cross_link(CrossLink::Cystine(1, 4)).unwrap();
+        prot.modification(2, Modification::Protonated);
+        prot.d_amino_acid(2);
+        let mut backbone = Protein::new([Ala, Ala, Pro, Ala, Dha]);
+        backbone.d_amino_acid(2);
+        assert_eq!(prot.backbone_smiles(), backbone.smiles());
+
+        let mut prot = Protein::new([Gly, Phe, Glu, Gly]);
+        prot.cyclization(Cyclization::HeadToTail);
+        prot.gamma_glutamyl(3);
+        let mut backbone = Protein::new([Gly, Ala, Glu, Gly]);
+        backbone.cyclization(Cyclization::HeadToTail);
+        backbone.gamma_glutamyl(3);
+        assert_eq!(prot.backbone_smiles(), backbone.smiles());
+
+        let mut prot = Protein::new([Gly, Ala, Asp]);
+        prot.cyclization(Cyclization::Lasso(3));
+        assert_eq!(prot.backbone_smiles(), smiles([Gly, Ala, Ala]));
+
+        let mut prot = Protein::new([Ala]);
+        prot.modification(1, Modification::Protonated);
+        assert!(prot.backbone_smiles().is_err());
+
+        // unknown residues are mapped to L-alanine
+        let prot = Protein::new([Gly, Xaa, Gly]);
+        assert_eq!(prot.backbone_smiles(), smiles([Gly, Ala, Gly]));
+    }
+
+    #[test]
+    fn modification_stack() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Met, Lys]);
+        prot.modification(1, Modification::Oxidized(1));
+        prot.modification(1, Modification::Oxidized(2));
+        assert!(prot.validate().is_ok());
+
+        prot.add_modification(1, Modification::Oxidized(1));
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(
+                1,
+                Met,
+                Modification::Oxidized(3)
+            ))
+        );
+
+        // successive oxidations of a sulfur add up
+        let mut prot = Protein::new([Met]);
+        prot.add_modification(1, Modification::Oxidized(1))
+            .add_modification(1, Modification::Oxidized(1));
+        let mut sulfone = Protein::new([Met]);
+        sulfone.modification(1, Modification::Oxidized(2));
+        assert_eq!(prot.smiles().unwrap(), sulfone.smiles().unwrap());
+
+        // a deamidated residue can be modified as the acid it yields
+        let mut prot = Protein::new([Asn, Gly]);
+        prot.add_modification(1, Modification::Deamidation)
+            .add_modification(1, Modification::Phospho);
+        let mut phospho = Protein::new([Asp, Gly]);
+        phospho.modification(1, Modification::Phospho);
+        assert_eq!(prot.smiles().unwrap(), phospho.smiles().unwrap());
+
+        // but only if the deamidation comes first
+        let mut prot = Protein::new([Asn]);
+        prot.add_modification(1, Modification::Phospho)
+            .add_modification(1, Modification::Deamidation);
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(
+                1,
+                Asn,
+                Modification::Deamidation
+            ))
+        );
+
+        // other modifications of the same side chain atom are exclusive
+        let mut prot = Protein::new([Lys]);
+        prot.add_modification(1, Modification::Acetyl)
+            .add_modification(1, Modification::Protonated);
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(1, Lys, Modification::Protonated))
+        );
+
+        let mut prot = Protein::new([Lys, Gly]);
+        prot.add_modification(1, Modification::Protonated);
+        let retro = prot.retro_inverso().unwrap();
+        assert_eq!(retro.modifications[&2], vec![Modification::Protonated]);
+    }
+
+    #[test]
+    fn diketopiperazine() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Gly]);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.clone().smiles().unwrap(), "N0CC(=O)NCC0=O");
+        assert_eq!(prot.backbone_atom_count(), 6);
+
+        let mut prot = Protein::new([Pro, Phe]);
+        prot.cyclization(Cyclization::HeadToTail);
+        assert!(prot.validate_smiles().is_ok());
+        assert_eq!(prot.molecular_formula().unwrap(), "C14H16N2O2");
+        assert_eq!(prot.ring_count().unwrap(), 3);
+        assert_eq!(prot.condensation_count(), 2);
+        assert_eq!(
+            prot.stereocenters().unwrap(),
+            vec![(1, StereoLabel::L), (2, StereoLabel::L)]
+        );
+    }
+
+    #[test]
+    fn read_and_validate_fasta() {
+        let fasta = b"GA\n>first  \nGGC*\n\n>second\nG1\n>empty\n";
+        let records = super::read_and_validate_fasta(&fasta[..]).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].0, "");
+        assert_eq!(records[1].0, "first");
+        assert_eq!(
+            records[1].1.clone().unwrap().smiles(),
+            smiles([AminoAcid::Gly, AminoAcid::Gly, AminoAcid::Cys])
+        );
+        assert_eq!(
+            records[2].1.as_ref().unwrap_err(),
+            &FastaError::UnknownResidue(UnknownResidue {
+                position: Some(2),
+                symbol: Some('1')
+            })
+        );
+        assert_eq!(records[3].0, "empty");
+    }
+
+    #[test]
+    fn proline_cis() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Pro, Gly, Pro]);
+        let s = prot.clone().smiles().unwrap();
+        prot.proline_cis(3);
+        assert!(prot.is_proline_cis(3));
+        assert!(!prot.is_proline_cis(1));
+        assert_eq!(prot.clone().smiles().unwrap(), s);
+
+        prot.proline_cis(1);
+        assert_eq!(prot.validate(), Err(Error::InvalidCisProline(1, Pro)));
+        prot.cyclization(Cyclization::HeadToTail);
+        assert!(prot.validate().is_ok());
+        assert!(matches!(
+            prot.retro_inverso(),
+            Err(Error::InvalidCisProline(_, Pro))
+        ));
+    }
+
+    #[test]
+    fn acetylation() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Ser, Thr, Lys]);
+        prot.modification(1, Modification::Acetyl);
+        prot.modification(2, Modification::Acetyl);
+        prot.modification(3, Modification::Acetyl);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            concat!(
+                "N[C@@H](COC(=O)C)C(=O)N[C@@H]([C@@H](C)OC(=O)C)",
+                "C(=O)N[C@@H](CCCCNC(=O)C)C(=O)-O"
+            )
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C19H32N4O9");
+
+        let mut prot = Protein::new([Cys, Ser]);
+        prot.cross_link(CrossLink::Lan(1, 2)).unwrap();
+        prot.modification(2, Modification::Acetyl);
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidModification(2, Ser, Modification::Acetyl))
+        );
+
+        let mut prot = Protein::new([Gly, Lys]);
+        prot.cyclization(Cyclization::Branched(2));
+        prot.modification(2, Modification::Acetyl);
+        assert!(prot.validate().is_err());
+
+        let mut prot = Protein::new([Tyr]);
+        prot.modification(1, Modification::Acetyl);
+        assert!(prot.validate().is_err());
+    }
+
+    #[test]
+    fn phosphorylation() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Ser, Thr, Tyr]);
+        prot.modification(1, Modification::Phospho);
+        prot.modification(2, Modification::Phospho);
+        prot.modification(3, Modification::Phospho);
+        assert!(prot.validate_smiles().is_ok());
+        assert_eq!(prot.molecular_formula().unwrap(), "C16H26N3O16P3");
+
+        let mut prot = Protein::new([Ser]);
+        prot.modification(1, Modification::Phospho);
+        assert_eq!(prot.smiles().unwrap(), "N[C@@H](COP(=O)(O)O)C(=O)-O");
+
+        let mut prot = Protein::new([His]);
+        prot.modification(1, Modification::Phospho);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](Cc1cn(P(=O)(O)O)cn1)C(=O)-O"
+        );
+        prot.kekulize(true);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CC1=CN(P(=O)(O)O)C=N1)C(=O)-O"
+        );
+        assert_eq!(prot.molecular_formula().unwrap(), "C6H10N3O5P");
+
+        let mut prot = Protein::new([Asp]);
+        prot.modification(1, Modification::Phospho);
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CC(OP(=O)(O)O)=O)C(=O)-O"
+        );
+
+        // other residues, or side chains already involved, are rejected
+        let mut prot = Protein::new([Gly, Asp, Ala]);
+        prot.modification(2, Modification::Phospho);
+        prot.cyclization(Cyclization::Lasso(2));
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(2, Asp, Modification::Phospho))
+        );
+        prot.modification(3, Modification::Phospho);
+        prot.cyclization(Cyclization::None);
+        assert_eq!(
+            prot.validate(),
+            Err(Error::InvalidModification(3, Ala, Modification::Phospho))
+        );
+    }
+
+    #[test]
+    fn oxidation() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys]);
+        let mass = prot.monoisotopic_mass().unwrap();
+        prot.modification(1, Modification::Oxidized(1));
+        assert_eq!(prot.clone().smiles().unwrap(), "N[C@@H](CSO)C(=O)-O");
+        prot.modification(1, Modification::Oxidized(3));
+        assert_eq!(
+            prot.clone().smiles().unwrap(),
+            "N[C@@H](CS(=O)(=O)O)C(=O)-O"
+        );
+        let delta = prot.monoisotopic_mass().unwrap() - mass;
+        assert!((delta - 47.984744).abs() < 1e-5);
+
+        let mut prot = Protein::new([Met]);
+        prot.modification(1, Modification::Oxidized(1));
+        assert_eq!(prot.clone().smiles().unwrap(), "N[C@@H](CCS(=O)C)C(=O)-O");
+        prot.modification(1, Modification::Oxidized(3));
+        assert_eq!(
+            prot.smiles(),
+            Err(Error::InvalidModification(
+                1,
+                Met,
+                Modification::Oxidized(3)
+            ))
+        );
+
+        let mut prot = Protein::new([Cys, Ala, Ser]);
+        prot.cross_link(CrossLink::Lan(1, 3)).unwrap();
+        prot.modification(1, Modification::Oxidized(1));
+        assert_eq!(prot.molecular_formula().unwrap(), "C9H15N3O5S");
+        prot.modification(1, Modification::Oxidized(3));
+        assert!(prot.validate().is_err());
+
+        let mut prot = Protein::new([Cys, Cys]);
+        prot.cross_link(CrossLink::Cystine(1, 2)).unwrap();
+        prot.modification(2, Modification::Oxidized(1));
+        assert!(prot.validate().is_err());
+
+        let mut prot = Protein::new([Ser]);
+        prot.modification(1, Modification::Oxidized(1));
+        assert!(prot.validate().is_err());
+    }
+
+    #[test]
+    fn hydrogen_bonds() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Cys, Lys, Cys]);
+        assert_eq!(prot.hbd_count().unwrap(), 5);
+        assert_eq!(prot.hba_count().unwrap(), 8);
+
+        prot.n_terminus(NTerm::Acetyl);
+        assert_eq!(prot.hbd_count().unwrap(), 5);
+        assert_eq!(prot.hba_count().unwrap(), 9);
+
+        prot.n_terminus(NTerm::Free)
+            .cyclization(Cyclization::HeadToTail);
+        assert_eq!(prot.hbd_count().unwrap(), 4);
+        assert_eq!(prot.hba_count().unwrap(), 7);
+
+        prot.cross_link(CrossLink::Cystine(1, 3)).unwrap();
+        assert_eq!(prot.hbd_count().unwrap(), 4);
+    }
+
+    #[test]
+    fn molfile() {
+        use AminoAcid::*;
+
+        let mut prot = Protein::new([Gly, Phe]);
+        prot.zwitterion(true);
+        let molfile = prot.molfile().unwrap();
+        let lines = molfile.lines().collect::<Vec<_>>();
+        assert_eq!(lines[3], " 16 16  0  0  0  0  0  0  0  0999 V2000");
+        assert_eq!(
+            lines[4],
+            "    0.0000    0.0000    0.0000 N   0  3  0  0  0  0  0  0  0  0  0  0"
+        );
+        assert_eq!(lines[4 + 16], "  1  2  1  0");
+        assert_eq!(lines[4 + 16 + 16], "M  CHG  2   1   1  16  -1");
+        assert_eq!(lines.last(), Some(&"M  END"));
+
+        let bonds = &lines[4 + 16..4 + 16 + 16];
+        assert_eq!(bonds.iter().filter(|l| l.ends_with("2  0")).count(), 5);
+        assert!(bonds.iter().all(|l| !l.ends_with("4  0")));
+
+        let prot = Protein::new(vec![Trp; 100]);
+        assert_eq!(prot.molfile(), Err(Error::TooManyAtoms));
+    }
+
+    #[test]
+    fn verify_residue() {
+        for &aa in AminoAcid::ALL {
+            assert_eq!(super::verify_residue(aa), Ok(()));
+        }
+
+        // atom order and aromaticity do not matter
+        let same = |a: &str, b: &str| reference::same_structure(a, b).unwrap();
+        assert!(same("c1ccccc1O", "OC1=CC=CC=C1"));
+        assert!(same("N[C@@H](C)C(=O)O", "C[C@H](N)C(=O)O"));
+        assert!(same("N1CCC[C@H]1C(=O)O", "OC(=O)[C@@H]1CCCN1"));
+        // enantiomers, diastereomers and tautomers are distinguished
+        assert!(!same("N[C@@H](C)C(=O)O", "N[C@H](C)C(=O)O"));
+        assert!(!same(
+            "C[C@H]([C@@H](C(=O)O)N)O",
+            "C[C@@H]([C@@H](C(=O)O)N)O"
+        ));
+        assert!(!same("NC(=N)NC", "NC(N)=NC"));
+        assert!(!same("CCO", "COC"));
+        // structures with the same atom invariants are distinguished
+        assert!(!same("C1CCC2CCCCC2C1", "C1CCC(C1)C1CCCC1"));
+        assert!(same("C1CCC2CCCCC2C1", "C1CCCC2C1CCCC2"));
+        assert!(reference::same_structure("C1CC", "CC").is_err());
+    }
+
+    #[test]
+    fn all_residues() {
+        // fails to compile when a variant is added to the enum, as a
+        // reminder to add it to `AminoAcid::ALL` as well, with every
+        // variant numbered after its position in `AminoAcid::ALL`
+        let position = |aa: AminoAcid| match aa {
+            AminoAcid::Arg => 0,
+            AminoAcid::His => 1,
+            AminoAcid::Lys => 2,
+            AminoAcid::Asp => 3,
+            AminoAcid::Glu => 4,
+            AminoAcid::Ser => 5,
+            AminoAcid::Thr => 6,
+            AminoAcid::Asn => 7,
+            AminoAcid::Gln => 8,
+            AminoAcid::Gly => 9,
+            AminoAcid::Pro => 10,
+            AminoAcid::Cys => 11,
+            AminoAcid::Sec => 12,
+            AminoAcid::Ala => 13,
+            AminoAcid::Val => 14,
+            AminoAcid::Ile => 15,
+            AminoAcid::Leu => 16,
+            AminoAcid::Met => 17,
+            AminoAcid::Phe => 18,
+            AminoAcid::Tyr => 19,
+            AminoAcid::Trp => 20,
+            AminoAcid::Pyl => 21,
+            AminoAcid::Dha => 22,
+            AminoAcid::Dhb => 23,
+            AminoAcid::Htp => 24,
+            AminoAcid::Nfk => 25,
+            AminoAcid::Oia => 26,
+            AminoAcid::BAla => 27,
+            AminoAcid::Gaba => 28,
+            AminoAcid::Har => 29,
+            AminoAcid::Phe4F => 30,
+            AminoAcid::Phe4Cl => 31,
+            AminoAcid::Phe4Br => 32,
+            AminoAcid::S5 => 33,
+            AminoAcid::R8 => 34,
+            AminoAcid::Orn => 35,
+            AminoAcid::Dab => 36,
+            AminoAcid::Dap => 37,
+            AminoAcid::Xaa => 38,
+        };
+        for (i, &aa) in AminoAcid::ALL.iter().enumerate() {
+            assert_eq!(position(aa), i, "{:?}", aa);
+        }
+        // `Xaa` is numbered last, so this also catches missing variants
+        assert_eq!(AminoAcid::ALL.len(), position(AminoAcid::Xaa) + 1);
+
+        for &aa in AminoAcid::ALL {
+            let prot = Protein::new([aa]);
+            assert!(prot.validate_smiles().is_ok(), "{:?}", aa);
+            assert!(!prot.smiles().unwrap().is_empty());
+            assert_eq!(AminoAcid::from_code(aa.as_code()), Ok(aa));
+            assert!(!aa.side_chain_smiles().is_empty());
+        }
+    }
+
+    #[test]
+    fn property() {
+        assert_eq!(AminoAcid::Glu.property(), ResidueClass::Acidic);
+        assert_eq!(AminoAcid::His.property(), ResidueClass::Basic);
+        assert_eq!(AminoAcid::Har.property(), ResidueClass::Basic);
+        assert_eq!(AminoAcid::Sec.property(), ResidueClass::Polar);
+        assert_eq!(AminoAcid::S5.property(), ResidueClass::Hydrophobic);
+        assert_eq!(AminoAcid::Gaba.property(), ResidueClass::Special);
+        assert_eq!(AminoAcid::Xaa.property(), ResidueClass::Special);
     }
 
     #[test]
     fn from_char() {
         assert_eq!(AminoAcid::from_char('Y'), Ok(AminoAcid::Tyr));
-        assert_eq!(AminoAcid::from_char('α'), Err(UnknownResidue));
+        assert_eq!(
+            AminoAcid::from_char('α'),
+            Err(UnknownResidue {
+                position: None,
+                symbol: Some('α')
+            })
+        );
     }
 
     #[test]
     fn from_code() {
         assert_eq!(AminoAcid::from_code("Thr"), Ok(AminoAcid::Thr));
-        assert_eq!(AminoAcid::from_code("Xyz"), Err(UnknownResidue));
+        assert_eq!(AminoAcid::from_code("Xyz"), Err(UnknownResidue::default()));
+        assert_eq!(AminoAcid::from_code("ALA"), Ok(AminoAcid::Ala));
+        assert_eq!(AminoAcid::from_code("sec"), Ok(AminoAcid::Sec));
+        assert_eq!(AminoAcid::from_code("hIS"), Ok(AminoAcid::His));
+        assert_eq!(AminoAcid::from_code("BALA"), Ok(AminoAcid::BAla));
+        assert_eq!(AminoAcid::from_code("gaba"), Ok(AminoAcid::Gaba));
+        assert_eq!(AminoAcid::from_code("ala").unwrap().as_code(), "Ala");
     }
 }
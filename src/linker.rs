@@ -0,0 +1,197 @@
+//! Insertion of user-provided linkers between cross-linked residues.
+
+use std::collections::HashMap;
+
+use purr::feature::AtomKind;
+use purr::feature::BondKind;
+use purr::feature::BracketSymbol;
+use purr::feature::Configuration;
+use purr::feature::Rnum;
+use purr::walk::Follower;
+
+use super::mass;
+use super::rnum_to_u16;
+
+/// Check whether an atom is an attachment point of the linker.
+fn is_attachment(kind: &AtomKind) -> bool {
+    matches!(
+        kind,
+        AtomKind::Star
+            | AtomKind::Bracket {
+                symbol: BracketSymbol::Star,
+                ..
+            }
+    )
+}
+
+/// Check whether an atom of a linker can be written by the crate.
+///
+/// The element must be supported by the mass functions, and isotopes or
+/// stereocenters other than tetrahedral ones are not supported.
+fn is_supported(kind: &AtomKind) -> bool {
+    match kind {
+        AtomKind::Bracket {
+            isotope,
+            configuration,
+            ..
+        } => {
+            isotope.is_none()
+                && matches!(
+                    configuration,
+                    None | Some(Configuration::TH1) | Some(Configuration::TH2)
+                )
+                && mass::is_supported(kind)
+        }
+        other => mass::is_supported(other),
+    }
+}
+
+/// A follower checking the structure of a linker.
+#[derive(Default)]
+struct Checker {
+    roots: usize,
+    degrees: Vec<usize>,
+    attachments: Vec<usize>,
+    stack: Vec<usize>,
+    open: Vec<u16>,
+    rings: u16,
+    unsupported: bool,
+}
+
+impl Follower for Checker {
+    fn root(&mut self, root: AtomKind) {
+        self.roots += 1;
+        self.extend(BondKind::Elided, root);
+    }
+
+    fn extend(&mut self, bond_kind: BondKind, atom_kind: AtomKind) {
+        self.unsupported |= bond_kind == BondKind::Quadruple || !is_supported(&atom_kind);
+        let index = self.degrees.len();
+        if let Some(&parent) = self.stack.last() {
+            self.degrees[parent] += 1;
+            self.degrees.push(1);
+        } else {
+            self.degrees.push(0);
+        }
+        if is_attachment(&atom_kind) {
+            self.attachments.push(index);
+        }
+        self.stack.push(index);
+    }
+
+    fn join(&mut self, bond_kind: BondKind, rnum: Rnum) {
+        self.unsupported |= bond_kind == BondKind::Quadruple;
+        if let Some(&index) = self.stack.last() {
+            self.degrees[index] += 1;
+        }
+        let num = rnum_to_u16(&rnum);
+        match self.open.iter().position(|&n| n == num) {
+            Some(position) => {
+                self.open.remove(position);
+            }
+            None => {
+                self.open.push(num);
+                self.rings = self.rings.max(self.open.len() as u16);
+            }
+        }
+    }
+
+    fn pop(&mut self, depth: usize) {
+        let len = self.stack.len().saturating_sub(depth);
+        self.stack.truncate(len);
+    }
+}
+
+/// Count the ring closure numbers needed by the rings of a linker.
+///
+/// A linker is a single SMILES fragment with exactly two attachment points,
+/// given as `*` atoms with a single bond each, the first of which must be
+/// the first atom of the fragment. Atoms and bonds that the crate cannot
+/// describe, such as isotopes, elements missing from the mass tables,
+/// non-tetrahedral stereocenters or quadruple bonds, are not supported.
+/// Returns `None` if the linker is invalid.
+pub fn ring_count(linker: &str) -> Option<u16> {
+    let mut checker = Checker::default();
+    purr::read::read(linker, &mut checker, None).ok()?;
+    let valid = !checker.unsupported
+        && checker.roots == 1
+        && checker.open.is_empty()
+        && checker.attachments.len() == 2
+        && checker.attachments[0] == 0
+        && checker.attachments.iter().all(|&i| checker.degrees[i] == 1);
+    if valid {
+        Some(checker.rings)
+    } else {
+        None
+    }
+}
+
+/// A follower replaying a linker onto the follower of a protein.
+struct Emitter<'f, F: Follower> {
+    follower: &'f mut F,
+    closure: Rnum,
+    first: u16,
+    stack: Vec<bool>,
+    rnums: HashMap<u16, u16>,
+}
+
+impl<'f, F: Follower> Follower for Emitter<'f, F> {
+    fn root(&mut self, _root: AtomKind) {
+        // the first attachment point is the atom of the residue
+        self.stack.push(false);
+    }
+
+    fn extend(&mut self, bond_kind: BondKind, atom_kind: AtomKind) {
+        if is_attachment(&atom_kind) {
+            // the second attachment point is bonded to the other residue
+            self.follower.join(bond_kind, self.closure.clone());
+            self.stack.push(false);
+        } else {
+            self.follower.extend(bond_kind, atom_kind);
+            self.stack.push(true);
+        }
+    }
+
+    fn join(&mut self, bond_kind: BondKind, rnum: Rnum) {
+        let num = rnum_to_u16(&rnum);
+        let mapped = match self.rnums.remove(&num) {
+            Some(mapped) => mapped,
+            None => {
+                let mapped = (self.first..)
+                    .find(|n| !self.rnums.values().any(|m| m == n))
+                    .unwrap();
+                self.rnums.insert(num, mapped);
+                mapped
+            }
+        };
+        let rnum = Rnum::try_from(mapped).expect("ring closures checked by caller");
+        self.follower.join(bond_kind, rnum);
+    }
+
+    fn pop(&mut self, depth: usize) {
+        let len = self.stack.len() - depth;
+        let real = self.stack.drain(len..).filter(|&real| real).count();
+        // a branch holding only the second attachment point is a ring closure
+        if real > 0 {
+            self.follower.pop(real);
+        }
+    }
+}
+
+/// Write a linker from the atom currently visited by the follower.
+///
+/// The second attachment point of the linker is written as the `closure`
+/// ring closure, and the rings of the linker are numbered from `first`.
+/// The linker continues the chain of the follower: the number of atoms
+/// left on the chain is returned so that the caller can pop them.
+pub fn visit<F: Follower>(linker: &str, follower: &mut F, closure: Rnum, first: u16) -> usize {
+    let mut emitter = Emitter {
+        follower,
+        closure,
+        first,
+        stack: Vec::new(),
+        rnums: HashMap::new(),
+    };
+    purr::read::read(linker, &mut emitter, None).expect("linker checked by caller");
+    emitter.stack.iter().filter(|&&real| real).count()
+}
@@ -0,0 +1,303 @@
+//! Element counting and mass computation on molecular graphs.
+
+use std::collections::BTreeMap;
+
+use purr::feature::Aliphatic;
+use purr::feature::Aromatic;
+use purr::feature::AtomKind;
+use purr::feature::BondKind;
+use purr::feature::BracketAromatic;
+use purr::feature::BracketSymbol;
+use purr::feature::Element;
+use purr::feature::VirtualHydrogen;
+use purr::graph::Atom;
+
+/// The monoisotopic mass of a proton.
+pub const PROTON: f64 = 1.007276466812;
+
+/// Get the monoisotopic mass of the most abundant isotope of an element.
+pub fn monoisotopic_mass(element: &Element) -> f64 {
+    match element {
+        Element::H => 1.00782503207,
+        Element::B => 11.0093054,
+        Element::C => 12.0,
+        Element::N => 14.0030740048,
+        Element::O => 15.99491461956,
+        Element::F => 18.99840322,
+        Element::P => 30.97376163,
+        Element::S => 31.97207100,
+        Element::Cl => 34.96885268,
+        Element::Se => 79.9165213,
+        Element::Br => 78.9183371,
+        Element::I => 126.904473,
+        other => unreachable!("unsupported element: {:?}", other),
+    }
+}
+
+/// Get the symbol of an element.
+pub fn symbol(element: &Element) -> &'static str {
+    match element {
+        Element::H => "H",
+        Element::B => "B",
+        Element::C => "C",
+        Element::N => "N",
+        Element::O => "O",
+        Element::F => "F",
+        Element::P => "P",
+        Element::S => "S",
+        Element::Cl => "Cl",
+        Element::Se => "Se",
+        Element::Br => "Br",
+        Element::I => "I",
+        other => unreachable!("unsupported element: {:?}", other),
+    }
+}
+
+/// Check whether the element of an atom is supported by this module.
+///
+/// Wildcard atoms are supported, since they are ignored when counting
+/// elements.
+pub fn is_supported(kind: &AtomKind) -> bool {
+    match kind {
+        AtomKind::Star | AtomKind::Aromatic(_) => true,
+        AtomKind::Aliphatic(aliphatic) => matches!(
+            aliphatic,
+            Aliphatic::B
+                | Aliphatic::C
+                | Aliphatic::N
+                | Aliphatic::O
+                | Aliphatic::S
+                | Aliphatic::P
+                | Aliphatic::F
+                | Aliphatic::Cl
+                | Aliphatic::Br
+                | Aliphatic::I
+        ),
+        AtomKind::Bracket { symbol, .. } => match symbol {
+            BracketSymbol::Star => true,
+            BracketSymbol::Element(element) => matches!(
+                element,
+                Element::H
+                    | Element::B
+                    | Element::C
+                    | Element::N
+                    | Element::O
+                    | Element::F
+                    | Element::P
+                    | Element::S
+                    | Element::Cl
+                    | Element::Se
+                    | Element::Br
+                    | Element::I
+            ),
+            BracketSymbol::Aromatic(aromatic) => matches!(
+                aromatic,
+                BracketAromatic::B
+                    | BracketAromatic::C
+                    | BracketAromatic::N
+                    | BracketAromatic::O
+                    | BracketAromatic::S
+                    | BracketAromatic::P
+                    | BracketAromatic::Se
+            ),
+        },
+    }
+}
+
+/// Get the element of an atom, or `None` for a wildcard atom.
+pub fn element(kind: &AtomKind) -> Option<Element> {
+    match kind {
+        AtomKind::Star => None,
+        AtomKind::Aliphatic(aliphatic) => Some(match aliphatic {
+            Aliphatic::B => Element::B,
+            Aliphatic::C => Element::C,
+            Aliphatic::N => Element::N,
+            Aliphatic::O => Element::O,
+            Aliphatic::S => Element::S,
+            Aliphatic::P => Element::P,
+            Aliphatic::F => Element::F,
+            Aliphatic::Cl => Element::Cl,
+            Aliphatic::Br => Element::Br,
+            Aliphatic::I => Element::I,
+            other => unreachable!("unsupported element: {:?}", other),
+        }),
+        AtomKind::Aromatic(aromatic) => Some(match aromatic {
+            Aromatic::B => Element::B,
+            Aromatic::C => Element::C,
+            Aromatic::N => Element::N,
+            Aromatic::O => Element::O,
+            Aromatic::S => Element::S,
+            Aromatic::P => Element::P,
+        }),
+        AtomKind::Bracket { symbol, .. } => match symbol {
+            BracketSymbol::Star => None,
+            // elements are not `Clone`, so rebuild the ones of the mass table
+            BracketSymbol::Element(element) => Some(match element {
+                Element::H => Element::H,
+                Element::B => Element::B,
+                Element::C => Element::C,
+                Element::N => Element::N,
+                Element::O => Element::O,
+                Element::F => Element::F,
+                Element::P => Element::P,
+                Element::S => Element::S,
+                Element::Cl => Element::Cl,
+                Element::Se => Element::Se,
+                Element::Br => Element::Br,
+                Element::I => Element::I,
+                other => unreachable!("unsupported element: {:?}", other),
+            }),
+            BracketSymbol::Aromatic(aromatic) => Some(match aromatic {
+                BracketAromatic::B => Element::B,
+                BracketAromatic::C => Element::C,
+                BracketAromatic::N => Element::N,
+                BracketAromatic::O => Element::O,
+                BracketAromatic::S => Element::S,
+                BracketAromatic::P => Element::P,
+                BracketAromatic::Se => Element::Se,
+                other => unreachable!("unsupported element: {:?}", other),
+            }),
+        },
+    }
+}
+
+/// Get the number of hydrogens attached to the atom at `index`.
+///
+/// Bracket atoms carry an explicit hydrogen count, while the implicit
+/// hydrogen count of atoms of the organic subset is derived from their
+/// default valences, following the SMILES specification.
+pub fn hydrogens(atoms: &[Atom], index: usize) -> u8 {
+    let atom = &atoms[index];
+    let valence = atom
+        .bonds
+        .iter()
+        .map(|bond| match bond.kind {
+            BondKind::Double => 2,
+            BondKind::Triple => 3,
+            BondKind::Quadruple => 4,
+            _ => 1,
+        })
+        .sum::<u8>();
+    match &atom.kind {
+        AtomKind::Star => 0,
+        AtomKind::Bracket { hcount, .. } => match hcount {
+            None | Some(VirtualHydrogen::H0) => 0,
+            Some(VirtualHydrogen::H1) => 1,
+            Some(VirtualHydrogen::H2) => 2,
+            Some(VirtualHydrogen::H3) => 3,
+            Some(VirtualHydrogen::H4) => 4,
+            Some(VirtualHydrogen::H5) => 5,
+            Some(VirtualHydrogen::H6) => 6,
+            Some(VirtualHydrogen::H7) => 7,
+            Some(VirtualHydrogen::H8) => 8,
+            Some(VirtualHydrogen::H9) => 9,
+        },
+        AtomKind::Aromatic(aromatic) => {
+            // one valence is used by the aromatic system
+            let target: u8 = match aromatic {
+                Aromatic::C => 4,
+                Aromatic::B | Aromatic::N | Aromatic::P => 3,
+                Aromatic::O | Aromatic::S => 2,
+            };
+            target.saturating_sub(valence + 1)
+        }
+        AtomKind::Aliphatic(aliphatic) => {
+            let targets: &[u8] = match aliphatic {
+                Aliphatic::B => &[3],
+                Aliphatic::C => &[4],
+                Aliphatic::N => &[3, 5],
+                Aliphatic::O => &[2],
+                Aliphatic::S => &[2, 4, 6],
+                Aliphatic::P => &[3, 5],
+                _ => &[1],
+            };
+            targets
+                .iter()
+                .find(|&&target| target >= valence)
+                .map(|target| target - valence)
+                .unwrap_or(0)
+        }
+    }
+}
+
+/// Compute the monoisotopic mass of a molecular graph.
+pub fn molecule_mass(atoms: &[Atom]) -> f64 {
+    (0..atoms.len())
+        .map(|index| {
+            let heavy = element(&atoms[index].kind)
+                .map(|e| monoisotopic_mass(&e))
+                .unwrap_or(0.0);
+            heavy + hydrogens(atoms, index) as f64 * monoisotopic_mass(&Element::H)
+        })
+        .sum()
+}
+
+/// Count the atoms of each element in a molecular graph.
+///
+/// Implicit hydrogens are counted along with explicit ones, and wildcard
+/// atoms are ignored.
+pub fn element_counts(atoms: &[Atom]) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::<&'static str, usize>::new();
+    for index in 0..atoms.len() {
+        if let Some(element) = element(&atoms[index].kind) {
+            *counts.entry(symbol(&element)).or_default() += 1;
+        }
+        *counts.entry("H").or_default() += hydrogens(atoms, index) as usize;
+    }
+    counts.retain(|_, &mut count| count > 0);
+    counts
+}
+
+/// Compute the molecular formula of a molecular graph, in Hill notation.
+///
+/// Carbon and hydrogen are listed first, followed by the other elements in
+/// alphabetical order. Without carbon, all elements are listed
+/// alphabetically.
+pub fn molecular_formula(atoms: &[Atom]) -> String {
+    let mut counts = element_counts(atoms);
+
+    let mut formula = String::new();
+    let mut write = |symbol: &str, count: usize| {
+        formula.push_str(symbol);
+        if count > 1 {
+            formula.push_str(&count.to_string());
+        }
+    };
+    if let Some(carbons) = counts.remove("C") {
+        write("C", carbons);
+        if let Some(hydrogens) = counts.remove("H") {
+            write("H", hydrogens);
+        }
+    }
+    for (symbol, count) in counts {
+        write(symbol, count);
+    }
+    formula
+}
+
+/// Check whether the atom at `index` is a nitrogen or an oxygen.
+fn is_heteroatom(atoms: &[Atom], index: usize) -> bool {
+    matches!(
+        element(&atoms[index].kind),
+        Some(Element::N) | Some(Element::O)
+    )
+}
+
+/// Count the hydrogen bond donors of a molecular graph.
+///
+/// Following Lipinski, donors are nitrogen and oxygen atoms carrying at
+/// least one hydrogen.
+pub fn hbd_count(atoms: &[Atom]) -> usize {
+    (0..atoms.len())
+        .filter(|&index| is_heteroatom(atoms, index) && hydrogens(atoms, index) > 0)
+        .count()
+}
+
+/// Count the hydrogen bond acceptors of a molecular graph.
+///
+/// Following Lipinski, acceptors are all nitrogen and oxygen atoms.
+pub fn hba_count(atoms: &[Atom]) -> usize {
+    (0..atoms.len())
+        .filter(|&index| is_heteroatom(atoms, index))
+        .count()
+}
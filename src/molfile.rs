@@ -0,0 +1,100 @@
+//! Serialization of molecular graphs to MDL Molfiles.
+
+use purr::feature::BondKind;
+use purr::graph::Atom;
+
+use super::Atom as AtomInfo;
+
+/// The maximum number of atoms or bonds in a V2000 Molfile.
+const MAX_ENTRIES: usize = 999;
+
+/// Get the V2000 atom block charge code of a formal charge.
+fn charge_code(charge: i8) -> u8 {
+    match charge {
+        3 => 1,
+        2 => 2,
+        1 => 3,
+        -1 => 5,
+        -2 => 6,
+        -3 => 7,
+        _ => 0,
+    }
+}
+
+/// Get the V2000 bond type of a bond between two atoms.
+fn bond_type(kind: &BondKind, aromatic: bool) -> u8 {
+    match kind {
+        BondKind::Double => 2,
+        BondKind::Triple => 3,
+        BondKind::Aromatic => 4,
+        BondKind::Elided if aromatic => 4,
+        BondKind::Quadruple => unreachable!("unsupported bond: {:?}", kind),
+        _ => 1,
+    }
+}
+
+/// Write a molecular graph as a V2000 Molfile with zeroed coordinates.
+///
+/// Returns `None` if the graph has too many atoms or bonds to fit in the
+/// counts line of a V2000 Molfile.
+pub fn write(atoms: &[Atom]) -> Option<String> {
+    let infos = atoms
+        .iter()
+        .map(|atom| AtomInfo::from_kind(&atom.kind))
+        .collect::<Vec<_>>();
+    let bonds = atoms
+        .iter()
+        .enumerate()
+        .flat_map(|(i, atom)| {
+            atom.bonds
+                .iter()
+                .filter(move |bond| i < bond.tid)
+                .map(move |bond| (i, bond))
+        })
+        .collect::<Vec<_>>();
+    if infos.len() > MAX_ENTRIES || bonds.len() > MAX_ENTRIES {
+        return None;
+    }
+
+    let mut molfile = String::new();
+    molfile.push_str("\n  proteinogenic\n\n");
+    molfile.push_str(&format!(
+        "{:>3}{:>3}  0  0  0  0  0  0  0  0999 V2000\n",
+        infos.len(),
+        bonds.len()
+    ));
+    for info in infos.iter() {
+        molfile.push_str(&format!(
+            "{:>10.4}{:>10.4}{:>10.4} {:<3} 0{:>3}  0  0  0  0  0  0  0{:>3}  0  0\n",
+            0.0,
+            0.0,
+            0.0,
+            info.element.unwrap_or("*"),
+            charge_code(info.charge),
+            info.map.unwrap_or(0),
+        ));
+    }
+    for (i, bond) in bonds.iter() {
+        let aromatic = infos[*i].aromatic && infos[bond.tid].aromatic;
+        molfile.push_str(&format!(
+            "{:>3}{:>3}{:>3}  0\n",
+            i + 1,
+            bond.tid + 1,
+            bond_type(&bond.kind, aromatic)
+        ));
+    }
+    let charged = infos
+        .iter()
+        .enumerate()
+        .filter(|(_, info)| info.charge != 0)
+        .collect::<Vec<_>>();
+    for chunk in charged.chunks(8) {
+        molfile.push_str(&format!("M  CHG{:>3}", chunk.len()));
+        for (index, info) in chunk {
+            molfile.push_str(&format!(" {:>3} {:>3}", index + 1, info.charge));
+        }
+        molfile.push('\n');
+    }
+    molfile.push_str("M  END\n");
+    Some(molfile)
+}
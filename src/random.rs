@@ -0,0 +1,87 @@
+//! Random generation of proteins with a realistic residue composition.
+
+use rand::distributions::Distribution;
+use rand::distributions::WeightedError;
+use rand::distributions::WeightedIndex;
+use rand::Rng;
+
+use super::AminoAcid;
+use super::Protein;
+
+/// The relative abundance of the standard amino acids in natural proteins.
+///
+/// Values are given in percent, following the amino acid composition of
+/// the UniProtKB/Swiss-Prot database.
+pub const NATURAL_ABUNDANCE: [(AminoAcid, f64); 20] = [
+    (AminoAcid::Ala, 8.25),
+    (AminoAcid::Arg, 5.53),
+    (AminoAcid::Asn, 4.06),
+    (AminoAcid::Asp, 5.45),
+    (AminoAcid::Cys, 1.37),
+    (AminoAcid::Gln, 3.93),
+    (AminoAcid::Glu, 6.75),
+    (AminoAcid::Gly, 7.07),
+    (AminoAcid::His, 2.27),
+    (AminoAcid::Ile, 5.96),
+    (AminoAcid::Leu, 9.66),
+    (AminoAcid::Lys, 5.84),
+    (AminoAcid::Met, 2.42),
+    (AminoAcid::Phe, 3.86),
+    (AminoAcid::Pro, 4.70),
+    (AminoAcid::Ser, 6.56),
+    (AminoAcid::Thr, 5.34),
+    (AminoAcid::Trp, 1.08),
+    (AminoAcid::Tyr, 2.92),
+    (AminoAcid::Val, 6.87),
+];
+
+impl Protein<Vec<AminoAcid>> {
+    /// Generate a random linear protein of the given length.
+    ///
+    /// Residues are drawn independently from the natural abundance of the
+    /// standard amino acids, given in `NATURAL_ABUNDANCE`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    /// let prot = proteinogenic::Protein::random(10, &mut rng);
+    /// assert!(prot.smiles().is_ok());
+    /// ```
+    pub fn random(len: usize, rng: &mut impl Rng) -> Self {
+        Self::random_with_weights(len, &NATURAL_ABUNDANCE, rng)
+            .expect("natural abundances are valid weights")
+    }
+
+    /// Generate a random linear protein using the given residue weights.
+    ///
+    /// Weights are relative, and do not need to sum to any particular
+    /// value. Residues missing from the table are never drawn.
+    ///
+    /// # Errors
+    /// A `WeightedError` is returned if the weight table is empty, if a
+    /// weight is negative or not a number, or if all weights are zero.
+    ///
+    /// # Example
+    /// ```rust
+    /// use proteinogenic::AminoAcid::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    /// let weights = [(Gly, 1.0), (Ala, 0.0)];
+    /// let prot = proteinogenic::Protein::random_with_weights(3, &weights, &mut rng).unwrap();
+    /// assert_eq!(prot.smiles(), proteinogenic::smiles([Gly, Gly, Gly]));
+    /// ```
+    pub fn random_with_weights(
+        len: usize,
+        weights: &[(AminoAcid, f64)],
+        rng: &mut impl Rng,
+    ) -> Result<Self, WeightedError> {
+        let index = WeightedIndex::new(weights.iter().map(|(_, weight)| *weight))?;
+        let sequence = (0..len)
+            .map(|_| weights[index.sample(rng)].0)
+            .collect::<Vec<AminoAcid>>();
+        Ok(Protein::new(sequence))
+    }
+}
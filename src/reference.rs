@@ -0,0 +1,410 @@
+//! Reference structures to verify the SMILES generated for each residue.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use purr::feature::AtomKind;
+use purr::feature::BondKind;
+use purr::feature::Rnum;
+use purr::graph::Bond;
+use purr::walk::Follower;
+
+use super::mass;
+use super::rnum_to_u16;
+use super::AminoAcid;
+use super::Atom;
+use super::Chirality;
+
+/// The reference structures of the free amino acids, in SMILES format.
+///
+/// Each line gives the SMILES of a free amino acid and its code, separated
+/// by a tab. The structures are written in a different form than the one
+/// generated by the crate, usually following the isomeric SMILES of PubChem,
+/// and use the same tautomers as the generated structures.
+const REFERENCES: &str = include_str!("residues.smi");
+
+/// A molecular graph keeping the neighbors of each atom in SMILES order.
+#[derive(Default)]
+pub struct Graph {
+    pub atoms: Vec<purr::graph::Atom>,
+    pub neighbors: Vec<Vec<Option<usize>>>,
+    stack: Vec<usize>,
+    open: HashMap<u16, (usize, usize, BondKind)>,
+}
+
+impl Graph {
+    /// Read a molecular graph from a SMILES string.
+    fn read(smiles: &str) -> Result<Self, String> {
+        let mut graph = Graph::default();
+        purr::read::read(smiles, &mut graph, None)
+            .map_err(|e| format!("failed to parse {:?}: {:?}", smiles, e))?;
+        if !graph.open.is_empty() {
+            return Err(format!("unbalanced ring closure in {:?}", smiles));
+        }
+        Ok(graph)
+    }
+
+    /// Add an atom to the graph, bonded to the atom on top of the stack.
+    ///
+    /// The implicit hydrogen of a bracket atom is listed right after the
+    /// atom it is bonded from, as it is for the stereochemistry of SMILES.
+    fn add(&mut self, parent: Option<(usize, BondKind)>, kind: AtomKind) {
+        let index = self.atoms.len();
+        let mut neighbors = Vec::new();
+        let mut bonds = Vec::new();
+        if let Some((parent, bond_kind)) = parent {
+            neighbors.push(Some(parent));
+            self.neighbors[parent].push(Some(index));
+            bonds.push(Bond {
+                kind: bond_kind.clone(),
+                tid: parent,
+            });
+            self.atoms[parent].bonds.push(Bond {
+                kind: bond_kind,
+                tid: index,
+            });
+        }
+        if Atom::from_kind(&kind).hydrogens.unwrap_or(0) > 0 {
+            neighbors.push(None);
+        }
+        self.atoms.push(purr::graph::Atom { kind, bonds });
+        self.neighbors.push(neighbors);
+        self.stack.push(index);
+    }
+}
+
+impl Follower for Graph {
+    fn root(&mut self, root: AtomKind) {
+        self.stack.clear();
+        self.add(None, root);
+    }
+
+    fn extend(&mut self, bond_kind: BondKind, atom_kind: AtomKind) {
+        let parent = *self.stack.last().expect("extend called before root");
+        self.add(Some((parent, bond_kind)), atom_kind);
+    }
+
+    fn join(&mut self, bond_kind: BondKind, rnum: Rnum) {
+        let index = *self.stack.last().expect("join called before root");
+        let num = rnum_to_u16(&rnum);
+        match self.open.remove(&num) {
+            Some((other, slot, other_kind)) => {
+                let kind = match bond_kind {
+                    BondKind::Elided => other_kind,
+                    kind => kind,
+                };
+                self.neighbors[other][slot] = Some(index);
+                self.neighbors[index].push(Some(other));
+                self.atoms[other].bonds.push(Bond {
+                    kind: kind.clone(),
+                    tid: index,
+                });
+                self.atoms[index].bonds.push(Bond { kind, tid: other });
+            }
+            None => {
+                let slot = self.neighbors[index].len();
+                self.neighbors[index].push(Some(index));
+                self.open.insert(num, (index, slot, bond_kind));
+            }
+        }
+    }
+
+    fn pop(&mut self, depth: usize) {
+        let len = self.stack.len().saturating_sub(depth);
+        self.stack.truncate(len);
+    }
+}
+
+/// Compute the canonical invariants of the atoms of several graphs.
+///
+/// Atoms are first labeled with their element, charge and number of
+/// hydrogens, and the labels are then refined with the labels of their
+/// neighbors until the number of distinct labels is stable. Bond orders
+/// are not used, so that the aromatic and Kekulé forms of a ring are
+/// equivalent. The stereocenters whose neighbors all have distinct labels
+/// are then labeled with their configuration, relative to the order of
+/// the labels of their neighbors.
+fn invariants(graphs: &[&Graph]) -> Vec<Vec<(usize, Option<bool>)>> {
+    // label the atoms of every graph together, so that labels can be compared
+    let atoms = graphs
+        .iter()
+        .flat_map(|graph| (0..graph.atoms.len()).map(move |index| (*graph, index)))
+        .collect::<Vec<_>>();
+    let offsets = graphs
+        .iter()
+        .scan(0, |offset, graph| {
+            let start = *offset;
+            *offset += graph.atoms.len();
+            Some(start)
+        })
+        .collect::<Vec<_>>();
+    let global = |graph: usize, index: usize| offsets[graph] + index;
+    let graph_of = |atom: usize| offsets.iter().rposition(|&offset| offset <= atom).unwrap();
+
+    let mut labels = {
+        let initial = atoms
+            .iter()
+            .map(|&(graph, index)| {
+                let atom = Atom::from_kind(&graph.atoms[index].kind);
+                let hydrogens = mass::hydrogens(&graph.atoms, index);
+                (atom.element, atom.charge, hydrogens)
+            })
+            .collect::<Vec<_>>();
+        rank(&initial)
+    };
+    let mut classes = 0;
+    while labels.iter().max().map_or(0, |&m| m + 1) > classes {
+        classes = labels.iter().max().map_or(0, |&m| m + 1);
+        let signatures = (0..atoms.len())
+            .map(|atom| {
+                let g = graph_of(atom);
+                let index = atom - offsets[g];
+                let mut neighbors = graphs[g].atoms[index]
+                    .bonds
+                    .iter()
+                    .map(|bond| labels[global(g, bond.tid)])
+                    .collect::<Vec<_>>();
+                neighbors.sort_unstable();
+                (labels[atom], neighbors)
+            })
+            .collect::<Vec<_>>();
+        labels = rank(&signatures);
+    }
+
+    graphs
+        .iter()
+        .enumerate()
+        .map(|(g, graph)| {
+            (0..graph.atoms.len())
+                .map(|index| {
+                    let chirality = Atom::from_kind(&graph.atoms[index].kind).chirality;
+                    let keys = graph.neighbors[index]
+                        .iter()
+                        .map(|n| n.map(|n| labels[global(g, n)]))
+                        .collect::<Vec<_>>();
+                    let mut sorted = keys.clone();
+                    sorted.sort_unstable();
+                    sorted.dedup();
+                    let stereo = match chirality {
+                        Some(chirality) if sorted.len() == keys.len() => {
+                            let clockwise = chirality == Chirality::Clockwise;
+                            Some(clockwise ^ odd_permutation(&keys))
+                        }
+                        _ => None,
+                    };
+                    (labels[global(g, index)], stereo)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Replace every value with its rank among the distinct values.
+pub fn rank<T: Ord + Clone>(values: &[T]) -> Vec<usize> {
+    let ranks = values
+        .iter()
+        .cloned()
+        .map(|value| (value, 0))
+        .collect::<BTreeMap<_, _>>()
+        .into_keys()
+        .enumerate()
+        .map(|(rank, value)| (value, rank))
+        .collect::<BTreeMap<_, _>>();
+    values.iter().map(|value| ranks[value]).collect()
+}
+
+/// Check whether sorting distinct values requires an odd permutation.
+pub fn odd_permutation<T: Ord>(values: &[T]) -> bool {
+    let mut inversions = 0;
+    for i in 0..values.len() {
+        for j in i + 1..values.len() {
+            if values[i] > values[j] {
+                inversions += 1;
+            }
+        }
+    }
+    inversions % 2 == 1
+}
+
+/// A search for an isomorphism between two molecular graphs.
+///
+/// Atoms are only matched with atoms of the other graph sharing the same
+/// invariants, in breadth-first order, so that every candidate can be
+/// checked against the neighbors already matched.
+struct Matcher<'g> {
+    graph: &'g Graph,
+    other: &'g Graph,
+    invariants: &'g [Vec<(usize, Option<bool>)>],
+    order: Vec<usize>,
+    mapping: Vec<Option<usize>>,
+    used: Vec<bool>,
+}
+
+impl<'g> Matcher<'g> {
+    fn new(
+        graph: &'g Graph,
+        other: &'g Graph,
+        invariants: &'g [Vec<(usize, Option<bool>)>],
+    ) -> Self {
+        let n = graph.atoms.len();
+        let mut order = Vec::with_capacity(n);
+        let mut seen = vec![false; n];
+        for start in 0..n {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut k = order.len();
+            order.push(start);
+            while k < order.len() {
+                for bond in graph.atoms[order[k]].bonds.iter() {
+                    if !seen[bond.tid] {
+                        seen[bond.tid] = true;
+                        order.push(bond.tid);
+                    }
+                }
+                k += 1;
+            }
+        }
+        Self {
+            graph,
+            other,
+            invariants,
+            order,
+            mapping: vec![None; n],
+            used: vec![false; other.atoms.len()],
+        }
+    }
+
+    /// Check whether `b` can be matched with `a`, given the current mapping.
+    fn is_feasible(&self, a: usize, b: usize) -> bool {
+        if self.used[b] || self.invariants[0][a] != self.invariants[1][b] {
+            return false;
+        }
+        self.graph.atoms[a].bonds.iter().all(|bond| {
+            self.mapping[bond.tid].iter().all(|&mapped| {
+                self.other.atoms[b]
+                    .bonds
+                    .iter()
+                    .any(|other| other.tid == mapped)
+            })
+        })
+    }
+
+    /// Check whether the stereocenter `a` has the configuration of its match.
+    fn same_configuration(&self, a: usize) -> bool {
+        let b = self.mapping[a].unwrap();
+        let chirality = Atom::from_kind(&self.graph.atoms[a].kind).chirality;
+        let other_chirality = Atom::from_kind(&self.other.atoms[b].kind).chirality;
+        match (chirality, other_chirality) {
+            (None, None) => true,
+            (Some(x), Some(y)) => {
+                let positions = self.graph.neighbors[a]
+                    .iter()
+                    .map(|n| n.map(|n| self.mapping[n].unwrap()))
+                    .map(|n| self.other.neighbors[b].iter().position(|&m| m == n))
+                    .collect::<Option<Vec<_>>>();
+                match positions {
+                    Some(positions) => (x == y) != odd_permutation(&positions),
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Extend the mapping from the atom at `depth` in the search order.
+    fn search(&mut self, depth: usize) -> bool {
+        let a = match self.order.get(depth) {
+            Some(&a) => a,
+            None => return (0..self.order.len()).all(|a| self.same_configuration(a)),
+        };
+        for b in 0..self.other.atoms.len() {
+            if self.is_feasible(a, b) {
+                self.mapping[a] = Some(b);
+                self.used[b] = true;
+                if self.search(depth + 1) {
+                    return true;
+                }
+                self.mapping[a] = None;
+                self.used[b] = false;
+            }
+        }
+        false
+    }
+}
+
+/// Check whether two SMILES strings describe the same structure.
+///
+/// The canonical invariants of the atoms, which take the connectivity, the
+/// hydrogens, the charges and the configuration of the stereocenters into
+/// account, are first compared to discard different structures early.
+/// The atoms of both structures are then matched to each other, so that
+/// structures with the same invariants but a different connectivity are
+/// still distinguished. The geometry of double bonds is not compared.
+pub fn same_structure(smiles: &str, reference: &str) -> Result<bool, String> {
+    let graph = Graph::read(smiles)?;
+    let other = Graph::read(reference)?;
+    let invariants = invariants(&[&graph, &other]);
+    let mut sorted = invariants.clone();
+    for atoms in sorted.iter_mut() {
+        atoms.sort_unstable();
+    }
+    if sorted[0] != sorted[1] {
+        return Ok(false);
+    }
+    Ok(Matcher::new(&graph, &other, &invariants).search(0))
+}
+
+/// Get the reference SMILES of a free amino acid.
+///
+/// The reference structures are written independently of the SMILES
+/// generated by the crate, and are used by `verify_residue`.
+///
+/// # Example
+/// ```rust
+/// use proteinogenic::AminoAcid;
+///
+/// let smiles = proteinogenic::reference_smiles(AminoAcid::Ala);
+/// assert_eq!(smiles, Some("C[C@@H](C(=O)O)N"));
+/// ```
+pub fn reference_smiles(aa: AminoAcid) -> Option<&'static str> {
+    REFERENCES
+        .lines()
+        .find_map(|line| match line.split_once('\t') {
+            Some((smiles, code)) if code.trim() == aa.as_code() => Some(smiles),
+            _ => None,
+        })
+}
+
+/// Check the SMILES generated for a free amino acid against its reference.
+///
+/// The SMILES of the free amino acid is parsed again and compared to the
+/// reference structure, regardless of the order in which atoms are written
+/// or of the aromaticity model. This can be used in downstream tests to
+/// check residues rendered through the crate. The text of the first error
+/// encountered, or of the mismatch, is returned on failure.
+///
+/// # Example
+/// ```rust
+/// use proteinogenic::AminoAcid;
+///
+/// for &aa in AminoAcid::ALL {
+///     assert_eq!(proteinogenic::verify_residue(aa), Ok(()));
+/// }
+/// ```
+pub fn verify_residue(aa: AminoAcid) -> Result<(), String> {
+    let reference = reference_smiles(aa)
+        .ok_or_else(|| format!("no reference structure for {}", aa.as_code()))?;
+    let smiles = aa.smiles().map_err(|e| e.to_string())?;
+    if same_structure(&smiles, reference)? {
+        Ok(())
+    } else {
+        Err(format!(
+            "structure of {} does not match its reference: {:?} != {:?}",
+            aa.as_code(),
+            smiles,
+            reference
+        ))
+    }
+}
@@ -0,0 +1,148 @@
+//! Residues that can be written in a peptide chain.
+
+use purr::walk::Follower;
+
+use super::AminoAcid;
+use super::Context;
+use super::Error;
+use super::Protein;
+
+/// The context in which a residue is visited.
+#[derive(Clone, Copy)]
+pub struct ResidueContext<'c> {
+    index: u16,
+    context: &'c Context<'c>,
+}
+
+impl<'c> ResidueContext<'c> {
+    /// Create a new context for the residue at the given index.
+    pub(crate) fn new(index: u16, context: &'c Context<'c>) -> Self {
+        Self { index, context }
+    }
+
+    /// Get the index of the residue in the protein, starting at 1.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Check whether stereochemistry should be written for the residue.
+    pub fn full_stereo(&self) -> bool {
+        self.context.full_stereo
+    }
+
+    /// Check whether the alpha carbon should carry the residue index as an
+    /// atom map.
+    pub fn residue_maps(&self) -> bool {
+        self.context.residue_maps
+    }
+
+    /// Check whether aromatic rings should be written in their kekulized
+    /// form, with explicit single and double bonds.
+    pub fn kekulize(&self) -> bool {
+        self.context.kekulize
+    }
+
+    /// Check whether the residue is involved in a cross-link, a side chain
+    /// cyclization, or carries a modification of its side chain.
+    pub(crate) fn is_modified(&self) -> bool {
+        let index = &self.index;
+        let context = self.context;
+        context.cross_links.contains_key(index)
+            || context.modifications.contains_key(index)
+            || context.gamma_glutamyls.contains(index)
+            || context.iso_aspartyls.contains(index)
+            || context.cis_prolines.contains(index)
+            || context.d_residues.contains(index)
+            || context.beta_residues.contains(index)
+            || context.geometries.contains_key(index)
+            || context.melan_configurations.contains_key(index)
+            || context
+                .cyclizations
+                .iter()
+                .any(|(_, cyclization)| cyclization.side_chain() == Some(self.index))
+    }
+}
+
+/// A residue that can be written in a peptide chain.
+///
+/// Implement this trait to render residues that are not covered by
+/// `AminoAcid`. Custom residues cannot be cross-linked, modified or
+/// cyclized through their side chain, since only the residue itself knows
+/// its structure.
+///
+/// # Example
+/// A custom residue for L-norvaline, wrapped with the standard residues:
+/// ```rust
+/// use proteinogenic::AminoAcid;
+/// use proteinogenic::Error;
+/// use proteinogenic::Protein;
+/// use proteinogenic::Residue;
+/// use proteinogenic::ResidueContext;
+/// use purr::feature::{Aliphatic, AtomKind, BondKind, BracketSymbol};
+/// use purr::feature::{Configuration, Element, VirtualHydrogen};
+/// use purr::walk::Follower;
+///
+/// enum MyResidue {
+///     Standard(AminoAcid),
+///     Nva,
+/// }
+///
+/// impl Residue for MyResidue {
+///     fn visit<F: Follower>(&self, follower: &mut F, context: &ResidueContext) -> Result<(), Error> {
+///         match self {
+///             MyResidue::Standard(aa) => aa.visit(follower, context),
+///             MyResidue::Nva => {
+///                 follower.extend(BondKind::Elided, AtomKind::Bracket {
+///                     symbol: BracketSymbol::Element(Element::C),
+///                     configuration: Some(Configuration::TH2),
+///                     hcount: Some(VirtualHydrogen::H1),
+///                     isotope: None,
+///                     charge: None,
+///                     map: None,
+///                 });
+///                 for _ in 0..3 {
+///                     follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+///                 }
+///                 follower.pop(3);
+///                 follower.extend(BondKind::Elided, AtomKind::Aliphatic(Aliphatic::C));
+///                 Ok(())
+///             }
+///         }
+///     }
+///
+///     fn amino_acid(&self) -> Option<AminoAcid> {
+///         match self {
+///             MyResidue::Standard(aa) => Some(*aa),
+///             MyResidue::Nva => None,
+///         }
+///     }
+/// }
+///
+/// let prot = Protein::new([MyResidue::Standard(AminoAcid::Gly), MyResidue::Nva]);
+/// assert_eq!(prot.smiles().unwrap(), "NCC(=O)N[C@@H](CCC)C(=O)-O");
+/// ```
+pub trait Residue {
+    /// Visit the atoms of the residue using the given follower.
+    ///
+    /// The follower is given positioned on the nitrogen of the backbone,
+    /// and must be left positioned on the carbon of the backbone carbonyl,
+    /// whose oxygen and next residue are added by the protein.
+    fn visit<F: Follower>(&self, follower: &mut F, context: &ResidueContext) -> Result<(), Error>;
+
+    /// Get the standard amino acid this residue stands for, if any.
+    ///
+    /// Custom residues should return `None`, which is the default.
+    fn amino_acid(&self) -> Option<AminoAcid> {
+        None
+    }
+}
+
+impl Residue for AminoAcid {
+    fn visit<F: Follower>(&self, follower: &mut F, context: &ResidueContext) -> Result<(), Error> {
+        Protein::<Vec<AminoAcid>>::visit_residue(*self, follower, context.index, context.context)
+    }
+
+    fn amino_acid(&self) -> Option<AminoAcid> {
+        Some(*self)
+    }
+}
@@ -0,0 +1,84 @@
+//! A SMILES writer building its output in a single string.
+
+use std::fmt::Write;
+
+use purr::feature::AtomKind;
+use purr::feature::BondKind;
+use purr::feature::Rnum;
+use purr::walk::Follower;
+
+/// The average length of the SMILES string of a residue, in bytes.
+const RESIDUE_LENGTH: usize = 20;
+
+/// A `Follower` writing the same SMILES string as `purr::write::Writer`.
+///
+/// The `purr` writer allocates a string for every atom, and concatenates
+/// them again whenever a branch is closed. This writer only records the
+/// offset of each atom in the output string, so that it can grow in place
+/// and be pre-allocated for a known number of residues.
+#[derive(Debug, Default)]
+pub struct Writer {
+    smiles: String,
+    offsets: Vec<usize>,
+}
+
+impl Writer {
+    /// Create a new writer with an empty output.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new writer with enough capacity for a protein of `residues`.
+    pub fn with_residues(residues: usize) -> Self {
+        Self {
+            smiles: String::with_capacity(residues * RESIDUE_LENGTH),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Get the SMILES string written so far.
+    pub fn write(self) -> String {
+        self.smiles
+    }
+
+    /// Get a copy of the SMILES string written so far, and clear the writer.
+    ///
+    /// The buffers of the writer are kept, so that the writer can be reused
+    /// without allocating them again.
+    pub fn flush(&mut self) -> String {
+        let smiles = self.smiles.clone();
+        self.smiles.clear();
+        self.offsets.clear();
+        smiles
+    }
+}
+
+impl Follower for Writer {
+    fn root(&mut self, root: AtomKind) {
+        self.offsets.push(self.smiles.len());
+        if !self.smiles.is_empty() {
+            self.smiles.push('.');
+        }
+        write!(self.smiles, "{}", root).unwrap();
+    }
+
+    fn extend(&mut self, bond_kind: BondKind, atom_kind: AtomKind) {
+        self.offsets.push(self.smiles.len());
+        write!(self.smiles, "{}{}", bond_kind, atom_kind).unwrap();
+    }
+
+    fn join(&mut self, bond_kind: BondKind, rnum: Rnum) {
+        write!(self.smiles, "{}{}", bond_kind, rnum).unwrap();
+    }
+
+    fn pop(&mut self, depth: usize) {
+        if depth >= self.offsets.len() {
+            panic!("overpop")
+        }
+        // the popped atoms are the end of the string, wrapped in a branch
+        let len = self.offsets.len() - depth;
+        self.smiles.insert(self.offsets[len], '(');
+        self.smiles.push(')');
+        self.offsets.truncate(len);
+    }
+}
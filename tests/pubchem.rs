@@ -103,6 +103,11 @@ test_peptide!(test_tryptophan, 6305, [Trp]);
 test_peptide!(test_pyrrolysine, 5460671, [Pyl]);
 test_peptide!(test_dehydroalanine, 123991, [Dha]);
 test_peptide!(test_dehydrobutyrine, 6449989, [Dhb]);
+test_peptide!(test_hydroxytryptophan, 439280, [Htp]);
+test_peptide!(test_beta_alanine, 239, [BAla]);
+test_peptide!(test_gaba, 119, [Gaba]);
+test_peptide!(test_homoarginine, 9085, [Har]);
+test_peptide!(test_ornithine, 6262, [Orn]);
 
 // dipeptides
 test_peptide!(test_alanylvaline, 96799, [Ala, Val]);